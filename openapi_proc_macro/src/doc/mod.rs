@@ -0,0 +1,95 @@
+use syn::Attribute;
+use syn::Lit;
+use syn::Meta;
+
+/// The `///` doc comment of an item, with the leading space of every line removed. `None` when
+/// there is no doc comment or it is blank.
+pub fn doc_comment(attrs: &[Attribute]) -> Option<String> {
+    let lines: Vec<String> = attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("doc"))
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(Meta::NameValue(nv)) => match nv.lit {
+                Lit::Str(s) => Some(s.value()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .map(|line| line.strip_prefix(' ').map(str::to_owned).unwrap_or(line))
+        .collect();
+
+    non_empty(lines.join("\n"))
+}
+
+/// Split a doc comment into a summary, its first paragraph joined into one line, and a
+/// description, the remaining paragraphs.
+pub fn summary_and_description(doc: &str) -> (Option<String>, Option<String>) {
+    let doc = doc.trim();
+    let (summary, description) = match doc.find("\n\n") {
+        Some(index) => (&doc[..index], &doc[index..]),
+        None => (doc, ""),
+    };
+    let summary = summary.lines().map(str::trim).collect::<Vec<_>>().join(" ");
+
+    (non_empty(summary), non_empty(description.trim().to_owned()))
+}
+
+/// `Some(value)` unless `value` is blank.
+pub fn non_empty(value: String) -> Option<String> {
+    if value.trim().is_empty() {
+        None
+    } else {
+        Some(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::summary_and_description as split;
+    use super::*;
+
+    use syn::parse_quote;
+
+    #[test]
+    fn a_single_line_is_only_a_summary() {
+        assert_eq!(split("List the users."), (Some("List the users.".to_owned()), None));
+    }
+
+    #[test]
+    fn the_first_paragraph_is_the_summary() {
+        let doc = "List the users\nof the account.\n\nPaginated by `page`.\n\nAdmins only.";
+        assert_eq!(
+            split(doc),
+            (
+                Some("List the users of the account.".to_owned()),
+                Some("Paginated by `page`.\n\nAdmins only.".to_owned())
+            )
+        );
+    }
+
+    #[test]
+    fn an_empty_doc_has_neither() {
+        assert_eq!(split(""), (None, None));
+        assert_eq!(split(" \n\n "), (None, None));
+    }
+
+    #[test]
+    fn doc_comments_lose_their_leading_space() {
+        let item: syn::ItemFn = parse_quote! {
+            /// List the users.
+            ///
+            ///  Indented.
+            fn list_users() {}
+        };
+        assert_eq!(
+            doc_comment(&item.attrs).as_deref(),
+            Some("List the users.\n\n Indented.")
+        );
+
+        let undocumented: syn::ItemFn = parse_quote! {
+            #[inline]
+            fn list_users() {}
+        };
+        assert_eq!(doc_comment(&undocumented.attrs), None);
+    }
+}
@@ -1,30 +1,48 @@
 #[macro_use]
 extern crate quote;
 
+mod doc;
 mod openapi_attr;
+mod param_derive;
 
 use proc_macro::TokenStream;
+use syn::parse_macro_input;
+use syn::DeriveInput;
 
-#[proc_macro_attribute]
-pub fn query(
-    args: TokenStream,
-    mut input: TokenStream,
-) -> TokenStream {
-    input.extend(
-        openapi_attr::parse_query(args.into(), input.clone().into()),
-    );
-
-    input
+/// Derive `openapi_rs::request::QueryParams` for a struct used with `axum::extract::Query`.
+///
+/// Every property of the struct's `JsonSchema` becomes one `in: query` parameter. Fields accept
+/// `#[param(...)]` metadata and `validator` constraints from `#[validate(...)]`.
+#[proc_macro_derive(OpenApiQuery, attributes(param, validate))]
+pub fn derive_query(input: TokenStream) -> TokenStream {
+    derive_params(input, param_derive::ParamKind::Query)
+}
+
+/// Derive `openapi_rs::request::PathParams` for a struct used with `axum::extract::Path`.
+#[proc_macro_derive(OpenApiPath, attributes(param, validate))]
+pub fn derive_path(input: TokenStream) -> TokenStream {
+    derive_params(input, param_derive::ParamKind::Path)
+}
+
+/// Derive `openapi_rs::request::HeaderParams` for a struct describing request headers.
+#[proc_macro_derive(OpenApiHeaders, attributes(param, validate))]
+pub fn derive_headers(input: TokenStream) -> TokenStream {
+    derive_params(input, param_derive::ParamKind::Header)
+}
+
+fn derive_params(input: TokenStream, kind: param_derive::ParamKind) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    param_derive::derive_params(input, kind)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
 }
 
 #[proc_macro_attribute]
-pub fn handler(
-    args: TokenStream,
-    mut input: TokenStream,
-) -> TokenStream {
-    input.extend(
-        openapi_attr::parse_handler(args.into(), input.clone().into()),
-    );
-
-    input
-}
\ No newline at end of file
+pub fn handler(args: TokenStream, input: TokenStream) -> TokenStream {
+    let mut output: proc_macro2::TokenStream = input.clone().into();
+
+    output.extend(openapi_attr::parse_handler(args.into(), input.into()));
+
+    output.into()
+}
@@ -0,0 +1,115 @@
+use proc_macro2::Delimiter;
+use proc_macro2::TokenStream;
+use proc_macro2::TokenTree;
+use syn::parse::Parse;
+use syn::parse::ParseStream;
+use syn::Error;
+use syn::Expr;
+use syn::Ident;
+use syn::LitStr;
+use syn::Token;
+
+/// `extension(key = "x-owner", value = json!({ "team": "payments" }))`: a specification
+/// extension of the operation. `value` is any expression convertible to a `serde_json::Value`.
+pub struct ExtensionAttribute {
+    pub key: LitStr,
+
+    pub value: Expr,
+}
+
+impl Parse for ExtensionAttribute {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut key: Option<LitStr> = None;
+        let mut value: Option<Expr> = None;
+
+        while !input.is_empty() {
+            let name: Ident = input.parse()?;
+            input.parse::<Token![=]>()?;
+            if name == "key" {
+                key = Some(input.parse()?);
+            } else if name == "value" {
+                value = Some(input.parse()?);
+            } else {
+                return Err(Error::new(name.span(), "expected `key` or `value`"));
+            }
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+
+        let key = key.ok_or_else(|| input.error("missing `key` in `extension(...)`"))?;
+        let value = value.ok_or_else(|| input.error("missing `value` in `extension(...)`"))?;
+        if !key.value().starts_with("x-") {
+            return Err(Error::new(key.span(), "extension keys must start with `x-`"));
+        }
+
+        Ok(ExtensionAttribute { key, value })
+    }
+}
+
+/// Take the `extension(...)` items out of the handler attribute arguments. Their `value` is an
+/// arbitrary expression, which the `AttributeArgs` parsed by darling cannot hold.
+pub fn extract_extensions(args: TokenStream) -> syn::Result<(TokenStream, Vec<ExtensionAttribute>)> {
+    let mut rest = TokenStream::new();
+    let mut extensions = vec![];
+    let mut tokens = args.into_iter().peekable();
+
+    while let Some(token) = tokens.next() {
+        let group = match (&token, tokens.peek()) {
+            (TokenTree::Ident(ident), Some(TokenTree::Group(group)))
+                if ident == "extension" && group.delimiter() == Delimiter::Parenthesis =>
+            {
+                group.stream()
+            }
+            _ => {
+                rest.extend([token]);
+                continue;
+            }
+        };
+        tokens.next();
+        extensions.push(syn::parse2(group)?);
+        if matches!(tokens.peek(), Some(TokenTree::Punct(p)) if p.as_char() == ',') {
+            tokens.next();
+        }
+    }
+
+    Ok((rest, extensions))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extract(args: TokenStream) -> syn::Result<(String, Vec<String>)> {
+        let (rest, extensions) = extract_extensions(args)?;
+        let keys = extensions.iter().map(|e| e.key.value()).collect();
+        Ok((rest.to_string(), keys))
+    }
+
+    #[test]
+    fn extensions_are_taken_out_of_the_arguments() {
+        let args = quote! {
+            method = "GET",
+            extension(key = "x-owner", value = json!({ "team": "payments" })),
+            tag = "orders",
+            extension(value = 3, key = "x-rank")
+        };
+        let (rest, keys) = extract(args).unwrap();
+        assert_eq!(rest, quote!(method = "GET", tag = "orders",).to_string());
+        assert_eq!(keys, ["x-owner", "x-rank"]);
+    }
+
+    #[test]
+    fn extension_keys_must_start_with_x() {
+        let error = extract(quote!(extension(key = "owner", value = 1))).unwrap_err();
+        assert_eq!(error.to_string(), "extension keys must start with `x-`");
+    }
+
+    #[test]
+    fn extensions_need_a_key_and_a_value() {
+        let missing_value = extract(quote!(extension(key = "x-owner"))).unwrap_err();
+        assert!(missing_value.to_string().ends_with("missing `value` in `extension(...)`"));
+        let unknown = extract(quote!(extension(key = "x-owner", name = 1))).unwrap_err();
+        assert_eq!(unknown.to_string(), "expected `key` or `value`");
+    }
+}
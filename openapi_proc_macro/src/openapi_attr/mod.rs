@@ -1,373 +1,646 @@
-extern crate proc_macro;
 extern crate proc_macro2;
 extern crate syn;
 
+mod extension;
+
+use crate::doc;
 use darling::FromMeta;
 use proc_macro2::Span;
-// use proc_macro2::TokenStream;
-use proc_macro::TokenStream;
+use proc_macro2::TokenStream;
+use syn::parse::Parser;
+use syn::parse2;
+use syn::punctuated::Punctuated;
 use syn::AttributeArgs;
-use std::collections::HashMap;
-use std::ops::Fn;
-use syn::parse;
-use syn::parse_macro_input;
-use syn::Data;
-use syn::DeriveInput;
 use syn::FnArg;
 use syn::GenericArgument;
 use syn::Ident;
 use syn::ItemFn;
+use syn::NestedMeta;
 use syn::PathArguments;
+use syn::ReturnType;
+use syn::Token;
 use syn::Type;
+
 #[derive(Debug, Default, FromMeta)]
 #[darling(default)]
 struct OpenApiAttribute {
     pub method: String,
 
-    pub summary: String,
+    pub summary: Option<String>,
 
-    pub description: String,
+    pub description: Option<String>,
+
+    pub operation_id: Option<String>,
 
     #[darling(multiple, rename = "tag")]
     pub tags: Vec<String>,
 
     #[darling(multiple, rename = "headers")]
-    pub headers: Vec<String>,
-}
+    pub headers: Vec<Type>,
 
-pub fn parse_query(_: TokenStream, input: TokenStream) -> TokenStream {
-    let ast: DeriveInput = parse(input.into()).expect("failed to parse input");
+    #[darling(multiple, rename = "security")]
+    pub security_types: Vec<Type>,
 
-    let name = &ast.ident;
-    let data = &ast.data;
+    #[darling(multiple, rename = "cookie")]
+    pub cookies: Vec<CookieAttribute>,
 
-    let mut fields = vec![];
+    #[darling(multiple)]
+    pub set_cookie: Vec<String>,
 
-    match data {
-        Data::Struct(s) => {
-            for field in &s.fields {
-                if let Some(ident) = &field.ident {
-                    fields.push(ident);
-                }
-            }
-        }
-        _ => {}
+    #[darling(multiple, rename = "response")]
+    pub responses: Vec<ResponseAttribute>,
+
+    pub deprecated: bool,
+
+    pub deprecated_since: Option<String>,
+
+    pub sunset: Option<String>,
+
+    pub replaced_by: Option<String>,
+
+    #[darling(multiple, rename = "extensions")]
+    pub extension_types: Vec<Type>,
+
+    #[darling(multiple, rename = "callback")]
+    pub callbacks: Vec<CallbackAttribute>,
+
+    #[darling(multiple, rename = "link")]
+    pub links: Vec<LinkAttribute>,
+
+    pub request_examples: bool,
+
+    pub response_examples: Option<ResponseExamplesAttribute>,
+}
+
+/// `response_examples` or `response_examples(status = 201)`: sets the examples of the `Json<T>`
+/// return type on a response. `status` defaults to the first successful response.
+#[derive(Debug, Default)]
+struct ResponseExamplesAttribute {
+    pub status: Option<u16>,
+}
+
+impl FromMeta for ResponseExamplesAttribute {
+    fn from_word() -> darling::Result<Self> {
+        Ok(Self::default())
     }
 
-    quote! {
-        impl #name {
-            pub fn query( gen: &mut OpenApiGenerator) -> Vec<RefOr<Parameter>> {
-                use ::openapi_rs::parameter_from_schema;
-                use ::okapi::openapi3::{Object, Parameter, ParameterValue};
-                use ::schemars::schema::{InstanceType, Schema, SchemaObject, SingleOrVec};
-                use ::schemars::JsonSchema;
-
-                let schema = gen.json_schema_no_ref::<#name>();
-    // Get a list of properties from the structure.
-    let mut properties: schemars::Map<String, Schema> = schemars::Map::new();
-    // Create all the `Parameter` for every property
-    let mut parameter_list: Vec<RefOr<Parameter>> = Vec::new();
-    match &schema.instance_type {
-        Some(SingleOrVec::Single(instance_type)) => {
-            if **instance_type == InstanceType::Object {
-                if let Some(object) = schema.object {
-                    properties = object.properties;
-                }
-                for (key, property) in properties {
-                    let prop_schema = match property {
-                        Schema::Object(x) => x,
-                        _ => SchemaObject::default(),
-                    };
-                    parameter_list.push(RefOr::Object(parameter_from_schema(prop_schema, key, true)));
-                }
-            } else {
-                #(parameter_list.push(RefOr::Object(parameter_from_schema(schema.clone(), stringify!(#fields).to_string(), true))));*
-                
-            }
-        }
-        None => {
-            // Used when `SchemaObject.reference` is set.
-            // https://github.com/GREsau/schemars/issues/105
-            #(parameter_list.push(RefOr::Object(parameter_from_schema(schema.clone(), stringify!(#fields).to_string(), true))));*
-        }
-        _ => {
-            // TODO: Do nothing for now, might need implementation later.
-            // log::warn!(
-            //     "Please let `okapi` devs know how you triggered this type: `{:?}`.",
-            //     schema.instance_type
-            // );
-            #(parameter_list.push(RefOr::Object(parameter_from_schema(schema.clone(), stringify!(#fields).to_string(), true))));*
+    fn from_list(items: &[NestedMeta]) -> darling::Result<Self> {
+        #[derive(FromMeta)]
+        struct Status {
+            status: u16,
         }
+
+        Status::from_list(items).map(|s| ResponseExamplesAttribute {
+            status: Some(s.status),
+        })
+    }
+}
+
+/// `cookie(name = "session", description = "...", required)`: a cookie read by the handler.
+#[derive(Debug, Default, FromMeta)]
+#[darling(default)]
+struct CookieAttribute {
+    pub name: String,
+
+    pub description: Option<String>,
+
+    pub required: bool,
+}
+
+/// `response(status = 404, description = "...")`: overrides the description of a response.
+#[derive(Debug, Default, FromMeta)]
+#[darling(default)]
+struct ResponseAttribute {
+    pub status: u16,
+
+    pub description: String,
+}
+
+/// `callback(name = "onEvent", expression = "{$request.body#/callbackUrl}", payload = "Event")`:
+/// a request sent by the API to a URL given by the client. `method` defaults to `POST`.
+#[derive(Debug, FromMeta)]
+struct CallbackAttribute {
+    pub name: String,
+
+    pub expression: String,
+
+    pub payload: Type,
+
+    #[darling(default = "default_callback_method")]
+    pub method: String,
+
+    #[darling(default)]
+    pub description: Option<String>,
+}
+
+/// `link(name = "GetOrder", handler = "get_order", parameter(name = "id", expression = "..."))`:
+/// a link from a response to the operation of another handler. `status` defaults to the first
+/// successful response.
+#[derive(Debug, Default, FromMeta)]
+#[darling(default)]
+struct LinkAttribute {
+    pub name: String,
+
+    pub handler: String,
+
+    pub status: Option<u16>,
+
+    pub description: Option<String>,
+
+    #[darling(multiple, rename = "parameter")]
+    pub parameters: Vec<LinkParameterAttribute>,
+}
+
+/// `parameter(name = "id", expression = "$response.body#/id")` of a link.
+#[derive(Debug, Default, FromMeta)]
+#[darling(default)]
+struct LinkParameterAttribute {
+    pub name: String,
+
+    pub expression: String,
+}
+
+fn default_callback_method() -> String {
+    "POST".to_owned()
+}
+
+/// Strip references and parentheses so the path of the type can be inspected.
+fn peel(ty: &Type) -> &Type {
+    match ty {
+        Type::Reference(r) => peel(&r.elem),
+        Type::Paren(p) => peel(&p.elem),
+        Type::Group(g) => peel(&g.elem),
+        _ => ty,
     }
-    parameter_list
+}
+
+fn first_generic_type(arguments: &PathArguments) -> Option<&Type> {
+    if let PathArguments::AngleBracketed(angle_ga) = arguments {
+        for ga in &angle_ga.args {
+            if let GenericArgument::Type(ty) = ga {
+                return Some(ty);
             }
         }
-
     }
-    .into()
+
+    None
 }
 
-fn extract_generic_ident(arguments: &PathArguments) -> Vec<Ident> {
-    let mut idents = vec![];
-
-    if let PathArguments::AngleBracketed(angle_ga) = &arguments {
-        if let Some(ga) = angle_ga.args.first() {
-            
-            if let GenericArgument::Type(Type::Path(ty)) = ga {
-                
-               let generic_segments = &ty.path.segments;
-
-                for generic_segment in generic_segments {
-                    idents.push(generic_segment.ident.clone());
-                    if !generic_segment.arguments.is_empty() {
-                        for ident in extract_generic_ident(&generic_segment.arguments) {
-                            idents.push(ident);
+/// Find the first path segment named `wrapper` inside `ty` (searching through generic
+/// arguments, e.g. `Result<Json<T>, E>`) and return its first generic type argument.
+fn find_wrapped<'a>(ty: &'a Type, wrapper: &str) -> Option<&'a Type> {
+    if let Type::Path(p) = peel(ty) {
+        for segment in &p.path.segments {
+            if segment.ident == wrapper {
+                return first_generic_type(&segment.arguments);
+            }
+
+            if let PathArguments::AngleBracketed(angle_ga) = &segment.arguments {
+                for ga in &angle_ga.args {
+                    if let GenericArgument::Type(inner) = ga {
+                        if let Some(found) = find_wrapped(inner, wrapper) {
+                            return Some(found);
                         }
                     }
                 }
-
             }
-
         }
     }
 
-   idents
+    None
+}
+
+/// Ident of the last segment of the type path, e.g. `Option` for `std::option::Option<T>`.
+fn last_ident(ty: &Type) -> Option<&Ident> {
+    match peel(ty) {
+        Type::Path(p) => p.path.segments.last().map(|s| &s.ident),
+        _ => None,
+    }
 }
 
-fn filter_type<P: Fn(&Ident) -> bool>(ty: Box<Type>, cb: P) -> Vec<Ident> {
-    let mut idents = vec![];
+/// Statements that document one argument of the handler.
+fn argument_tokens(ty: &Type) -> TokenStream {
+    let is_option = last_ident(ty).is_some_and(|i| i == "Option");
 
-    match &*ty {
-        Type::Path(p) => {
-            let segments = &p.path.segments;
+    if let Some(query) = find_wrapped(ty, "Query") {
+        return quote! {
+            parameters.extend(
+                <#query as ::openapi_rs::request::QueryParams>::query_parameters(gen)
+                    .map_err(|e| ::openapi_rs::error::OpenApiError::schema(HANDLER, e))?,
+            );
+        };
+    }
 
-            for segment in segments {
-                let ident = &segment.ident;
+    if let Some(path) = find_wrapped(ty, "Path") {
+        return quote! {
+            parameters.extend(
+                <#path as ::openapi_rs::request::PathParams>::path_parameters(gen, path)
+                    .map_err(|e| ::openapi_rs::error::OpenApiError::schema(HANDLER, e))?,
+            );
+        };
+    }
 
-                let generic_idents = extract_generic_ident(&segment.arguments);
+    if let Some(header) = find_wrapped(ty, "TypedHeader") {
+        let required = !is_option;
+        return quote! {
+            let header_name = <#header as ::openapi_rs::__private::axum::headers::Header>::name().as_str();
+            let parameter = ::openapi_rs::ParameterBuilder::new(
+                header_name,
+                ::openapi_rs::ParameterLocation::Header,
+                gen.json_schema::<::std::string::String>(),
+            )
+            .required(#required)
+            .build();
+            parameters.push(::openapi_rs::okapi::openapi3::RefOr::Object(parameter));
+        };
+    }
 
-                if cb(ident) {
-                    idents.push(ident.clone());
-                    for ident in generic_idents {
-                            idents.push(ident);
-                    }
-                }
-            }
+    if let Some(body) = find_wrapped(ty, "Json") {
+        let body_ty = if is_option {
+            quote!(::std::option::Option<::openapi_rs::__private::axum::Json<#body>>)
+        } else {
+            quote!(::openapi_rs::__private::axum::Json<#body>)
+        };
+
+        return quote! {
+            request_body = Some(::openapi_rs::okapi::openapi3::RefOr::Object(
+                <#body_ty as ::openapi_rs::OpenApiFromData>::request_body(gen)
+                    .map_err(|e| ::openapi_rs::error::OpenApiError::schema(HANDLER, e))?,
+            ));
+        };
+    }
+
+    TokenStream::new()
+}
+
+/// Statements adding the security scheme of an extractor declared with `security = "Type"`.
+fn security_tokens(ty: &Type) -> TokenStream {
+    quote! {
+        if let ::openapi_rs::request::RequestHeaderInput::Security(security_name, security_scheme, security_requirement) =
+            <#ty as ::openapi_rs::request::OpenApiFromRequest<::std::string::String>>::from_request_input(gen, ::std::string::String::new(), true)
+                .map_err(|e| ::openapi_rs::error::OpenApiError::schema(HANDLER, e))?
+        {
+            gen.add_security_scheme(security_name, security_scheme);
+            security.get_or_insert_with(::std::vec::Vec::new).push(security_requirement);
         }
-        _ => {}
     }
+}
+
+/// Statements producing the `responses` of the operation from the handler return type, through
+/// its `OpenApiResponderInner` implementation so that `Result<T, E>` merges both sides. `impl
+/// Trait` return types document no response.
+fn responses_tokens(output: &ReturnType) -> TokenStream {
+    match output {
+        ReturnType::Type(_, ty) if !matches!(peel(ty), Type::ImplTrait(_)) => quote! {
+            let mut responses = <#ty as ::openapi_rs::response::OpenApiResponderInner>::responses(gen)
+                .map_err(|e| ::openapi_rs::error::OpenApiError::schema(HANDLER, e))?;
+        },
+        _ => quote! {
+            let mut responses = ::openapi_rs::okapi::openapi3::Responses::default();
+        },
+    }
+}
+
+/// Check that a `YYYY-MM-DD` date exists in the calendar.
+fn is_date(value: &str) -> bool {
+    let bytes = value.as_bytes();
+    let shape = bytes.len() == 10
+        && bytes.iter().enumerate().all(|(i, b)| match i {
+            4 | 7 => *b == b'-',
+            _ => b.is_ascii_digit(),
+        });
+    if !shape {
+        return false;
+    }
+    let number = |range: std::ops::Range<usize>| value[range].parse::<u32>().unwrap_or_default();
+    let (year, month, day) = (number(0..4), number(5..7), number(8..10));
+    let leap = (year % 4 == 0 && year % 100 != 0) || year % 400 == 0;
+    let days_in_month = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap => 29,
+        2 => 28,
+        _ => return false,
+    };
+    (1..=days_in_month).contains(&day)
+}
+
+fn opt<T: quote::ToTokens>(value: Option<T>) -> TokenStream {
+    match value {
+        Some(v) => quote!(::std::option::Option::Some(#v)),
+        None => quote!(::std::option::Option::None),
+    }
+}
 
-    idents
+fn opt_string(value: Option<String>) -> TokenStream {
+    match value {
+        Some(v) => quote!(::std::option::Option::Some(::std::string::String::from(#v))),
+        None => quote!(::std::option::Option::None),
+    }
 }
 
 pub fn parse_handler(args: TokenStream, input: TokenStream) -> TokenStream {
-    // let args1: proc_macro::TokenStream  = args.into();
-    let attr_args = parse_macro_input!(args as AttributeArgs);
-    // let attr_args = parse_macro_input!(args as AttributeArgs);
-    // let input = parse_macro_input!(input as ItemFn);
-    let input_fn: ItemFn = parse(input.into()).expect("failed to parse input");
+    let input_fn: ItemFn = match parse2(input) {
+        Ok(f) => f,
+        Err(e) => return e.into_compile_error(),
+    };
 
-    let okapi_attr = match OpenApiAttribute::from_list(&attr_args) {
+    let (args, extensions) = match extension::extract_extensions(args) {
         Ok(v) => v,
-        Err(e) => {
-            return e.write_errors().into();
-        }
+        Err(e) => return e.into_compile_error(),
+    };
+    let args: AttributeArgs = match Punctuated::<NestedMeta, Token![,]>::parse_terminated.parse2(args) {
+        Ok(args) => args.into_iter().collect(),
+        Err(e) => return e.into_compile_error(),
     };
 
-    let mut tokens_map = HashMap::new();
-
-    let fn_args = &input_fn.sig.inputs;
-
-    if fn_args.len() > 0 {
-        for fn_arg in fn_args {
-            if let FnArg::Typed(t) = fn_arg {
-                let query_idents = filter_type(t.ty.clone(), |i| i == "Query");
-                let request_idents = filter_type(t.ty.clone(), |i| i.to_string().contains("Result") || i == "Json");
-                let header_idents = filter_type(t.ty.clone(), |i| i == "TypedHeader");
-                let auth_idents = filter_type(t.ty.clone(),|i| i.to_string().contains("Auth"));
-    
-                if let Some(query_name) = query_idents.last() {
-                    let q = quote! {
-                        let mut parameters = #query_name::query(gen);
-                    };
-                    tokens_map.insert("query",q);
-                } else {
-                    if !tokens_map.contains_key("query") {
-                        let q = quote! {
-                            let mut parameters: Vec<RefOr<Parameter>> = vec![];
-                        };
-                        tokens_map.insert("query",q);
-                    }
-                }
-    
-                let requests = request_idents.iter().any(|i| i.to_string().contains("Result"));
-    
-                let mut ident = request_idents.last();
+    let okapi_attr = match OpenApiAttribute::from_list(&args) {
+        Ok(v) => v,
+        Err(e) => return e.write_errors(),
+    };
 
-                if requests {
-                    ident = request_idents.get(2);
-                }
-    
-                if let Some(request_name) = ident {
-                    let b = quote! {
-                        let requests = Some(RefOr::Object(Json::<#request_name>::request_body(gen).expect("failed to generate response schema")));
-                    };
-                    tokens_map.insert("request",b);
-                } else {
-                    if !tokens_map.contains_key("request") {
-                    let b = quote! {
-                        let requests: Option<RefOr<RequestBody>> = None;
-                    };
-                    tokens_map.insert("request",b);
-                    }
-                }
-    
-                if let Some(auth) = auth_idents.last() {
-                    let a = quote! {
-                        let scheme_type = <#auth as OpenApiFromRequest::<String>>::from_request_input(gen,String::new(),true);
-                        let mut requirement = None;
-                        let mut scheme = None;
-                        let mut name = None;
-    
-                        if let Ok(RequestHeaderInput::Security(security_name,security_scheme,security_requirement)) = scheme_type {
-                            name = Some(security_name);
-                            requirement = Some(vec![security_requirement]);
-                            scheme = Some(security_scheme); 
-                        }
-                    };
-    
-                    tokens_map.insert("auth",a);
-                } else {
-    
-                    if !tokens_map.contains_key("auth") {
-                        let a = quote! {
-                            let requirement: Option<Vec<SecurityRequirement>> = None;
-                            let scheme: Option<SecurityScheme> = None;
-                            let name: Option<String> = None;
-                        };    
-    
-                        tokens_map.insert("auth",a);
-                    }
-    
+    let arguments = input_fn.sig.inputs.iter().filter_map(|fn_arg| match fn_arg {
+        FnArg::Typed(t) => Some(argument_tokens(&t.ty)),
+        FnArg::Receiver(_) => None,
+    });
+
+    let security_types = okapi_attr.security_types.iter().map(security_tokens);
+
+    let headers = okapi_attr.headers.iter().map(|header| {
+        quote! {
+            parameters.extend(
+                <#header as ::openapi_rs::request::HeaderParams>::header_parameters(gen)
+                    .map_err(|e| ::openapi_rs::error::OpenApiError::schema(HANDLER, e))?,
+            );
+        }
+    });
+
+    let cookies = okapi_attr.cookies.iter().map(|cookie| {
+        let name = &cookie.name;
+        let required = cookie.required;
+        let description = cookie.description.as_ref().map(|d| quote!(.description(#d)));
+        quote! {
+            let parameter = ::openapi_rs::ParameterBuilder::new(
+                #name,
+                ::openapi_rs::ParameterLocation::Cookie,
+                gen.json_schema::<::std::string::String>(),
+            )
+            .required(#required)
+            #description
+            .build();
+            parameters.push(::openapi_rs::okapi::openapi3::RefOr::Object(parameter));
+        }
+    });
+
+    let responses = responses_tokens(&input_fn.sig.output);
+
+    let set_cookies = okapi_attr.set_cookie.iter().map(|cookie| {
+        quote! {
+            ::openapi_rs::utils::add_set_cookie_header(&mut responses, #cookie)
+                .map_err(|e| ::openapi_rs::error::OpenApiError::schema(HANDLER, e))?;
+        }
+    });
+
+    let response_descriptions = okapi_attr.responses.iter().map(|response| {
+        let status = response.status;
+        let description = &response.description;
+        quote! {
+            ::openapi_rs::utils::set_response_description(&mut responses, #status, #description)
+                .map_err(|e| ::openapi_rs::error::OpenApiError::schema(HANDLER, e))?;
+        }
+    });
+
+    let callbacks = okapi_attr.callbacks.iter().map(|callback| {
+        let name = &callback.name;
+        let expression = &callback.expression;
+        let payload = &callback.payload;
+        let method = &callback.method;
+        let description = opt_string(callback.description.clone());
+        quote! {
+            ::openapi_rs::callback::add_callback::<#payload>(
+                gen,
+                &mut callbacks,
+                #name,
+                #expression,
+                ::openapi_rs::OpenApiMethod::from(#method),
+                #description,
+            )
+            .map_err(|e| ::openapi_rs::error::OpenApiError::schema(HANDLER, e))?;
+        }
+    });
+
+    let links = okapi_attr.links.iter().map(|link| {
+        let name = &link.name;
+        let handler = &link.handler;
+        let status = opt(link.status);
+        let description = opt_string(link.description.clone());
+        let parameter_names = link.parameters.iter().map(|p| &p.name);
+        let parameter_expressions = link.parameters.iter().map(|p| &p.expression);
+        quote! {
+            ::openapi_rs::link::add_handler_link(
+                &mut responses,
+                #status,
+                #name,
+                ::openapi_rs::link::resolve_handler_path(module_path!(), #handler),
+                ::std::vec![#((
+                    ::std::string::String::from(#parameter_names),
+                    ::std::string::String::from(#parameter_expressions),
+                )),*],
+                #description,
+            )
+            .map_err(|e| ::openapi_rs::error::OpenApiError::schema(HANDLER, e))?;
+        }
+    });
+
+    let request_examples = if okapi_attr.request_examples {
+        let body = input_fn.sig.inputs.iter().find_map(|fn_arg| match fn_arg {
+            FnArg::Typed(t) => find_wrapped(&t.ty, "Json"),
+            FnArg::Receiver(_) => None,
+        });
+        match body {
+            Some(body) => quote! {
+                if let ::std::option::Option::Some(body) = &mut request_body {
+                    ::openapi_rs::example::add_request_examples::<#body>(gen, body)
+                        .map_err(|e| ::openapi_rs::error::OpenApiError::schema(HANDLER, e))?;
                 }
-    
-    
-                if let Some(header) = header_idents.last() {
-                            let a = quote! {
-                                let header_name = #header::name().as_str();
-    
-                                let schema = gen.json_schema::<String>();
-    
-                                let parameter = Parameter {
-                                    name: header_name.into(),
-                                    location: "header".to_owned(),
-                                    description: None,
-                                    required: true,
-                                    deprecated: false,
-                                    allow_empty_value: false,
-                                    value: ParameterValue::Schema {
-                                        style: None,
-                                        explode: None,
-                                        allow_reserved: false,
-                                        schema,
-                                        example: None,
-                                        examples: None,
-                                    },
-                                    extensions: Object::default(),
-                                };
-    
-                                parameters.push(RefOr::Object(parameter));
-    
-                            };
-        
-                            tokens_map.insert("header",a);
-    
-                    } else {
-                        if !tokens_map.contains_key("auth") {
-                            let a = quote! {
-                                let requirement: Option<Vec<SecurityRequirement>> = None;
-                                let scheme: Option<SecurityScheme> = None;
-                                let name: Option<String> = None;
-                            };    
-        
-                            tokens_map.insert("auth",a);
-                        }
-                    }
+            },
+            None => {
+                return syn::Error::new(
+                    Span::call_site(),
+                    "`request_examples` requires a `Json<T>` argument",
+                )
+                .into_compile_error()
             }
         }
     } else {
-        let v = quote! {
-            let requirement: Option<Vec<SecurityRequirement>> = None;
-            let scheme: Option<SecurityScheme> = None;
-            let name: Option<String> = None;
-            let mut parameters: Vec<RefOr<Parameter>> = vec![];
-            let requests: Option<RefOr<RequestBody>> = None;
-        };
-
-        tokens_map.insert("empty",v);
-    }
-
-    let return_type = input_fn.sig.output;
+        TokenStream::new()
+    };
 
-    let fn_ident = input_fn.sig.ident;
+    let response_examples = if let Some(examples) = &okapi_attr.response_examples {
+        let status = opt(examples.status);
+        let body = match &input_fn.sig.output {
+            ReturnType::Type(_, ty) => find_wrapped(ty, "Json"),
+            ReturnType::Default => None,
+        };
+        match body {
+            Some(body) => quote! {
+                ::openapi_rs::example::add_response_examples::<#body>(gen, &mut responses, #status)
+                    .map_err(|e| ::openapi_rs::error::OpenApiError::schema(HANDLER, e))?;
+            },
+            None => {
+                return syn::Error::new(
+                    Span::call_site(),
+                    "`response_examples` requires a `Json<T>` return type",
+                )
+                .into_compile_error()
+            }
+        }
+    } else {
+        TokenStream::new()
+    };
 
-    let spec_fn_ident = Ident::new(&format!("{}_spec",&fn_ident),Span::call_site());
+    let fn_ident = &input_fn.sig.ident;
 
-    match return_type {
-        syn::ReturnType::Type(_, t) => {
-            let body_ident = filter_type(t.clone(), |i| i.to_string().contains("Result") || i == "Json");
+    let spec_fn_ident = Ident::new(&format!("{}_spec", fn_ident), Span::call_site());
 
-            if let Some(b_type) = body_ident.last() {
-                let b = quote! {
-                    let responses = Json::<#b_type>::responses(gen).expect("failed to generate response schema");
-                };
+    let operation_id = match &okapi_attr.operation_id {
+        Some(id) => quote!(::std::option::Option::Some(::std::string::String::from(#id))),
+        None => quote!(::std::option::Option::None),
+    };
 
-                tokens_map.insert("response",b);
+    for (name, date) in [
+        ("deprecated_since", &okapi_attr.deprecated_since),
+        ("sunset", &okapi_attr.sunset),
+    ] {
+        if let Some(date) = date {
+            if !is_date(date) {
+                return syn::Error::new(
+                    Span::call_site(),
+                    format!("`{}` must be a `YYYY-MM-DD` date, got `{}`", name, date),
+                )
+                .into_compile_error();
             }
         }
-        _ => {}
     }
+    let deprecated = okapi_attr.deprecated
+        || okapi_attr.deprecated_since.is_some()
+        || input_fn.attrs.iter().any(|attr| attr.path.is_ident("deprecated"));
+    let deprecated_since = okapi_attr.deprecated_since.iter();
+    let sunset = okapi_attr.sunset.iter();
+    let replaced_by = okapi_attr.replaced_by.iter();
+    let extension_keys = extensions.iter().map(|e| &e.key);
+    let extension_values = extensions.iter().map(|e| &e.value);
+    let extension_types = &okapi_attr.extension_types;
 
     let method = okapi_attr.method;
     let tags = okapi_attr.tags;
-    let summary = okapi_attr.summary;
-    let description = okapi_attr.description;
+    // Attributes take precedence over the doc comment of the handler.
+    let (doc_summary, doc_description) = doc::doc_comment(&input_fn.attrs)
+        .map(|doc| doc::summary_and_description(&doc))
+        .unwrap_or_default();
+    let summary = opt_string(okapi_attr.summary.and_then(doc::non_empty).or(doc_summary));
+    let description = opt_string(
+        okapi_attr
+            .description
+            .and_then(doc::non_empty)
+            .or(doc_description),
+    );
 
-    let tokens = tokens_map.values();
-    // println!("{:?}",return_type);
     quote! {
-        pub fn #spec_fn_ident(path:&str,gen: &mut OpenApiGenerator) {
-            use ::okapi::openapi3::{RequestBody,Parameter,Operation,SecurityScheme,SecurityRequirement,ParameterValue};
-            use ::openapi_rs::OperationInfo;
-            use ::openapi_rs::response::OpenApiResponderInner;
-            use ::openapi_rs::request::{RequestHeaderInput,OpenApiFromRequest};
-            use ::headers::*;
+        #[allow(unused_mut)]
+        pub fn #spec_fn_ident(
+            path: &str,
+            gen: &mut ::openapi_rs::gen::OpenApiGenerator,
+        ) -> ::openapi_rs::error::Result<()> {
+            const HANDLER: &str = concat!(module_path!(), "::", stringify!(#fn_ident));
+
+            let mut parameters: ::std::vec::Vec<::openapi_rs::okapi::openapi3::RefOr<::openapi_rs::okapi::openapi3::Parameter>> =
+                ::std::vec::Vec::new();
+            let mut request_body: ::std::option::Option<::openapi_rs::okapi::openapi3::RefOr<::openapi_rs::okapi::openapi3::RequestBody>> =
+                ::std::option::Option::None;
+            let mut security: ::std::option::Option<::std::vec::Vec<::openapi_rs::okapi::openapi3::SecurityRequirement>> =
+                ::std::option::Option::None;
+
+            #(#arguments)*
+
+            #(#security_types)*
 
-            #(#tokens)*
+            #(#headers)*
 
-            if let (Some(security_name),Some(security_scheme)) = (name,scheme) {
-                gen.add_security_scheme(security_name,security_scheme);
+            #(#cookies)*
+
+            #request_examples
+
+            #responses
+
+            #(#set_cookies)*
+
+            #(#response_descriptions)*
+
+            #response_examples
+
+            if responses.responses.is_empty() && responses.default.is_none() {
+                ::openapi_rs::utils::ensure_status_code_exists(&mut responses, 200);
             }
 
-            let operation = Operation {
+            #(#links)*
+
+            let mut callbacks = ::openapi_rs::okapi::Map::new();
+            #(#callbacks)*
+
+            let mut extensions = ::openapi_rs::okapi::openapi3::Object::default();
+            #(
+                extensions.insert(
+                    ::std::string::String::from(::openapi_rs::deprecation::DEPRECATED_SINCE_EXTENSION),
+                    ::openapi_rs::__private::serde_json::Value::from(#deprecated_since),
+                );
+            )*
+            #(
+                extensions.insert(
+                    ::std::string::String::from(::openapi_rs::deprecation::SUNSET_EXTENSION),
+                    ::openapi_rs::__private::serde_json::Value::from(#sunset),
+                );
+            )*
+            #(
+                extensions.insert(
+                    ::std::string::String::from(::openapi_rs::deprecation::REPLACED_BY_EXTENSION),
+                    ::openapi_rs::__private::serde_json::Value::from(#replaced_by),
+                );
+            )*
+            #(
+                extensions.extend(
+                    <#extension_types as ::openapi_rs::extension::OpenApiExtensions>::extensions(gen)
+                        .map_err(|e| ::openapi_rs::error::OpenApiError::schema(HANDLER, e))?,
+                );
+            )*
+            #(
+                extensions.insert(
+                    ::std::string::String::from(#extension_keys),
+                    ::openapi_rs::__private::serde_json::Value::from(#extension_values),
+                );
+            )*
+
+            let operation = ::openapi_rs::okapi::openapi3::Operation {
                 parameters,
-                tags: vec![#(stringify!(#tags).to_string())*],
-                summary: Some(#summary.to_string()),
-                description: Some(#description.to_string()),
+                tags: ::std::vec![#(::std::string::String::from(#tags)),*],
+                summary: #summary,
+                description: #description,
                 responses,
-                request_body: requests,
-                operation_id: Some(stringify!(#fn_ident).to_string()),
-                security: requirement,
-                ..Default::default()
+                request_body,
+                operation_id: #operation_id,
+                security,
+                deprecated: #deprecated,
+                callbacks,
+                extensions,
+                ..::std::default::Default::default()
             };
 
-            let mut operation_info = OperationInfo {
+            gen.add_operation(::openapi_rs::OperationInfo {
                 path: path.to_string(),
-                method: #method.into(),
-                operation
-            };
-
-            gen.add_operation(operation_info);
+                method: ::openapi_rs::OpenApiMethod::from(#method),
+                handler: ::std::option::Option::Some(::std::string::String::from(HANDLER)),
+                operation,
+            })
         }
-    }.into()
+    }
 }
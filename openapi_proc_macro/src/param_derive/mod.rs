@@ -0,0 +1,383 @@
+use crate::doc;
+use darling::FromMeta;
+use proc_macro2::TokenStream;
+use quote::ToTokens;
+use syn::Attribute;
+use syn::Data;
+use syn::DeriveInput;
+use syn::Error;
+use syn::Field;
+use syn::Fields;
+use syn::Lit;
+use syn::Meta;
+use syn::NestedMeta;
+
+/// The parameter location a derive macro generates parameters for.
+#[derive(Clone, Copy)]
+pub enum ParamKind {
+    Query,
+    Path,
+    Header,
+}
+
+impl ParamKind {
+    fn derive_name(self) -> &'static str {
+        match self {
+            ParamKind::Query => "OpenApiQuery",
+            ParamKind::Path => "OpenApiPath",
+            ParamKind::Header => "OpenApiHeaders",
+        }
+    }
+
+    fn location(self) -> TokenStream {
+        match self {
+            ParamKind::Query => quote!(::openapi_rs::ParameterLocation::Query),
+            ParamKind::Path => quote!(::openapi_rs::ParameterLocation::Path),
+            ParamKind::Header => quote!(::openapi_rs::ParameterLocation::Header),
+        }
+    }
+}
+
+/// Field-level `#[param(...)]` attribute.
+#[derive(Debug, Default, FromMeta)]
+#[darling(default)]
+struct ParamAttribute {
+    description: Option<String>,
+
+    example: Option<String>,
+
+    deprecated: bool,
+
+    allow_empty_value: bool,
+
+    style: Option<String>,
+
+    explode: Option<bool>,
+
+    allow_reserved: bool,
+
+    pattern: Option<String>,
+}
+
+/// Constraints read from the field-level `#[validate(...)]` attribute of the `validator` crate.
+#[derive(Debug, Default)]
+struct Constraints {
+    minimum: Option<f64>,
+    maximum: Option<f64>,
+    min_length: Option<u32>,
+    max_length: Option<u32>,
+    format: Option<&'static str>,
+}
+
+pub fn derive_params(input: DeriveInput, kind: ParamKind) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+
+    let fields = match &input.data {
+        Data::Struct(s) => &s.fields,
+        _ => {
+            return Err(Error::new_spanned(
+                name,
+                format!("`{}` can only be derived for structs", kind.derive_name()),
+            ))
+        }
+    };
+
+    let rename_all = serde_attribute(&input.attrs, "rename_all")?;
+
+    let body = match (fields, kind) {
+        (Fields::Named(named), _) => {
+            let mut parameter_fields = vec![];
+            for field in &named.named {
+                parameter_fields.push(parameter_field(field, rename_all.as_deref())?);
+            }
+            let location = kind.location();
+
+            quote! {
+                let schema = gen.json_schema_no_ref::<Self>();
+                let parameters = ::openapi_rs::parameters_from_schema(
+                    schema,
+                    #location,
+                    ::std::vec![#(#parameter_fields),*],
+                );
+            }
+        }
+        (Fields::Unnamed(unnamed), ParamKind::Path) if unnamed.unnamed.len() == 1 => quote! {
+            let schema = gen.json_schema_no_ref::<Self>();
+            let parameters = ::openapi_rs::parameter::positional_path_parameters(path, ::std::vec![schema]);
+        },
+        _ => {
+            return Err(Error::new_spanned(
+                fields,
+                format!(
+                    "`{}` can only be derived for structs with named fields",
+                    kind.derive_name()
+                ),
+            ))
+        }
+    };
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let (trait_path, signature) = match kind {
+        ParamKind::Query => (
+            quote!(::openapi_rs::request::QueryParams),
+            quote!(query_parameters(gen: &mut ::openapi_rs::gen::OpenApiGenerator)),
+        ),
+        ParamKind::Path => (
+            quote!(::openapi_rs::request::PathParams),
+            quote!(path_parameters(gen: &mut ::openapi_rs::gen::OpenApiGenerator, path: &str)),
+        ),
+        ParamKind::Header => (
+            quote!(::openapi_rs::request::HeaderParams),
+            quote!(header_parameters(gen: &mut ::openapi_rs::gen::OpenApiGenerator)),
+        ),
+    };
+
+    Ok(quote! {
+        impl #impl_generics #trait_path for #name #ty_generics #where_clause {
+            #[allow(unused_variables)]
+            fn #signature -> ::openapi_rs::__private::anyhow::Result<
+                ::std::vec::Vec<::openapi_rs::okapi::openapi3::RefOr<::openapi_rs::okapi::openapi3::Parameter>>,
+            > {
+                #body
+                ::std::result::Result::Ok(parameters)
+            }
+        }
+    })
+}
+
+/// Build the `ParameterField` expression describing one struct field.
+fn parameter_field(field: &Field, rename_all: Option<&str>) -> syn::Result<TokenStream> {
+    let ident = field.ident.as_ref().expect("named field");
+    let name = match serde_attribute(&field.attrs, "rename")? {
+        Some(rename) => rename,
+        None => rename_field(&ident.to_string(), rename_all),
+    };
+
+    let mut param_items: Vec<NestedMeta> = vec![];
+    for attr in field.attrs.iter().filter(|a| a.path.is_ident("param")) {
+        match attr.parse_meta()? {
+            Meta::List(list) => param_items.extend(list.nested),
+            meta => return Err(Error::new_spanned(meta, "expected `#[param(...)]`")),
+        }
+    }
+    let param = ParamAttribute::from_list(&param_items).map_err(|e| {
+        let span = attr_span(&field.attrs, "param").unwrap_or_else(|| ident.span());
+        Error::new(span, e.to_string())
+    })?;
+
+    let style = match param.style.as_deref() {
+        None => quote!(::std::option::Option::None),
+        Some(style) => {
+            let variant = match style {
+                "matrix" => quote!(Matrix),
+                "label" => quote!(Label),
+                "form" => quote!(Form),
+                "simple" => quote!(Simple),
+                "spaceDelimited" => quote!(SpaceDelimited),
+                "pipeDelimited" => quote!(PipeDelimited),
+                "deepObject" => quote!(DeepObject),
+                other => {
+                    let span = attr_span(&field.attrs, "param").unwrap_or_else(|| ident.span());
+                    return Err(Error::new(
+                        span,
+                        format!(
+                            "unknown parameter style `{}`, expected one of `matrix`, `label`, `form`, `simple`, `spaceDelimited`, `pipeDelimited`, `deepObject`",
+                            other
+                        ),
+                    ));
+                }
+            };
+            quote!(::std::option::Option::Some(::openapi_rs::okapi::openapi3::ParameterStyle::#variant))
+        }
+    };
+
+    let constraints = validate_constraints(&field.attrs);
+
+    let description = opt_string(param.description.or_else(|| doc::doc_comment(&field.attrs)));
+    let example = opt_string(param.example);
+    let deprecated = param.deprecated;
+    let allow_empty_value = param.allow_empty_value;
+    let explode = opt(param.explode);
+    let allow_reserved = param.allow_reserved;
+    let minimum = opt(constraints.minimum);
+    let maximum = opt(constraints.maximum);
+    let min_length = opt(constraints.min_length);
+    let max_length = opt(constraints.max_length);
+    let pattern = opt_string(param.pattern);
+    let format = opt_string(constraints.format.map(str::to_owned));
+
+    Ok(quote! {
+        ::openapi_rs::parameter::ParameterField {
+            name: #name,
+            attributes: ::openapi_rs::parameter::ParameterAttributes {
+                description: #description,
+                example: #example,
+                deprecated: #deprecated,
+                allow_empty_value: #allow_empty_value,
+                style: #style,
+                explode: #explode,
+                allow_reserved: #allow_reserved,
+                constraints: ::openapi_rs::parameter::Constraints {
+                    minimum: #minimum,
+                    maximum: #maximum,
+                    min_length: #min_length,
+                    max_length: #max_length,
+                    pattern: #pattern,
+                    format: #format,
+                },
+            },
+        }
+    })
+}
+
+fn attr_span(attrs: &[Attribute], name: &str) -> Option<proc_macro2::Span> {
+    attrs
+        .iter()
+        .find(|a| a.path.is_ident(name))
+        .map(|a| a.path.segments[0].ident.span())
+}
+
+fn opt<T: ToTokens>(value: Option<T>) -> TokenStream {
+    match value {
+        Some(v) => quote!(::std::option::Option::Some(#v)),
+        None => quote!(::std::option::Option::None),
+    }
+}
+
+fn opt_string(value: Option<String>) -> TokenStream {
+    match value {
+        Some(v) => quote!(::std::option::Option::Some(::std::string::String::from(#v))),
+        None => quote!(::std::option::Option::None),
+    }
+}
+
+/// Read `#[serde(key = "...")]` or `#[serde(key(deserialize = "..."))]`.
+fn serde_attribute(attrs: &[Attribute], key: &str) -> syn::Result<Option<String>> {
+    for attr in attrs.iter().filter(|a| a.path.is_ident("serde")) {
+        let list = match attr.parse_meta()? {
+            Meta::List(list) => list,
+            _ => continue,
+        };
+        for nested in list.nested {
+            match nested {
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident(key) => {
+                    if let Lit::Str(s) = nv.lit {
+                        return Ok(Some(s.value()));
+                    }
+                }
+                NestedMeta::Meta(Meta::List(inner)) if inner.path.is_ident(key) => {
+                    for nested in inner.nested {
+                        if let NestedMeta::Meta(Meta::NameValue(nv)) = nested {
+                            if let (true, Lit::Str(s)) = (nv.path.is_ident("deserialize"), nv.lit) {
+                                return Ok(Some(s.value()));
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// Apply a serde `rename_all` rule to a snake_case field name.
+fn rename_field(field: &str, rename_all: Option<&str>) -> String {
+    let field = field.trim_start_matches("r#");
+    let words = field.split('_').filter(|w| !w.is_empty());
+    let capitalize = |w: &str| {
+        let mut chars = w.chars();
+        match chars.next() {
+            Some(first) => first.to_uppercase().chain(chars).collect::<String>(),
+            None => String::new(),
+        }
+    };
+
+    match rename_all {
+        Some("lowercase") => field.to_lowercase(),
+        Some("UPPERCASE") => field.to_uppercase(),
+        Some("PascalCase") => words.map(capitalize).collect(),
+        Some("camelCase") => words
+            .enumerate()
+            .map(|(i, w)| if i == 0 { w.to_owned() } else { capitalize(w) })
+            .collect(),
+        Some("SCREAMING_SNAKE_CASE") => field.to_uppercase(),
+        Some("kebab-case") => field.replace('_', "-"),
+        Some("SCREAMING-KEBAB-CASE") => field.replace('_', "-").to_uppercase(),
+        _ => field.to_owned(),
+    }
+}
+
+/// Read the constraints of the `validator` crate that map onto JSON Schema keywords. Anything
+/// that cannot be represented (custom validators, non-literal bounds) is ignored.
+fn validate_constraints(attrs: &[Attribute]) -> Constraints {
+    let mut constraints = Constraints::default();
+
+    for attr in attrs.iter().filter(|a| a.path.is_ident("validate")) {
+        let list = match attr.parse_meta() {
+            Ok(Meta::List(list)) => list,
+            _ => continue,
+        };
+        for nested in list.nested {
+            match nested {
+                NestedMeta::Meta(Meta::Path(p)) if p.is_ident("email") => {
+                    constraints.format = Some("email");
+                }
+                NestedMeta::Meta(Meta::Path(p)) if p.is_ident("url") => {
+                    constraints.format = Some("uri");
+                }
+                NestedMeta::Meta(Meta::List(inner)) if inner.path.is_ident("length") => {
+                    for (key, lit) in name_values(inner.nested) {
+                        let value = match lit {
+                            Lit::Int(i) => i.base10_parse::<u32>().ok(),
+                            _ => None,
+                        };
+                        match key.as_str() {
+                            "min" => constraints.min_length = value,
+                            "max" => constraints.max_length = value,
+                            "equal" => {
+                                constraints.min_length = value;
+                                constraints.max_length = value;
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                NestedMeta::Meta(Meta::List(inner)) if inner.path.is_ident("range") => {
+                    for (key, lit) in name_values(inner.nested) {
+                        let value = match lit {
+                            Lit::Int(i) => i.base10_parse::<f64>().ok(),
+                            Lit::Float(f) => f.base10_parse::<f64>().ok(),
+                            _ => None,
+                        };
+                        match key.as_str() {
+                            "min" => constraints.minimum = value,
+                            "max" => constraints.maximum = value,
+                            _ => {}
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    constraints
+}
+
+fn name_values(
+    nested: syn::punctuated::Punctuated<NestedMeta, syn::token::Comma>,
+) -> Vec<(String, Lit)> {
+    nested
+        .into_iter()
+        .filter_map(|n| match n {
+            NestedMeta::Meta(Meta::NameValue(nv)) => nv
+                .path
+                .get_ident()
+                .map(|ident| (ident.to_string(), nv.lit)),
+            _ => None,
+        })
+        .collect()
+}
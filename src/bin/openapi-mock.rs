@@ -0,0 +1,77 @@
+//! Serve a mock of the API described by an OpenAPI file, in JSON or in YAML when its extension is
+//! `.yaml` or `.yml`, read by [`openapi_rs::cli::load`].
+//!
+//! ```text
+//! openapi-mock openapi.json [--port 4010] [--validate]
+//! ```
+
+use std::net::SocketAddr;
+use std::process::ExitCode;
+
+use openapi_rs::mock::MockServer;
+
+const USAGE: &str = "usage: openapi-mock <openapi.json|openapi.yaml> [--port <port>] [--validate]";
+
+struct Args {
+    spec: String,
+    port: u16,
+    validate: bool,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut spec = None;
+    let mut port = 4010;
+    let mut validate = false;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--port" => {
+                let value = args.next().ok_or("missing value for --port")?;
+                port = value.parse().map_err(|_| format!("invalid port `{}`", value))?;
+            }
+            "--validate" => validate = true,
+            "-h" | "--help" => return Err(USAGE.to_owned()),
+            _ if arg.starts_with('-') => return Err(format!("unknown option `{}`", arg)),
+            _ if spec.is_none() => spec = Some(arg),
+            _ => return Err(format!("unexpected argument `{}`", arg)),
+        }
+    }
+    Ok(Args {
+        spec: spec.ok_or("missing specification file")?,
+        port,
+        validate,
+    })
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("{}\n{}", message, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    let openapi = match openapi_rs::cli::load(&args.spec) {
+        Ok(openapi) => openapi,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let app = MockServer::new(&openapi)
+        .validate_requests(args.validate)
+        .into_router();
+    let address = SocketAddr::from(([127, 0, 0, 1], args.port));
+    println!("mocking {} on http://{}", args.spec, address);
+    if let Err(e) = axum::Server::bind(&address)
+        .serve(app.into_make_service())
+        .await
+    {
+        eprintln!("server error: {}", e);
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
+
@@ -0,0 +1,5 @@
+//! Validate and diff OpenAPI files, see [`openapi_rs::cli`].
+
+fn main() -> std::process::ExitCode {
+    openapi_rs::cli::main()
+}
@@ -0,0 +1,74 @@
+//! Callbacks of operations and outgoing webhooks.
+//!
+//! A callback is a request the API sends to a URL provided by the client, declared on the handler
+//! registering it:
+//! ```rust,ignore
+//! #[handler(
+//!     method = "POST",
+//!     callback(name = "onEvent", expression = "{$request.body#/callbackUrl}", payload = "Event"),
+//! )]
+//! async fn subscribe(Json(subscription): Json<Subscription>) -> Json<Subscribed> { ... }
+//! ```
+//! Webhooks are requests sent to URLs registered out of band. They are added to the generator
+//! with [`OpenApiGenerator::add_webhook`] and rendered according to
+//! [`OpenApiVersion`](crate::settings::OpenApiVersion).
+
+use anyhow::Result;
+use okapi::openapi3::{Callback, MediaType, Operation, RefOr, RequestBody};
+use okapi::Map;
+use schemars::JsonSchema;
+
+use crate::gen::{set_operation, OpenApiGenerator};
+use crate::utils::ensure_status_code_exists;
+use crate::OpenApiMethod;
+
+/// Create the operation of a callback or webhook sending a JSON `T` to the receiver, which
+/// answers with `200`.
+pub fn callback_operation<T: ?Sized + JsonSchema>(
+    gen: &mut OpenApiGenerator,
+    description: Option<String>,
+) -> Result<Operation> {
+    let mut content = Map::new();
+    content.insert(
+        "application/json".to_owned(),
+        MediaType {
+            schema: Some(gen.json_schema::<T>()),
+            ..MediaType::default()
+        },
+    );
+
+    let mut operation = Operation {
+        description,
+        request_body: Some(RefOr::Object(RequestBody {
+            content,
+            required: true,
+            ..RequestBody::default()
+        })),
+        ..Operation::default()
+    };
+    ensure_status_code_exists(&mut operation.responses, 200);
+    Ok(operation)
+}
+
+/// Add the callback `name` sending a JSON `T` with `method` to the URL given by the runtime
+/// `expression`, e.g. `{$request.body#/callbackUrl}`.
+pub fn add_callback<T: ?Sized + JsonSchema>(
+    gen: &mut OpenApiGenerator,
+    callbacks: &mut Map<String, RefOr<Callback>>,
+    name: &str,
+    expression: &str,
+    method: OpenApiMethod,
+    description: Option<String>,
+) -> Result<()> {
+    let operation = callback_operation::<T>(gen, description)?;
+    let callback = match callbacks
+        .entry(name.to_owned())
+        .or_insert_with(|| RefOr::Object(Callback::default()))
+    {
+        RefOr::Object(callback) => callback,
+        RefOr::Ref(r) => anyhow::bail!("callback `{}` is a reference to `{}`", name, r.reference),
+    };
+    let path_item = callback.callbacks.entry(expression.to_owned()).or_default();
+    set_operation(path_item, method, operation);
+    Ok(())
+}
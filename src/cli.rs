@@ -0,0 +1,288 @@
+//! The `openapi-rs` command-line tool (feature `cli`), to produce and check specifications in CI
+//! without starting the server.
+//!
+//! ```text
+//! openapi-rs dump [--format json|yaml] [--output <file>]
+//! openapi-rs validate <spec>
+//! openapi-rs diff <old> <new> [--format text|json]
+//! ```
+//!
+//! `validate` and `diff` read JSON or YAML files, by extension. `dump` needs the routes of a
+//! crate, so it is only available from a binary or example of that crate passing the function
+//! building its specification to [`main_with`]:
+//! ```no_run
+//! # mod my_api {
+//! #     use axum::Json;
+//! #     use openapi_rs::gen::OpenApiGenerator;
+//! #     use openapi_rs::settings::OpenApiSettings;
+//! #     #[openapi_rs::handler(method = "GET")]
+//! #     async fn list_users() -> Json<Vec<String>> {
+//! #         Json(vec!["ada".to_owned()])
+//! #     }
+//! #     pub fn openapi() -> anyhow::Result<okapi::openapi3::OpenApi> {
+//! #         let mut gen = OpenApiGenerator::new(&OpenApiSettings::new());
+//! #         list_users_spec("/users", &mut gen)?;
+//! #         Ok(gen.into_openapi()?)
+//! #     }
+//! # }
+//! // examples/openapi.rs: `cargo run --example openapi -- dump --output openapi.yaml`
+//! fn main() -> std::process::ExitCode {
+//!     openapi_rs::cli::main_with(my_api::openapi)
+//! }
+//! ```
+//!
+//! The exit code is 1 when `validate` finds errors or `diff` finds breaking changes, and 2 on
+//! usage or I/O errors.
+
+use std::path::Path;
+use std::process::ExitCode;
+
+use okapi::openapi3::OpenApi;
+
+use crate::diff::diff;
+use crate::render;
+use crate::utils::load_openapi;
+use crate::validate::validate;
+
+const USAGE: &str = "usage:
+    openapi-rs dump [--format json|yaml] [--output <file>]
+    openapi-rs validate <spec>
+    openapi-rs diff <old> <new> [--format text|json]";
+
+type Hook<'a> = Box<dyn FnOnce() -> anyhow::Result<OpenApi> + 'a>;
+
+/// Run the tool with the arguments of the process, without `dump` support.
+#[must_use]
+pub fn main() -> ExitCode {
+    exit(run(std::env::args().skip(1).collect(), None))
+}
+
+/// Run the tool with the arguments of the process, dumping the specification built by `openapi`.
+#[must_use]
+pub fn main_with<F>(openapi: F) -> ExitCode
+where
+    F: FnOnce() -> anyhow::Result<OpenApi>,
+{
+    exit(run(
+        std::env::args().skip(1).collect(),
+        Some(Box::new(openapi)),
+    ))
+}
+
+fn exit(result: Result<ExitCode, String>) -> ExitCode {
+    result.unwrap_or_else(|message| {
+        eprintln!("error: {}\n\n{}", message, USAGE);
+        ExitCode::from(2)
+    })
+}
+
+/// Serialization format of a specification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Json,
+    Yaml,
+}
+
+impl Format {
+    fn parse(format: &str) -> Result<Self, String> {
+        match format {
+            "json" => Ok(Format::Json),
+            "yaml" | "yml" => Ok(Format::Yaml),
+            _ => Err(format!("unknown format `{}`", format)),
+        }
+    }
+
+    /// The format of the file at `path`, by extension. JSON unless `.yaml` or `.yml`.
+    fn of(path: &str) -> Self {
+        match Path::new(path).extension().and_then(|e| e.to_str()) {
+            Some("yaml" | "yml") => Format::Yaml,
+            _ => Format::Json,
+        }
+    }
+}
+
+fn run(args: Vec<String>, openapi: Option<Hook<'_>>) -> Result<ExitCode, String> {
+    let mut args = args.into_iter();
+    let command = args.next().ok_or("missing command")?;
+
+    let mut positional = Vec::new();
+    let mut format = None;
+    let mut output = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => format = Some(args.next().ok_or("missing value for --format")?),
+            "--output" | "-o" => output = Some(args.next().ok_or("missing value for --output")?),
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(ExitCode::SUCCESS);
+            }
+            _ if arg.starts_with('-') => return Err(format!("unknown option `{}`", arg)),
+            _ => positional.push(arg),
+        }
+    }
+
+    match (command.as_str(), positional.as_slice()) {
+        ("dump", []) => {
+            let openapi = openapi.ok_or(
+                "`dump` needs the routes of a crate: call `openapi_rs::cli::main_with` from a binary or example of that crate",
+            )?;
+            let openapi =
+                openapi().map_err(|e| format!("cannot build the specification: {:#}", e))?;
+            let format = match (&format, &output) {
+                (Some(format), _) => Format::parse(format)?,
+                (None, Some(output)) => Format::of(output),
+                (None, None) => Format::Json,
+            };
+            let rendered = render(&openapi, format)?;
+            match output {
+                Some(output) => std::fs::write(&output, rendered)
+                    .map_err(|e| format!("cannot write `{}`: {}", output, e))?,
+                None => println!("{}", rendered),
+            }
+            Ok(ExitCode::SUCCESS)
+        }
+        ("validate", [spec]) => {
+            let errors = validate(&load(spec)?);
+            for error in &errors {
+                println!("{}", error);
+            }
+            if errors.is_empty() {
+                println!("{} is valid", spec);
+                Ok(ExitCode::SUCCESS)
+            } else {
+                println!("{} error(s) in {}", errors.len(), spec);
+                Ok(ExitCode::FAILURE)
+            }
+        }
+        ("diff", [old, new]) => {
+            let report = diff(&load(old)?, &load(new)?);
+            match format.as_deref().unwrap_or("text") {
+                "text" => print!("{}", report),
+                "json" => println!(
+                    "{}",
+                    serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?
+                ),
+                format => return Err(format!("unknown format `{}`", format)),
+            }
+            Ok(if report.has_breaking_changes() {
+                ExitCode::FAILURE
+            } else {
+                ExitCode::SUCCESS
+            })
+        }
+        ("dump" | "validate" | "diff", _) => {
+            Err(format!("wrong number of arguments for `{}`", command))
+        }
+        _ => Err(format!("unknown command `{}`", command)),
+    }
+}
+
+/// Read the OpenAPI document at `path`, in YAML when it ends in `.yaml` or `.yml` and in JSON
+/// otherwise, as `validate` and `diff` do.
+pub fn load(path: &str) -> Result<OpenApi, String> {
+    load_openapi(Path::new(path)).map_err(|e| format!("cannot load `{}`: {}", path, e))
+}
+
+fn render(openapi: &OpenApi, format: Format) -> Result<String, String> {
+    match format {
+        Format::Json => render::to_json(openapi),
+        Format::Yaml => render::to_yaml(openapi),
+    }
+    .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use okapi::openapi3::{Operation, PathItem};
+
+    use crate::utils::TempDir;
+
+    fn openapi(operations: &[&str]) -> OpenApi {
+        let mut openapi = OpenApi {
+            openapi: "3.0.0".to_owned(),
+            ..OpenApi::default()
+        };
+        openapi.info.title = "cli".to_owned();
+        openapi.info.version = "1".to_owned();
+        for id in operations {
+            let mut operation = Operation {
+                operation_id: Some((*id).to_owned()),
+                ..Operation::default()
+            };
+            operation.responses.responses.insert(
+                "200".to_owned(),
+                okapi::openapi3::Response {
+                    description: "OK".to_owned(),
+                    ..Default::default()
+                }
+                .into(),
+            );
+            openapi.paths.insert(
+                format!("/{}", id),
+                PathItem {
+                    get: Some(operation),
+                    ..PathItem::default()
+                },
+            );
+        }
+        openapi
+    }
+
+    /// Write `openapi` to the file `name` of `dir`, in the format of its extension.
+    fn write(dir: &TempDir, name: &str, openapi: &OpenApi) -> String {
+        let path = dir.path(name).to_string_lossy().into_owned();
+        std::fs::write(&path, render(openapi, Format::of(&path)).unwrap()).unwrap();
+        path
+    }
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| (*arg).to_owned()).collect()
+    }
+
+    #[test]
+    fn formats_follow_the_extension() {
+        assert_eq!(Format::of("openapi.yml"), Format::Yaml);
+        assert_eq!(Format::of("openapi.yaml"), Format::Yaml);
+        assert_eq!(Format::of("openapi.json"), Format::Json);
+        assert_eq!(Format::of("openapi"), Format::Json);
+        assert!(Format::parse("toml").is_err());
+    }
+
+    #[test]
+    fn specifications_round_trip_through_json_and_yaml() {
+        let openapi = openapi(&["users", "orders"]);
+        let dir = TempDir::new();
+        let json = load(&write(&dir, "round-trip.json", &openapi)).unwrap();
+        let yaml = load(&write(&dir, "round-trip.yaml", &openapi)).unwrap();
+        let yml = load(&write(&dir, "round-trip.yml", &openapi)).unwrap();
+        assert_eq!(render(&json, Format::Json), render(&openapi, Format::Json));
+        assert_eq!(render(&yaml, Format::Yaml), render(&openapi, Format::Yaml));
+        assert_eq!(render(&yml, Format::Yaml), render(&openapi, Format::Yaml));
+        assert!(load("missing.json").unwrap_err().starts_with("cannot load `missing.json`"));
+    }
+
+    #[test]
+    fn diff_fails_on_breaking_changes() {
+        let dir = TempDir::new();
+        let old = write(&dir, "old.yaml", &openapi(&["users", "orders"]));
+        let new = write(&dir, "new.json", &openapi(&["users"]));
+        let added = write(&dir, "added.json", &openapi(&["users", "orders", "items"]));
+        assert_eq!(run(args(&["diff", &old, &new]), None), Ok(ExitCode::FAILURE));
+        assert_eq!(run(args(&["diff", &old, &added]), None), Ok(ExitCode::SUCCESS));
+        assert_eq!(run(args(&["validate", &old]), None), Ok(ExitCode::SUCCESS));
+    }
+
+    #[test]
+    fn dump_needs_the_routes_of_a_crate() {
+        assert!(run(args(&["dump"]), None).is_err());
+        let dir = TempDir::new();
+        let output = write(&dir, "dump.yaml", &OpenApi::default());
+        let hook: Hook<'_> = Box::new(|| Ok(openapi(&["users"])));
+        let dumped = run(args(&["dump", "--output", &output]), Some(hook));
+        assert_eq!(dumped, Ok(ExitCode::SUCCESS));
+        let loaded = load(&output).unwrap();
+        assert_eq!(render(&loaded, Format::Json), render(&openapi(&["users"]), Format::Json));
+    }
+}
@@ -0,0 +1,71 @@
+//! Documentation of cookies read through the `axum-extra` cookie jars.
+//!
+//! A jar does not know which cookies a handler reads, so the names are declared on the handler:
+//! ```rust,ignore
+//! #[handler(method = "GET", cookie(name = "session", description = "Session id"))]
+//! async fn me(jar: CookieJar) -> Json<User> { ... }
+//! ```
+//! Custom extractors wrapping a jar can call [`OpenApiFromRequest::from_request_input`] with the
+//! cookie name to get the matching `in: cookie` parameter.
+
+use anyhow::Result;
+use axum_extra::extract::CookieJar;
+
+use super::gen::OpenApiGenerator;
+use super::parameter::{ParameterBuilder, ParameterLocation};
+use super::request::{OpenApiFromRequest, RequestHeaderInput};
+
+/// Create the `in: cookie` parameter for the cookie `name`.
+pub fn cookie_parameter(
+    gen: &mut OpenApiGenerator,
+    name: String,
+    required: bool,
+) -> RequestHeaderInput {
+    let parameter = ParameterBuilder::new(name, ParameterLocation::Cookie, gen.json_schema::<String>())
+        .required(required)
+        .build();
+    RequestHeaderInput::Parameter(parameter)
+}
+
+impl<B: Send> OpenApiFromRequest<B> for CookieJar {
+    /// `name` is the name of the cookie read from the jar.
+    fn from_request_input(
+        gen: &mut OpenApiGenerator,
+        name: String,
+        required: bool,
+    ) -> Result<RequestHeaderInput> {
+        Ok(cookie_parameter(gen, name, required))
+    }
+}
+
+#[cfg(feature = "cookie-private")]
+impl<B, K> OpenApiFromRequest<B> for axum_extra::extract::PrivateCookieJar<K>
+where
+    B: Send,
+    K: Into<axum_extra::extract::cookie::Key> + Clone + Send + Sync + 'static,
+{
+    /// `name` is the name of the cookie read from the jar.
+    fn from_request_input(
+        gen: &mut OpenApiGenerator,
+        name: String,
+        required: bool,
+    ) -> Result<RequestHeaderInput> {
+        Ok(cookie_parameter(gen, name, required))
+    }
+}
+
+#[cfg(feature = "cookie-private")]
+impl<B, K> OpenApiFromRequest<B> for axum_extra::extract::SignedCookieJar<K>
+where
+    B: Send,
+    K: Into<axum_extra::extract::cookie::Key> + Clone + Send + Sync + 'static,
+{
+    /// `name` is the name of the cookie read from the jar.
+    fn from_request_input(
+        gen: &mut OpenApiGenerator,
+        name: String,
+        required: bool,
+    ) -> Result<RequestHeaderInput> {
+        Ok(cookie_parameter(gen, name, required))
+    }
+}
@@ -0,0 +1,337 @@
+//! Deprecation metadata of operations and a middleware advertising it in responses.
+//!
+//! `#[handler(deprecated_since = "2026-06-01", sunset = "2027-01-01", replaced_by = "users_create_v2")]`
+//! (or a `deprecated` or `#[deprecated]` handler) marks the operation as deprecated and stores the
+//! deprecation date, the sunset date and the replacing operation in the
+//! [`DEPRECATED_SINCE_EXTENSION`], [`SUNSET_EXTENSION`] and [`REPLACED_BY_EXTENSION`] extensions.
+//! [`DeprecationLayer`] reads the same metadata from the finished specification:
+//! ```rust,ignore
+//! let openapi = gen.into_openapi()?;
+//! let app = Router::new()
+//!     .route("/users", post(create))
+//!     .layer(DeprecationLayer::new(&openapi));
+//! ```
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use axum::http::header::{HeaderName, HeaderValue, LINK};
+use axum::http::{Method, Request, Response};
+use okapi::openapi3::OpenApi;
+use tower_layer::Layer;
+use tower_service::Service;
+
+use crate::matcher::PathMatcher;
+use crate::validate::operations;
+
+/// Extension holding the date (`YYYY-MM-DD`) from which an operation is deprecated.
+pub const DEPRECATED_SINCE_EXTENSION: &str = "x-deprecated-since";
+/// Extension holding the sunset date (`YYYY-MM-DD`) of a deprecated operation.
+pub const SUNSET_EXTENSION: &str = "x-sunset";
+/// Extension holding the `operationId` of the operation replacing a deprecated one.
+pub const REPLACED_BY_EXTENSION: &str = "x-replaced-by";
+
+const DEPRECATION: HeaderName = HeaderName::from_static("deprecation");
+const SUNSET: HeaderName = HeaderName::from_static("sunset");
+
+/// Headers added to the responses of one deprecated operation.
+#[derive(Debug, Clone, Default)]
+struct DeprecationHeaders {
+    deprecation: Option<HeaderValue>,
+    sunset: Option<HeaderValue>,
+    link: Option<HeaderValue>,
+}
+
+type Routes = PathMatcher<HashMap<Method, DeprecationHeaders>>;
+
+/// A [`Layer`] adding the `Deprecation`, `Sunset` and `Link: rel="successor-version"` headers to
+/// the responses of the operations marked as deprecated or with a sunset date in the
+/// specification.
+///
+/// Following RFC 9745, `Deprecation` carries the deprecation date as `@<unix-time>`, taken from
+/// [`DEPRECATED_SINCE_EXTENSION`]; it is left out for deprecated operations without that date.
+/// The `Link` header is only sent when the replacing operation has a path without template
+/// variables, such as `/v2/users`: a template such as `/users/{id}` is not a URI reference. An
+/// unknown replacing operation is reported by [`validate`](crate::validate::validate).
+#[derive(Debug, Clone)]
+pub struct DeprecationLayer {
+    routes: Arc<Routes>,
+}
+
+impl DeprecationLayer {
+    /// Create the layer from the deprecation metadata of `openapi`.
+    #[must_use]
+    pub fn new(openapi: &OpenApi) -> Self {
+        let paths_by_id: HashMap<&str, &str> = openapi
+            .paths
+            .iter()
+            .flat_map(|(path, item)| {
+                operations(item)
+                    .into_iter()
+                    .filter_map(move |(_, op)| Some((op.operation_id.as_deref()?, path.as_str())))
+            })
+            .collect();
+
+        let mut routes = Routes::default();
+        for (path, item) in &openapi.paths {
+            let mut methods = HashMap::new();
+            for (method, op) in operations(item) {
+                let deprecation = op
+                    .extensions
+                    .get(DEPRECATED_SINCE_EXTENSION)
+                    .filter(|_| op.deprecated)
+                    .and_then(|v| v.as_str())
+                    .and_then(unix_time)
+                    .map(|since| {
+                        HeaderValue::from_str(&format!("@{}", since)).expect("valid header value")
+                    });
+                let sunset = op
+                    .extensions
+                    .get(SUNSET_EXTENSION)
+                    .and_then(|v| v.as_str())
+                    .and_then(http_date)
+                    .and_then(|date| HeaderValue::from_str(&date).ok());
+                let link = op
+                    .extensions
+                    .get(REPLACED_BY_EXTENSION)
+                    .and_then(|v| v.as_str())
+                    .and_then(|id| paths_by_id.get(id))
+                    .filter(|path| !path.contains('{'))
+                    .and_then(|path| {
+                        HeaderValue::from_str(&format!("<{}>; rel=\"successor-version\"", path)).ok()
+                    });
+                if op.deprecated || sunset.is_some() {
+                    let method = Method::from_bytes(method.as_bytes()).expect("valid method");
+                    methods.insert(
+                        method,
+                        DeprecationHeaders {
+                            deprecation,
+                            sunset,
+                            link,
+                        },
+                    );
+                }
+            }
+            if !methods.is_empty() {
+                routes.insert(path, methods);
+            }
+        }
+
+        DeprecationLayer {
+            routes: Arc::new(routes),
+        }
+    }
+}
+
+impl<S> Layer<S> for DeprecationLayer {
+    type Service = DeprecationService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        DeprecationService {
+            inner,
+            routes: self.routes.clone(),
+        }
+    }
+}
+
+/// The [`Service`] created by [`DeprecationLayer`].
+#[derive(Debug, Clone)]
+pub struct DeprecationService<S> {
+    inner: S,
+    routes: Arc<Routes>,
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for DeprecationService<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<ReqBody>) -> Self::Future {
+        let headers = self
+            .routes
+            .find(request.uri().path())
+            .and_then(|methods| methods.get(request.method()).cloned());
+        let future = self.inner.call(request);
+
+        Box::pin(async move {
+            let mut response = future.await?;
+            if let Some(headers) = headers {
+                let response_headers = response.headers_mut();
+                if let Some(deprecation) = headers.deprecation {
+                    response_headers.insert(DEPRECATION, deprecation);
+                }
+                if let Some(sunset) = headers.sunset {
+                    response_headers.insert(SUNSET, sunset);
+                }
+                if let Some(link) = headers.link {
+                    response_headers.append(LINK, link);
+                }
+            }
+            Ok(response)
+        })
+    }
+}
+
+/// Parse a `YYYY-MM-DD` calendar date.
+fn parse_date(date: &str) -> Option<(i64, u32, u32)> {
+    let mut parts = date.splitn(3, '-');
+    let (year, month, day) = (parts.next()?, parts.next()?, parts.next()?);
+    if year.len() != 4 || month.len() != 2 || day.len() != 2 {
+        return None;
+    }
+    let (year, month, day): (i64, u32, u32) = (year.parse().ok()?, month.parse().ok()?, day.parse().ok()?);
+    let leap = (year % 4 == 0 && year % 100 != 0) || year % 400 == 0;
+    let days_in_month = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap => 29,
+        2 => 28,
+        _ => return None,
+    };
+    (1..=days_in_month).contains(&day).then_some((year, month, day))
+}
+
+/// The Unix time of midnight UTC at a `YYYY-MM-DD` date.
+fn unix_time(date: &str) -> Option<i64> {
+    let (year, month, day) = parse_date(date)?;
+    // Days from civil, counting years from March so that leap days end them.
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let year_of_era = y - era * 400;
+    let month = i64::from(month);
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    Some((era * 146_097 + day_of_era - 719_468) * 86_400)
+}
+
+/// Format a `YYYY-MM-DD` date as an HTTP-date at midnight UTC, e.g.
+/// `Fri, 01 Jan 2027 00:00:00 GMT`.
+fn http_date(date: &str) -> Option<String> {
+    const DAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+
+    let (year, month, day) = parse_date(date)?;
+    // Sakamoto's algorithm, 0 is Sunday.
+    let offsets = [0, 3, 2, 5, 0, 3, 5, 1, 4, 6, 2, 4];
+    let y = if month < 3 { year - 1 } else { year };
+    let weekday = (y + y / 4 - y / 100 + y / 400 + offsets[month as usize - 1] + i64::from(day)) % 7;
+
+    Some(format!(
+        "{}, {:02} {} {} 00:00:00 GMT",
+        DAYS[weekday as usize],
+        day,
+        MONTHS[month as usize - 1],
+        year
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use axum::body::Body;
+    use okapi::openapi3::{Operation, PathItem};
+    use tower::{service_fn, ServiceExt};
+
+    fn openapi(extensions: &[(&str, &str)]) -> OpenApi {
+        let mut operation = Operation {
+            deprecated: true,
+            ..Operation::default()
+        };
+        for (key, value) in extensions {
+            operation.extensions.insert(key.to_string(), (*value).into());
+        }
+        let mut openapi = OpenApi::default();
+        openapi.paths.insert(
+            "/users".to_owned(),
+            PathItem {
+                get: Some(operation),
+                ..PathItem::default()
+            },
+        );
+        openapi
+    }
+
+    async fn headers(openapi: &OpenApi) -> axum::http::HeaderMap {
+        let service = DeprecationLayer::new(openapi)
+            .layer(service_fn(|_: Request<Body>| async { Ok::<_, ()>(Response::new(Body::empty())) }));
+        let request = Request::get("/users").body(Body::empty()).unwrap();
+        service.oneshot(request).await.unwrap().headers().clone()
+    }
+
+    #[test]
+    fn dates_must_exist() {
+        assert_eq!(parse_date("2024-02-29"), Some((2024, 2, 29)));
+        assert_eq!(parse_date("2023-02-29"), None);
+        assert_eq!(parse_date("2027-13-01"), None);
+        assert_eq!(parse_date("2027-01-32"), None);
+        assert_eq!(parse_date("27-01-01"), None);
+    }
+
+    #[test]
+    fn dates_convert_to_unix_time() {
+        assert_eq!(unix_time("1970-01-01"), Some(0));
+        assert_eq!(unix_time("2000-03-01"), Some(951_868_800));
+        assert_eq!(unix_time("2027-01-01"), Some(1_798_761_600));
+        assert_eq!(http_date("2027-01-01").as_deref(), Some("Fri, 01 Jan 2027 00:00:00 GMT"));
+    }
+
+    #[tokio::test]
+    async fn deprecation_carries_the_known_date() {
+        let headers = headers(&openapi(&[
+            (DEPRECATED_SINCE_EXTENSION, "2026-06-01"),
+            (SUNSET_EXTENSION, "2027-01-01"),
+        ]))
+        .await;
+        assert_eq!(headers[DEPRECATION], "@1780272000");
+        assert_eq!(headers[SUNSET], "Fri, 01 Jan 2027 00:00:00 GMT");
+    }
+
+    #[tokio::test]
+    async fn deprecation_needs_a_known_date() {
+        let headers = headers(&openapi(&[(SUNSET_EXTENSION, "2027-01-01")])).await;
+        assert!(!headers.contains_key(DEPRECATION));
+        assert!(headers.contains_key(SUNSET));
+    }
+
+    #[tokio::test]
+    async fn successor_links_need_a_concrete_path() {
+        let mut openapi = openapi(&[(REPLACED_BY_EXTENSION, "list_users_v2")]);
+        for (path, id) in [("/v2/users", "list_users_v2"), ("/v2/users/{id}", "get_user_v2")] {
+            let operation = Operation {
+                operation_id: Some(id.to_owned()),
+                ..Operation::default()
+            };
+            openapi.paths.insert(
+                path.to_owned(),
+                PathItem {
+                    get: Some(operation),
+                    ..PathItem::default()
+                },
+            );
+        }
+        assert_eq!(
+            headers(&openapi).await[LINK],
+            "</v2/users>; rel=\"successor-version\""
+        );
+
+        let operation = openapi.paths.get_mut("/users").unwrap().get.as_mut().unwrap();
+        operation
+            .extensions
+            .insert(REPLACED_BY_EXTENSION.to_owned(), "get_user_v2".into());
+        assert!(!headers(&openapi).await.contains_key(LINK));
+    }
+}
@@ -0,0 +1,1045 @@
+//! Detection of the changes between two [`OpenApi`] documents that can break the clients of the
+//! old one.
+//!
+//! ```no_run
+//! # use okapi::openapi3::OpenApi;
+//! # use openapi_rs::diff::diff;
+//! # use openapi_rs::gen::OpenApiGenerator;
+//! # fn check(released: OpenApi, gen: OpenApiGenerator) -> openapi_rs::error::Result<()> {
+//! let report = diff(&released, &gen.into_openapi()?);
+//! println!("{}", report);
+//! assert!(!report.has_breaking_changes());
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Operations, parameters, request bodies, responses, schemas (types, nullability, enums and
+//! properties, recursively) and security requirements are compared. A referenced schema is
+//! compared wherever it is used, so a change to a shared schema is reported under every
+//! parameter, body and property reaching it; a recursive reference stops at the schema it refers
+//! back to. Whether a schema change breaks clients depends on the direction of the data:
+//! narrowing what a request accepts breaks clients sending it, widening what a response returns
+//! breaks clients reading it. The [`DiffReport`] serializes to JSON for CI and displays as a
+//! human-readable report.
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt;
+
+use okapi::openapi3::OpenApi;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::utils::resolve_ref;
+
+const METHODS: [&str; 8] = [
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+/// How a change affects the clients of the old document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Compatible with existing clients.
+    Info,
+    /// Compatible in principle, but clients may rely on the old behavior, e.g. new enum values in
+    /// a response.
+    Warning,
+    /// Existing clients can fail.
+    Breaking,
+}
+
+/// What kind of change was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ChangeKind {
+    OperationAdded,
+    OperationRemoved,
+    ParameterAdded,
+    ParameterRemoved,
+    ParameterBecameRequired,
+    ParameterBecameOptional,
+    RequestBodyAdded,
+    RequestBodyRemoved,
+    RequestBodyBecameRequired,
+    RequestBodyBecameOptional,
+    MediaTypeAdded,
+    MediaTypeRemoved,
+    ResponseAdded,
+    ResponseRemoved,
+    TypeChanged,
+    NullabilityChanged,
+    EnumValueAdded,
+    EnumValueRemoved,
+    PropertyAdded,
+    PropertyRemoved,
+    PropertyBecameRequired,
+    PropertyBecameOptional,
+    SecurityChanged,
+}
+
+/// A difference between two documents.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Change {
+    /// The operation that changed, e.g. `GET /users/{id}`.
+    pub location: String,
+    /// What kind of change this is.
+    pub kind: ChangeKind,
+    /// How the change affects the clients of the old document.
+    pub severity: Severity,
+    /// Human-readable description of the change.
+    pub message: String,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.location, self.message)
+    }
+}
+
+/// Number of changes of each severity.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Summary {
+    pub breaking: usize,
+    pub warning: usize,
+    pub info: usize,
+}
+
+/// The changes between two documents, sorted by location.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DiffReport {
+    /// Number of changes of each severity.
+    pub summary: Summary,
+    /// Every change found.
+    pub changes: Vec<Change>,
+}
+
+impl DiffReport {
+    fn new(mut changes: Vec<Change>) -> Self {
+        changes.sort_by(|a, b| (&a.location, &a.message).cmp(&(&b.location, &b.message)));
+        let count = |severity| changes.iter().filter(|c| c.severity == severity).count();
+        DiffReport {
+            summary: Summary {
+                breaking: count(Severity::Breaking),
+                warning: count(Severity::Warning),
+                info: count(Severity::Info),
+            },
+            changes,
+        }
+    }
+
+    /// Whether any change breaks the clients of the old document.
+    #[must_use]
+    pub fn has_breaking_changes(&self) -> bool {
+        self.summary.breaking > 0
+    }
+
+    /// The highest severity among the changes, `None` when the documents are equivalent.
+    #[must_use]
+    pub fn max_severity(&self) -> Option<Severity> {
+        self.changes.iter().map(|c| c.severity).max()
+    }
+
+    /// The changes of `severity`.
+    pub fn with_severity(&self, severity: Severity) -> impl Iterator<Item = &Change> {
+        self.changes.iter().filter(move |c| c.severity == severity)
+    }
+}
+
+impl fmt::Display for DiffReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.changes.is_empty() {
+            return writeln!(f, "No changes.");
+        }
+        for (severity, title) in [
+            (Severity::Breaking, "Breaking changes"),
+            (Severity::Warning, "Warnings"),
+            (Severity::Info, "Other changes"),
+        ] {
+            let changes: Vec<&Change> = self.with_severity(severity).collect();
+            if changes.is_empty() {
+                continue;
+            }
+            writeln!(f, "{} ({}):", title, changes.len())?;
+            for change in changes {
+                writeln!(f, "  {}", change)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Compare `old` with `new`.
+///
+/// # Panics
+/// When a document cannot be serialized.
+#[must_use]
+pub fn diff(old: &OpenApi, new: &OpenApi) -> DiffReport {
+    let old = serde_json::to_value(old).expect("the specification serializes");
+    let new = serde_json::to_value(new).expect("the specification serializes");
+    let mut differ = Differ {
+        old: &old,
+        new: &new,
+        changes: Vec::new(),
+        comparing: HashSet::new(),
+    };
+
+    let old_operations = operations(&old);
+    let new_operations = operations(&new);
+    for (location, old_operation) in &old_operations {
+        match new_operations.get(location) {
+            Some(new_operation) => differ.operation(location, old_operation, new_operation),
+            None => differ.push(
+                location,
+                ChangeKind::OperationRemoved,
+                Severity::Breaking,
+                "operation removed".to_owned(),
+            ),
+        }
+    }
+    for location in new_operations.keys() {
+        if !old_operations.contains_key(location) {
+            differ.push(
+                location,
+                ChangeKind::OperationAdded,
+                Severity::Info,
+                "operation added".to_owned(),
+            );
+        }
+    }
+
+    DiffReport::new(differ.changes)
+}
+
+/// An operation with the path item it belongs to.
+struct Operation<'a> {
+    path_item: &'a Value,
+    operation: &'a Value,
+}
+
+fn operations(document: &Value) -> BTreeMap<String, Operation<'_>> {
+    let mut operations = BTreeMap::new();
+    let paths = document.get("paths").and_then(Value::as_object);
+    for (path, path_item) in paths.into_iter().flatten() {
+        for method in METHODS {
+            if let Some(operation) = path_item.get(method) {
+                operations.insert(
+                    format!("{} {}", method.to_ascii_uppercase(), path),
+                    Operation {
+                        path_item,
+                        operation,
+                    },
+                );
+            }
+        }
+    }
+    operations
+}
+
+/// What a schema describes: a parameter, request body or response, and the path of the compared
+/// field inside it, e.g. ``request body `address.lines[]` ``.
+struct Subject<'s> {
+    base: &'s str,
+    path: String,
+}
+
+impl<'s> Subject<'s> {
+    fn new(base: &'s str) -> Self {
+        Subject {
+            base,
+            path: String::new(),
+        }
+    }
+
+    fn field(&self, name: &str) -> Self {
+        let path = if self.path.is_empty() {
+            name.to_owned()
+        } else {
+            format!("{}.{}", self.path, name)
+        };
+        Subject {
+            base: self.base,
+            path,
+        }
+    }
+
+    fn items(&self) -> Self {
+        Subject {
+            base: self.base,
+            path: format!("{}[]", self.path),
+        }
+    }
+}
+
+impl fmt::Display for Subject<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.base)
+        } else {
+            write!(f, "{} `{}`", self.base, self.path)
+        }
+    }
+}
+
+/// The direction data flows in, deciding whether narrowing or widening a schema breaks clients.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Request,
+    Response,
+}
+
+impl Direction {
+    /// The severity of narrowing (`true`) or widening (`false`) the accepted values: narrowing
+    /// breaks senders (requests), widening breaks readers (responses).
+    fn severity(self, narrowed: bool) -> Severity {
+        match (self, narrowed) {
+            (Direction::Request, true) | (Direction::Response, false) => Severity::Breaking,
+            _ => Severity::Info,
+        }
+    }
+}
+
+struct Differ<'a> {
+    old: &'a Value,
+    new: &'a Value,
+    changes: Vec<Change>,
+    /// The `(old, new)` pairs of `$ref`s of the schemas enclosing the one being compared. A
+    /// reference back to one of them is recursive, and not followed again.
+    comparing: HashSet<(&'a str, &'a str)>,
+}
+
+impl<'a> Differ<'a> {
+    fn push(&mut self, location: &str, kind: ChangeKind, severity: Severity, message: String) {
+        self.changes.push(Change {
+            location: location.to_owned(),
+            kind,
+            severity,
+            message,
+        });
+    }
+
+    fn operation(&mut self, location: &str, old: &Operation<'a>, new: &Operation<'a>) {
+        self.parameters(location, old, new);
+        self.request_body(location, old.operation, new.operation);
+        self.responses(location, old.operation, new.operation);
+        self.security(location, old.operation, new.operation);
+    }
+
+    fn parameters(&mut self, location: &str, old: &Operation<'a>, new: &Operation<'a>) {
+        let old_parameters = parameters(self.old, old);
+        let new_parameters = parameters(self.new, new);
+
+        for (key, old_parameter) in &old_parameters {
+            let subject = format!("{} parameter `{}`", key.1, key.0);
+            let new_parameter = match new_parameters.get(key) {
+                Some(new_parameter) => new_parameter,
+                None => {
+                    self.push(
+                        location,
+                        ChangeKind::ParameterRemoved,
+                        Severity::Warning,
+                        format!("{} removed", subject),
+                    );
+                    continue;
+                }
+            };
+            match (is_required(old_parameter), is_required(new_parameter)) {
+                (false, true) => self.push(
+                    location,
+                    ChangeKind::ParameterBecameRequired,
+                    Severity::Breaking,
+                    format!("{} became required", subject),
+                ),
+                (true, false) => self.push(
+                    location,
+                    ChangeKind::ParameterBecameOptional,
+                    Severity::Info,
+                    format!("{} became optional", subject),
+                ),
+                _ => {}
+            }
+            if let (Some(old_schema), Some(new_schema)) =
+                (old_parameter.get("schema"), new_parameter.get("schema"))
+            {
+                let subject = Subject::new(&subject);
+                self.schema(location, &subject, Direction::Request, old_schema, new_schema);
+            }
+        }
+        for (key, new_parameter) in &new_parameters {
+            if !old_parameters.contains_key(key) {
+                let (severity, required) = if is_required(new_parameter) {
+                    (Severity::Breaking, "required")
+                } else {
+                    (Severity::Info, "optional")
+                };
+                self.push(
+                    location,
+                    ChangeKind::ParameterAdded,
+                    severity,
+                    format!("{} {} parameter `{}` added", required, key.1, key.0),
+                );
+            }
+        }
+    }
+
+    fn request_body(&mut self, location: &str, old: &'a Value, new: &'a Value) {
+        let old_body = old.get("requestBody").map(|b| resolve_ref(self.old, b));
+        let new_body = new.get("requestBody").map(|b| resolve_ref(self.new, b));
+        let (old_body, new_body) = match (old_body, new_body) {
+            (Some(old_body), Some(new_body)) => (old_body, new_body),
+            (None, Some(new_body)) => {
+                let (severity, required) = if is_required(new_body) {
+                    (Severity::Breaking, "required")
+                } else {
+                    (Severity::Info, "optional")
+                };
+                return self.push(
+                    location,
+                    ChangeKind::RequestBodyAdded,
+                    severity,
+                    format!("{} request body added", required),
+                );
+            }
+            (Some(_), None) => {
+                return self.push(
+                    location,
+                    ChangeKind::RequestBodyRemoved,
+                    Severity::Breaking,
+                    "request body removed".to_owned(),
+                )
+            }
+            (None, None) => return,
+        };
+
+        match (is_required(old_body), is_required(new_body)) {
+            (false, true) => self.push(
+                location,
+                ChangeKind::RequestBodyBecameRequired,
+                Severity::Breaking,
+                "request body became required".to_owned(),
+            ),
+            (true, false) => self.push(
+                location,
+                ChangeKind::RequestBodyBecameOptional,
+                Severity::Info,
+                "request body became optional".to_owned(),
+            ),
+            _ => {}
+        }
+        self.content(
+            location,
+            "request body",
+            Direction::Request,
+            old_body,
+            new_body,
+        );
+    }
+
+    fn responses(&mut self, location: &str, old: &'a Value, new: &'a Value) {
+        let (old_responses, new_responses) = (entries(old, "responses"), entries(new, "responses"));
+
+        for (status, old_response) in &old_responses {
+            if status.starts_with("x-") {
+                continue;
+            }
+            let new_response = match new_responses.get(status) {
+                Some(new_response) => new_response,
+                None => {
+                    self.push(
+                        location,
+                        ChangeKind::ResponseRemoved,
+                        Severity::Breaking,
+                        format!("response `{}` removed", status),
+                    );
+                    continue;
+                }
+            };
+            let old_response = resolve_ref(self.old, old_response);
+            let new_response = resolve_ref(self.new, new_response);
+            let subject = format!("response `{}`", status);
+            self.content(
+                location,
+                &subject,
+                Direction::Response,
+                old_response,
+                new_response,
+            );
+        }
+        for status in new_responses.keys() {
+            if !old_responses.contains_key(status) && !status.starts_with("x-") {
+                self.push(
+                    location,
+                    ChangeKind::ResponseAdded,
+                    Severity::Info,
+                    format!("response `{}` added", status),
+                );
+            }
+        }
+    }
+
+    /// Compare the `content` of request bodies or responses.
+    fn content(
+        &mut self,
+        location: &str,
+        subject: &str,
+        direction: Direction,
+        old: &'a Value,
+        new: &'a Value,
+    ) {
+        let (old_content, new_content) = (entries(old, "content"), entries(new, "content"));
+
+        for (media_type, old_media) in &old_content {
+            match new_content.get(media_type) {
+                Some(new_media) => {
+                    if let (Some(old_schema), Some(new_schema)) =
+                        (old_media.get("schema"), new_media.get("schema"))
+                    {
+                        let subject = Subject::new(subject);
+                        self.schema(location, &subject, direction, old_schema, new_schema);
+                    }
+                }
+                // Clients keep sending the media types they used; readers may only know one.
+                None => self.push(
+                    location,
+                    ChangeKind::MediaTypeRemoved,
+                    match direction {
+                        Direction::Request => Severity::Breaking,
+                        Direction::Response => Severity::Warning,
+                    },
+                    format!("{}: media type `{}` removed", subject, media_type),
+                ),
+            }
+        }
+        for media_type in new_content.keys() {
+            if !old_content.contains_key(media_type) {
+                self.push(
+                    location,
+                    ChangeKind::MediaTypeAdded,
+                    Severity::Info,
+                    format!("{}: media type `{}` added", subject, media_type),
+                );
+            }
+        }
+    }
+
+    /// Compare two schemas of the value described by `subject`, flowing in `direction`.
+    fn schema(
+        &mut self,
+        location: &str,
+        subject: &Subject<'_>,
+        direction: Direction,
+        old: &'a Value,
+        new: &'a Value,
+    ) {
+        let references = match (old.get("$ref"), new.get("$ref")) {
+            (Some(Value::String(old_ref)), Some(Value::String(new_ref))) => {
+                Some((old_ref.as_str(), new_ref.as_str()))
+            }
+            _ => None,
+        };
+        if let Some(references) = references {
+            if !self.comparing.insert(references) {
+                return;
+            }
+        }
+        let (old, new) = (resolve_ref(self.old, old), resolve_ref(self.new, new));
+        self.resolved_schema(location, subject, direction, old, new);
+        if let Some(references) = references {
+            self.comparing.remove(&references);
+        }
+    }
+
+    /// Compare two schemas like [`Self::schema`], once their `$ref`s are resolved.
+    fn resolved_schema(
+        &mut self,
+        location: &str,
+        subject: &Subject<'_>,
+        direction: Direction,
+        old: &'a Value,
+        new: &'a Value,
+    ) {
+        let (old_types, new_types) = (types(old), types(new));
+        if !old_types.is_empty() && old_types != new_types {
+            let severity = if !new_types.is_empty() && new_types.is_subset(&old_types) {
+                direction.severity(true)
+            } else if new_types.is_empty() || new_types.is_superset(&old_types) {
+                direction.severity(false)
+            } else {
+                Severity::Breaking
+            };
+            self.push(
+                location,
+                ChangeKind::TypeChanged,
+                severity,
+                format!(
+                    "{}: type changed from {} to {}",
+                    subject,
+                    describe_types(&old_types),
+                    describe_types(&new_types)
+                ),
+            );
+            // The rest of the schema describes a different kind of value.
+            if severity == Severity::Breaking {
+                return;
+            }
+        }
+
+        match (is_nullable(old), is_nullable(new)) {
+            (true, false) => self.push(
+                location,
+                ChangeKind::NullabilityChanged,
+                direction.severity(true),
+                format!("{}: no longer nullable", subject),
+            ),
+            (false, true) => self.push(
+                location,
+                ChangeKind::NullabilityChanged,
+                direction.severity(false),
+                format!("{}: became nullable", subject),
+            ),
+            _ => {}
+        }
+
+        if let (Some(old_values), Some(new_values)) = (enum_values(old), enum_values(new)) {
+            for value in old_values.difference(&new_values) {
+                self.push(
+                    location,
+                    ChangeKind::EnumValueRemoved,
+                    direction.severity(true),
+                    format!("{}: enum value {} removed", subject, value),
+                );
+            }
+            for value in new_values.difference(&old_values) {
+                self.push(
+                    location,
+                    ChangeKind::EnumValueAdded,
+                    match direction {
+                        Direction::Request => Severity::Info,
+                        Direction::Response => Severity::Warning,
+                    },
+                    format!("{}: enum value {} added", subject, value),
+                );
+            }
+        }
+
+        self.properties(location, subject, direction, old, new);
+
+        if let (Some(old_items), Some(new_items)) = (old.get("items"), new.get("items")) {
+            let subject = subject.items();
+            self.schema(location, &subject, direction, old_items, new_items);
+        }
+    }
+
+    fn properties(
+        &mut self,
+        location: &str,
+        subject: &Subject<'_>,
+        direction: Direction,
+        old: &'a Value,
+        new: &'a Value,
+    ) {
+        let (old_properties, new_properties) =
+            (entries(old, "properties"), entries(new, "properties"));
+        let (old_required, new_required) = (required(old), required(new));
+
+        for (name, old_property) in &old_properties {
+            let property = subject.field(name);
+            let new_property = match new_properties.get(name) {
+                Some(new_property) => new_property,
+                None => {
+                    self.push(
+                        location,
+                        ChangeKind::PropertyRemoved,
+                        match direction {
+                            Direction::Request => Severity::Warning,
+                            Direction::Response => Severity::Breaking,
+                        },
+                        format!("{}: property removed", property),
+                    );
+                    continue;
+                }
+            };
+            match (old_required.contains(*name), new_required.contains(*name)) {
+                (false, true) => self.push(
+                    location,
+                    ChangeKind::PropertyBecameRequired,
+                    direction.severity(true),
+                    format!("{}: became required", property),
+                ),
+                (true, false) => self.push(
+                    location,
+                    ChangeKind::PropertyBecameOptional,
+                    direction.severity(false),
+                    format!("{}: became optional", property),
+                ),
+                _ => {}
+            }
+            self.schema(location, &property, direction, old_property, new_property);
+        }
+        for name in new_properties.keys() {
+            if old_properties.contains_key(name) {
+                continue;
+            }
+            let (severity, required) = match (direction, new_required.contains(*name)) {
+                (Direction::Request, true) => (Severity::Breaking, "required"),
+                (_, true) => (Severity::Info, "required"),
+                (_, false) => (Severity::Info, "optional"),
+            };
+            self.push(
+                location,
+                ChangeKind::PropertyAdded,
+                severity,
+                format!("{}: {} property added", subject.field(name), required),
+            );
+        }
+    }
+
+    fn security(&mut self, location: &str, old: &'a Value, new: &'a Value) {
+        let old_requirements = requirements(self.old, old);
+        let new_requirements = requirements(self.new, new);
+        if old_requirements == new_requirements {
+            return;
+        }
+
+        // A client meeting an old alternative keeps working when some new alternative asks for
+        // a subset of its schemes and scopes. No requirement at all is met by every client.
+        let accepted = |old: &Requirement| {
+            new_requirements.is_empty()
+                || new_requirements.iter().any(|new| {
+                    new.iter().all(|(scheme, scopes)| {
+                        old.get(scheme)
+                            .is_some_and(|old_scopes| scopes.is_subset(old_scopes))
+                    })
+                })
+        };
+        // No old requirement is the anonymous client, meeting only an optional (`{}`) one.
+        let anonymous = [Requirement::new()];
+        let old_requirements = if old_requirements.is_empty() {
+            &anonymous[..]
+        } else {
+            &old_requirements[..]
+        };
+        let rejected: Vec<String> = old_requirements
+            .iter()
+            .filter(|old| !accepted(old))
+            .map(describe_requirement)
+            .collect();
+
+        if rejected.is_empty() {
+            self.push(
+                location,
+                ChangeKind::SecurityChanged,
+                Severity::Info,
+                "security requirements relaxed".to_owned(),
+            );
+        } else {
+            self.push(
+                location,
+                ChangeKind::SecurityChanged,
+                Severity::Breaking,
+                format!(
+                    "security requirements no longer accept {}",
+                    rejected.join(", ")
+                ),
+            );
+        }
+    }
+}
+
+/// The parameters of an operation, including those inherited from its path item, keyed by name
+/// and location.
+fn parameters<'a>(
+    document: &'a Value,
+    operation: &Operation<'a>,
+) -> BTreeMap<(String, String), &'a Value> {
+    [operation.path_item, operation.operation]
+        .into_iter()
+        .filter_map(|item| item.get("parameters")?.as_array())
+        .flatten()
+        .map(|parameter| resolve_ref(document, parameter))
+        .filter_map(|parameter| {
+            let name = parameter.get("name")?.as_str()?.to_owned();
+            let location = parameter.get("in")?.as_str()?.to_owned();
+            Some(((name, location), parameter))
+        })
+        .collect()
+}
+
+/// The entries of the object at `key` in `value`, empty when missing.
+fn entries<'v>(value: &'v Value, key: &str) -> BTreeMap<&'v str, &'v Value> {
+    value
+        .get(key)
+        .and_then(Value::as_object)
+        .map(|object| object.iter().map(|(k, v)| (k.as_str(), v)).collect())
+        .unwrap_or_default()
+}
+
+fn is_required(value: &Value) -> bool {
+    value.get("required").and_then(Value::as_bool) == Some(true)
+}
+
+/// The non-null types of `schema`. `number` includes `integer`, so that `integer` to `number`
+/// widens and the reverse narrows.
+fn types(schema: &Value) -> BTreeSet<&str> {
+    let mut types: BTreeSet<&str> = match schema.get("type") {
+        Some(Value::String(t)) => BTreeSet::from([t.as_str()]),
+        Some(Value::Array(ts)) => ts
+            .iter()
+            .filter_map(Value::as_str)
+            .filter(|t| *t != "null")
+            .collect(),
+        _ => BTreeSet::new(),
+    };
+    if types.contains("number") {
+        types.insert("integer");
+    }
+    types
+}
+
+fn describe_types(types: &BTreeSet<&str>) -> String {
+    if types.is_empty() {
+        return "any".to_owned();
+    }
+    let shown: Vec<&str> = types
+        .iter()
+        .copied()
+        .filter(|t| !(*t == "integer" && types.contains("number")))
+        .collect();
+    format!("`{}`", shown.join(" | "))
+}
+
+fn is_nullable(schema: &Value) -> bool {
+    schema.get("nullable").and_then(Value::as_bool) == Some(true)
+        || matches!(schema.get("type"), Some(Value::Array(ts)) if ts.iter().any(|t| t == "null"))
+}
+
+fn enum_values(schema: &Value) -> Option<BTreeSet<String>> {
+    Some(
+        schema
+            .get("enum")?
+            .as_array()?
+            .iter()
+            .map(Value::to_string)
+            .collect(),
+    )
+}
+
+fn required(schema: &Value) -> HashSet<&str> {
+    schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|required| required.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default()
+}
+
+/// A security requirement: scheme names with their scopes.
+type Requirement = BTreeMap<String, BTreeSet<String>>;
+
+/// The security requirements in effect for `operation`: its own, else those of the document.
+/// An empty list means no authentication; an empty requirement (`{}`) makes it optional.
+fn requirements(document: &Value, operation: &Value) -> Vec<Requirement> {
+    operation
+        .get("security")
+        .or_else(|| document.get("security"))
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_object)
+        .map(|requirement| {
+            requirement
+                .iter()
+                .map(|(scheme, scopes)| {
+                    let scopes = scopes
+                        .as_array()
+                        .into_iter()
+                        .flatten()
+                        .filter_map(Value::as_str)
+                        .map(str::to_owned)
+                        .collect();
+                    (scheme.clone(), scopes)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn describe_requirement(requirement: &Requirement) -> String {
+    if requirement.is_empty() {
+        return "unauthenticated requests".to_owned();
+    }
+    let schemes: Vec<String> = requirement
+        .iter()
+        .map(|(scheme, scopes)| {
+            if scopes.is_empty() {
+                format!("`{}`", scheme)
+            } else {
+                let scopes: Vec<&str> = scopes.iter().map(String::as_str).collect();
+                format!("`{}` ({})", scheme, scopes.join(", "))
+            }
+        })
+        .collect();
+    schemes.join(" + ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    /// A document with `GET /nodes` answering `node` and accepting `security`.
+    fn document(node: Value, security: Value) -> OpenApi {
+        serde_json::from_value(json!({
+            "openapi": "3.0.0",
+            "info": { "title": "diff", "version": "1" },
+            "paths": { "/nodes": { "get": {
+                "operationId": "get_nodes",
+                "security": security,
+                "responses": { "200": {
+                    "description": "OK",
+                    "content": { "application/json": {
+                        "schema": { "$ref": "#/components/schemas/Node" },
+                    } },
+                } },
+            } } },
+            "components": {
+                "schemas": { "Node": node },
+                "securitySchemes": { "key": { "type": "apiKey", "name": "key", "in": "header" } },
+            },
+        }))
+        .unwrap()
+    }
+
+    fn messages(report: &DiffReport) -> Vec<(Severity, String)> {
+        report
+            .changes
+            .iter()
+            .map(|change| (change.severity, change.message.clone()))
+            .collect()
+    }
+
+    fn node(value_type: &str) -> Value {
+        let node = json!({ "$ref": "#/components/schemas/Node" });
+        json!({
+            "type": "object",
+            "properties": {
+                "value": { "type": value_type },
+                "left": node,
+                "right": node,
+                "children": { "type": "array", "items": node },
+            },
+        })
+    }
+
+    #[test]
+    fn recursive_schemas_are_compared_once() {
+        let old = document(node("integer"), json!([]));
+        let new = document(node("string"), json!([]));
+        assert_eq!(
+            messages(&diff(&old, &new)),
+            [(
+                Severity::Breaking,
+                "response `200` `value`: type changed from `integer` to `string`".to_owned()
+            )]
+        );
+        assert!(diff(&old, &old).changes.is_empty());
+    }
+
+    #[test]
+    fn optional_authentication_relaxes_no_authentication() {
+        let anonymous = document(node("integer"), json!([]));
+        let optional = document(node("integer"), json!([{}, { "key": [] }]));
+        let required = document(node("integer"), json!([{ "key": [] }]));
+
+        let relaxed = diff(&anonymous, &optional);
+        assert_eq!(
+            messages(&relaxed),
+            [(Severity::Info, "security requirements relaxed".to_owned())]
+        );
+        assert!(!relaxed.has_breaking_changes());
+        assert!(diff(&anonymous, &required).has_breaking_changes());
+        assert!(diff(&optional, &required).has_breaking_changes());
+        assert!(!diff(&required, &optional).has_breaking_changes());
+        assert!(!diff(&required, &anonymous).has_breaking_changes());
+    }
+
+    #[test]
+    fn widening_a_response_breaks_readers() {
+        let old = document(json!({ "type": "object", "properties": {
+            "state": { "type": "string", "enum": ["on", "off"] },
+            "name": { "type": "string" },
+        }, "required": ["name"] }), json!([]));
+        let new = document(json!({ "type": "object", "properties": {
+            "state": { "type": "string", "enum": ["on", "off", "unknown"] },
+            "name": { "type": "string", "nullable": true },
+        } }), json!([]));
+        let report = diff(&old, &new);
+        assert_eq!(
+            messages(&report),
+            [
+                (Severity::Breaking, "response `200` `name`: became nullable".to_owned()),
+                (Severity::Breaking, "response `200` `name`: became optional".to_owned()),
+                (
+                    Severity::Warning,
+                    "response `200` `state`: enum value \"unknown\" added".to_owned()
+                ),
+            ]
+        );
+        assert_eq!(report.max_severity(), Some(Severity::Breaking));
+    }
+
+    #[test]
+    fn operations_are_added_and_removed() {
+        let old = document(node("integer"), json!([]));
+        let mut new = old.clone();
+        let item = new.paths.remove("/nodes").unwrap();
+        new.paths.insert("/trees".to_owned(), item);
+        let report = diff(&old, &new);
+        assert_eq!(report.summary, Summary { breaking: 1, warning: 0, info: 1 });
+        assert_eq!(
+            report.to_string(),
+            "Breaking changes (1):\n  GET /nodes: operation removed\n\
+             Other changes (1):\n  GET /trees: operation added\n"
+        );
+    }
+
+    #[test]
+    fn shared_schemas_are_reported_wherever_they_are_used() {
+        let address = |zip: &str| {
+            json!({ "type": "object", "properties": { "zip": { "type": zip } } })
+        };
+        let order = |zip: &str| {
+            let mut order = document(json!({ "type": "object" }), json!([]));
+            let schemas = &mut order.components.as_mut().unwrap().schemas;
+            schemas.insert(
+                "Node".to_owned(),
+                serde_json::from_value(json!({
+                    "type": "object",
+                    "properties": {
+                        "billing": { "$ref": "#/components/schemas/Address" },
+                        "shipping": { "$ref": "#/components/schemas/Address" },
+                    },
+                }))
+                .unwrap(),
+            );
+            schemas.insert("Address".to_owned(), serde_json::from_value(address(zip)).unwrap());
+            order
+        };
+
+        let report = diff(&order("integer"), &order("string"));
+        assert_eq!(
+            messages(&report),
+            [
+                (
+                    Severity::Breaking,
+                    "response `200` `billing.zip`: type changed from `integer` to `string`"
+                        .to_owned()
+                ),
+                (
+                    Severity::Breaking,
+                    "response `200` `shipping.zip`: type changed from `integer` to `string`"
+                        .to_owned()
+                ),
+            ]
+        );
+    }
+}
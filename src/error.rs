@@ -2,31 +2,107 @@ use okapi::merge::MergeError;
 use thiserror::Error;
 use std::fmt;
 
+use crate::OpenApiMethod;
+
 /// Type alias for `Result<T, OpenApiError>`.
 pub type Result<T> = std::result::Result<T, OpenApiError>;
 
-/// The error type returned by `rocket_okapi` when something fails.
+/// The error type returned by `openapi_rs` when something fails.
 #[derive(Debug, Clone, Error)]
-pub struct OpenApiError {
-    msg: String,
+pub enum OpenApiError {
+    /// Two handlers were registered for the same path and method.
+    #[error("operation {method} {path} is already defined")]
+    DuplicateOperation {
+        path: String,
+        method: OpenApiMethod,
+    },
+    /// Two operations share the same `operationId`.
+    #[error("operationId `{operation_id}` is used by both {first} and {second}")]
+    DuplicateOperationId {
+        operation_id: String,
+        /// Method and path of the operation registered first.
+        first: String,
+        /// Method and path of the conflicting operation.
+        second: String,
+    },
+    /// Two different examples were added to `components/examples` with the same name.
+    #[error("example `{name}` is already defined with a different value")]
+    ConflictingExample { name: String },
+    /// Two different components of the same kind, such as schemas or security schemes, have the
+    /// same name in merged specifications.
+    #[error("{kind} `{name}` is already defined with a different value")]
+    ConflictingComponent { kind: String, name: String },
+    /// The schemas of two different types have the same name in `components/schemas`.
+    #[error("the schemas of {first} and {second} are both named `{name}`, rename one of the types or change the `schema_naming` setting")]
+    ConflictingSchema {
+        name: String,
+        first: String,
+        second: String,
+    },
+    /// Two webhooks were registered with the same name.
+    #[error("webhook `{name}` is already defined")]
+    DuplicateWebhook { name: String },
+    /// The path of an operation is not a valid path template.
+    #[error("invalid path `{path}`: {reason}")]
+    InvalidPath { path: String, reason: String },
+    /// A specification extension does not start with `x-`.
+    #[error("invalid extension `{key}`: extension names must start with `x-`")]
+    InvalidExtension { key: String },
+    /// The target handler of a link could not be resolved to an operation.
+    #[error("cannot resolve the target `{handler}` of link `{link}`: {reason}")]
+    UnresolvedLink {
+        link: String,
+        handler: String,
+        reason: String,
+    },
+    /// The specification could not be serialized.
+    #[error("failed to serialize the specification: {0}")]
+    Serialize(String),
+    /// A specification fragment could not be read or parsed.
+    #[error("cannot load the specification fragment `{path}`: {message}")]
+    LoadFragment { path: String, message: String },
+    /// Two specifications or components could not be merged.
+    #[error("failed to merge specifications: {0}")]
+    Merge(String),
+    /// The schema, parameters or responses of an operation could not be generated.
+    #[error("failed to generate the documentation of `{context}`: {message}")]
+    Schema { context: String, message: String },
 }
 
 impl OpenApiError {
-    /// Create a new `OpenApiError` containing a message.
+    /// Create an [`OpenApiError::Schema`] for `context` (usually the handler path) from any error.
     #[must_use]
-    pub fn new(msg: String) -> Self {
-        OpenApiError { msg }
+    pub fn schema(context: impl Into<String>, error: impl fmt::Display) -> Self {
+        OpenApiError::Schema {
+            context: context.into(),
+            message: error.to_string(),
+        }
     }
 }
 
-impl fmt::Display for OpenApiError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.msg)
+impl From<MergeError> for OpenApiError {
+    fn from(error: MergeError) -> Self {
+        Self::Merge(error.msg)
     }
 }
 
-impl From<MergeError> for OpenApiError {
-    fn from(error: MergeError) -> Self {
-        Self::new(error.msg)
+/// A problem found while generating the specification that does not prevent generating it, such
+/// as an operation that cannot be represented in OpenAPI 3.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// The path of the operation concerned.
+    pub path: String,
+    /// The method of the operation concerned.
+    pub method: Option<OpenApiMethod>,
+    /// What happened.
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.method {
+            Some(method) => write!(f, "{} {}: {}", method, self.path, self.message),
+            None => write!(f, "{}: {}", self.path, self.message),
+        }
     }
 }
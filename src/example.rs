@@ -0,0 +1,160 @@
+//! Example values of request bodies, responses and parameters, generated from typed values so they
+//! cannot drift from the types.
+//!
+//! Examples come from two sources:
+//! - `#[schemars(example = "path::to::fn")]` on a type or field: the first example of the schema
+//!   becomes the `example` of the JSON bodies and parameters using it,
+//! - the [`OpenApiExample`] trait, used by handlers declared with
+//!   `#[handler(request_examples, response_examples)]`:
+//! ```rust,ignore
+//! impl OpenApiExample for User {
+//!     fn example() -> Self {
+//!         User { id: 1, name: "Ada".into() }
+//!     }
+//!
+//!     fn named_examples() -> Vec<NamedExample<Self>> {
+//!         vec![NamedExample::new("admin", User { id: 2, name: "root".into() })]
+//!     }
+//! }
+//! ```
+//!
+//! Response examples go to the first successful response of the handler, or to the one given by
+//! `response_examples(status = 201)`.
+
+use anyhow::Result;
+use okapi::openapi3::{Example, ExampleValue, MediaType, Object, RefOr, RequestBody, Responses};
+use okapi::Map;
+use schemars::schema::{Schema, SchemaObject};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::gen::OpenApiGenerator;
+use crate::utils::{ensure_not_ref, ensure_status_code_exists, first_success_status};
+
+/// Types providing example values for the documentation.
+pub trait OpenApiExample: Serialize + Sized {
+    /// The main example of the type.
+    fn example() -> Self;
+
+    /// Additional named examples. They are added to `components/examples` and to the `examples`
+    /// of the media types.
+    fn named_examples() -> Vec<NamedExample<Self>> {
+        Vec::new()
+    }
+}
+
+/// A named example value, see [`OpenApiExample::named_examples`].
+#[derive(Debug, Clone)]
+pub struct NamedExample<T> {
+    /// The name of the example in `components/examples`.
+    pub name: String,
+    /// Short description of the example.
+    pub summary: Option<String>,
+    /// The example value.
+    pub value: T,
+}
+
+impl<T> NamedExample<T> {
+    /// Create an example called `name` without summary.
+    pub fn new(name: impl Into<String>, value: T) -> Self {
+        NamedExample {
+            name: name.into(),
+            summary: None,
+            value,
+        }
+    }
+
+    /// Set the summary of the example.
+    #[must_use]
+    pub fn summary(mut self, summary: impl Into<String>) -> Self {
+        self.summary = Some(summary.into());
+        self
+    }
+}
+
+/// The first `examples` value of `schema`, following a reference to the definitions of `gen`.
+#[must_use]
+pub fn schema_example(gen: &OpenApiGenerator, schema: &SchemaObject) -> Option<Value> {
+    let schema = match &schema.reference {
+        Some(reference) => {
+            let name = reference.rsplit('/').next()?;
+            match gen.schema_generator().definitions().get(name)? {
+                Schema::Object(definition) => definition,
+                Schema::Bool(_) => return None,
+            }
+        }
+        None => schema,
+    };
+    schema.metadata.as_ref()?.examples.first().cloned()
+}
+
+/// Set the examples of `T` on every media type of `content`.
+///
+/// The main example is the `example` of the media types, unless `T` has named examples: the
+/// `example` and `examples` fields are mutually exclusive, so it is then added to `examples` as
+/// `default`. Named examples are also registered in `components/examples`.
+pub fn add_examples<T: OpenApiExample>(
+    gen: &mut OpenApiGenerator,
+    content: &mut Map<String, MediaType>,
+) -> Result<()> {
+    let example = serde_json::to_value(T::example())?;
+    let mut named = Map::new();
+    for named_example in T::named_examples() {
+        let example = Example {
+            summary: named_example.summary,
+            description: None,
+            value: ExampleValue::Value(serde_json::to_value(named_example.value)?),
+            extensions: Object::default(),
+        };
+        gen.add_component_example(named_example.name.clone(), example.clone())?;
+        named.insert(named_example.name, example);
+    }
+
+    for media in content.values_mut() {
+        if named.is_empty() {
+            media.example = Some(example.clone());
+            continue;
+        }
+        let examples = media.examples.get_or_insert_with(Map::new);
+        examples.insert(
+            "default".to_owned(),
+            Example {
+                summary: None,
+                description: None,
+                value: ExampleValue::Value(example.clone()),
+                extensions: Object::default(),
+            },
+        );
+        examples.extend(named.clone());
+        media.example = None;
+    }
+    Ok(())
+}
+
+/// Set the examples of `T` on a request body, see [`add_examples`].
+pub fn add_request_examples<T: OpenApiExample>(
+    gen: &mut OpenApiGenerator,
+    request_body: &mut RefOr<RequestBody>,
+) -> Result<()> {
+    match request_body {
+        RefOr::Object(body) => add_examples::<T>(gen, &mut body.content),
+        RefOr::Ref(r) => anyhow::bail!("cannot add examples to the request body `{}`", r.reference),
+    }
+}
+
+/// Set the examples of `T` on the response for `status` (the first successful response when
+/// `None`), see [`add_examples`].
+pub fn add_response_examples<T: OpenApiExample>(
+    gen: &mut OpenApiGenerator,
+    responses: &mut Responses,
+    status: Option<u16>,
+) -> Result<()> {
+    let status = status.unwrap_or_else(|| first_success_status(responses));
+    let response = ensure_not_ref(ensure_status_code_exists(responses, status))?;
+    anyhow::ensure!(
+        !response.content.is_empty(),
+        "the response for status {} has no content to add examples to",
+        status
+    );
+    add_examples::<T>(gen, &mut response.content)
+}
@@ -0,0 +1,84 @@
+//! Specification extensions (`x-*` fields).
+//!
+//! Extensions are added at every level of the document:
+//! - operations: `#[handler(extension(key = "x-owner", value = json!("payments")))]`, or
+//!   `#[handler(extensions = "GatewayIntegration")]` with a type implementing
+//!   [`OpenApiExtensions`],
+//! - parameters: [`ParameterBuilder::extension`](crate::ParameterBuilder::extension),
+//! - responses: [`add_response_extension`](crate::utils::add_response_extension),
+//! - components and the document itself:
+//!   [`OpenApiGenerator::add_components_extension`] and [`OpenApiGenerator::add_extension`].
+
+use anyhow::Result;
+use okapi::openapi3::Object;
+
+use crate::error::OpenApiError;
+use crate::gen::OpenApiGenerator;
+
+/// Types contributing extensions to the operations declaring them with
+/// `#[handler(extensions = "Type")]`.
+///
+/// ```rust,ignore
+/// struct GatewayIntegration;
+///
+/// impl OpenApiExtensions for GatewayIntegration {
+///     fn extensions(_: &mut OpenApiGenerator) -> anyhow::Result<Object> {
+///         let mut extensions = Object::default();
+///         extensions.insert("x-amazon-apigateway-integration".into(), json!({ "type": "http_proxy" }));
+///         Ok(extensions)
+///     }
+/// }
+/// ```
+pub trait OpenApiExtensions {
+    /// The extensions to add. Keys must start with `x-`.
+    fn extensions(gen: &mut OpenApiGenerator) -> Result<Object>;
+}
+
+/// Check that `key` is a valid extension name, i.e. starts with `x-`.
+pub fn check_extension_key(key: &str) -> std::result::Result<(), OpenApiError> {
+    if key.starts_with("x-") {
+        Ok(())
+    } else {
+        Err(OpenApiError::InvalidExtension {
+            key: key.to_owned(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use okapi::openapi3::Responses;
+    use serde_json::json;
+
+    use crate::settings::OpenApiSettings;
+    use crate::utils::add_response_extension;
+
+    #[test]
+    fn extension_keys_must_start_with_x() {
+        assert!(check_extension_key("x-owner").is_ok());
+        for key in ["owner", "X-owner", "-x-owner", ""] {
+            assert!(matches!(
+                check_extension_key(key),
+                Err(OpenApiError::InvalidExtension { key: invalid }) if invalid == key
+            ));
+        }
+    }
+
+    #[test]
+    fn every_level_rejects_other_keys() {
+        let mut gen = OpenApiGenerator::new(&OpenApiSettings::new());
+        assert!(gen.add_extension("owner", json!("payments")).is_err());
+        assert!(gen.add_components_extension("owner", json!("payments")).is_err());
+        let mut responses = Responses::default();
+        assert!(add_response_extension(&mut responses, 200, "owner", json!(1)).is_err());
+        assert!(responses.responses.is_empty());
+
+        gen.add_extension("x-owner", json!("payments")).unwrap();
+        gen.add_components_extension("x-owner", json!("payments")).unwrap();
+        let openapi = gen.into_openapi().unwrap();
+        assert_eq!(openapi.extensions["x-owner"], "payments");
+        assert_eq!(openapi.components.unwrap().extensions["x-owner"], "payments");
+    }
+}
@@ -1,13 +1,27 @@
+use crate::callback::callback_operation;
+use crate::error::{Diagnostic, OpenApiError, Result};
+use crate::extension::check_extension_key;
+use crate::link::HANDLER_EXTENSION;
+use crate::parameter::openapi_path;
+use crate::utils::{drop_misread_extensions, load_openapi, DESCRIPTIONS_EXTENSION};
+use crate::schema_names::{RenameReferences, SchemaNames};
+use crate::validate::{self, ValidationError};
 use crate::OpenApiMethod;
 
-use super::settings::OpenApiSettings;
+use super::settings::{KeyOrder, OpenApiSettings, OpenApiVersion};
 use super::OperationInfo;
-use okapi::openapi3::{Components, OpenApi, Operation, PathItem, RefOr, SecurityScheme};
+use okapi::openapi3::{
+    Callback, Components, Example, Object, OpenApi, Operation, PathItem, RefOr, SecurityScheme,
+    Tag,
+};
 use okapi::{Map};
 use schemars::gen::SchemaGenerator;
-use schemars::schema::SchemaObject;
+use schemars::schema::{Schema, SchemaObject};
+use schemars::visit::Visitor;
 use schemars::{JsonSchema, MapEntry};
-use std::collections::HashMap;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
 
 /// A struct that visits all `rocket::Route`s, and aggregates information about them.
 #[derive(Debug, Clone)]
@@ -15,7 +29,20 @@ pub struct OpenApiGenerator {
     settings: OpenApiSettings,
     schema_generator: SchemaGenerator,
     security_schemes: Map<String, SecurityScheme>,
-    operations: Map<String, HashMap<OpenApiMethod, Operation>>,
+    operations: Map<String, BTreeMap<OpenApiMethod, Operation>>,
+    diagnostics: Vec<Diagnostic>,
+    group_tags: Vec<String>,
+    extensions: Object,
+    components_extensions: Object,
+    webhooks: Map<String, Operation>,
+    handler_operation_ids: Map<String, String>,
+    examples: Map<String, Example>,
+    /// Components of merged fragments that the generator does not produce itself.
+    components: Components,
+    /// Path-level fields of merged fragments, such as shared parameters, by OpenAPI path.
+    path_items: Map<String, PathItem>,
+    tags: Vec<Tag>,
+    schema_names: SchemaNames,
 }
 
 impl OpenApiGenerator {
@@ -23,45 +50,404 @@ impl OpenApiGenerator {
     #[must_use]
     pub fn new(settings: &OpenApiSettings) -> Self {
         OpenApiGenerator {
-            schema_generator: settings.schema_settings.clone().into_generator(),
+            schema_generator: settings
+                .openapi_version
+                .schema_settings(settings.schema_settings.clone())
+                .into_generator(),
             settings: settings.clone(),
             security_schemes: Map::default(),
             operations: Map::default(),
+            diagnostics: Vec::new(),
+            group_tags: Vec::new(),
+            extensions: Object::default(),
+            components_extensions: Object::default(),
+            webhooks: Map::default(),
+            handler_operation_ids: Map::default(),
+            examples: Map::default(),
+            components: Components::default(),
+            path_items: Map::default(),
+            tags: Vec::new(),
+            schema_names: SchemaNames::default(),
         }
     }
 
+    /// Obtain the settings this generator was created with.
+    #[must_use]
+    pub fn settings(&self) -> &OpenApiSettings {
+        &self.settings
+    }
+
     /// Adds/Replace a security scheme to the generated output
     pub fn add_security_scheme(&mut self, name: String, scheme: SecurityScheme) {
         self.security_schemes.insert(name, scheme);
     }
 
+    /// Add or replace a specification extension at the root of the document.
+    ///
+    /// Fails when `key` does not start with `x-`.
+    pub fn add_extension(&mut self, key: impl Into<String>, value: Value) -> Result<()> {
+        let key = key.into();
+        check_extension_key(&key)?;
+        self.extensions.insert(key, value);
+        Ok(())
+    }
+
+    /// Add or replace a specification extension of the `components` object.
+    ///
+    /// Fails when `key` does not start with `x-`.
+    pub fn add_components_extension(&mut self, key: impl Into<String>, value: Value) -> Result<()> {
+        let key = key.into();
+        check_extension_key(&key)?;
+        self.components_extensions.insert(key, value);
+        Ok(())
+    }
+
+    /// Add a named example to `components/examples`.
+    ///
+    /// Adding the same example twice is allowed, but fails when another example has the same name.
+    pub fn add_component_example(&mut self, name: impl Into<String>, example: Example) -> Result<()> {
+        match self.examples.entry(name.into()) {
+            MapEntry::Occupied(e) if *e.get() != example => Err(OpenApiError::ConflictingExample {
+                name: e.key().clone(),
+            }),
+            MapEntry::Occupied(_) => Ok(()),
+            MapEntry::Vacant(e) => {
+                e.insert(example);
+                Ok(())
+            }
+        }
+    }
+
+    /// Register the outgoing webhook `name`, a `POST` of a JSON `T` to a URL registered out of
+    /// band.
+    ///
+    /// Webhooks are rendered as `webhooks` with [`OpenApiVersion::V3_1`], and as
+    /// `components/callbacks` called with the
+    /// [`webhook_url_expression`](OpenApiSettings::webhook_url_expression) of the settings with
+    /// [`OpenApiVersion::V3_0`].
+    ///
+    /// Fails when a webhook with the same name was already registered.
+    pub fn add_webhook<T: ?Sized + JsonSchema>(
+        &mut self,
+        name: impl Into<String>,
+        description: Option<String>,
+    ) -> Result<()> {
+        let name = name.into();
+        if self.webhooks.contains_key(&name) {
+            return Err(OpenApiError::DuplicateWebhook { name });
+        }
+        let operation = callback_operation::<T>(self, description)
+            .map_err(|e| OpenApiError::schema(name.clone(), e))?;
+        self.webhooks.insert(name, operation);
+        Ok(())
+    }
+
     /// Add a new `HTTP Method` to the collection of endpoints in the `OpenApiGenerator`.
-    pub fn add_operation(&mut self, mut op: OperationInfo) {
-        if let Some(op_id) = op.operation.operation_id {
-            // TODO do this outside add_operation
-            op.operation.operation_id = Some(op_id.trim_start_matches(':').replace("::", "_"));
-        }
-        match self.operations.entry(op.path) {
-            MapEntry::Occupied(mut e) => {
-                let map = e.get_mut();
-                if map.insert(op.method.clone(), op.operation).is_some() {
-                    // This will trow a warning if 2 routes have the same path and method
-                    // This is allowed by Rocket when a ranking is given for example: `#[get("/user", rank = 2)]`
-                    // See: https://rocket.rs/v0.4/guide/requests/#forwarding
-                    println!("Warning: Operation replaced for {}:{}", op.method, e.key());
-                }
+    ///
+    /// The tags of enclosing [`Self::with_tag`] calls are added to the operation. When it still
+    /// has no tag, one is inferred with the [`TagInference`](crate::settings::TagInference) of the
+    /// settings. When the operation has no `operationId`, one is derived from `op.handler` with the
+    /// [`OperationIdStrategy`](crate::settings::OperationIdStrategy) of the settings.
+    ///
+    /// Fails when the path is not a valid path template, or when an operation was already added
+    /// for the same path and method.
+    pub fn add_operation(&mut self, mut op: OperationInfo) -> Result<()> {
+        validate_path(&op.path)?;
+
+        let responses = &mut op.operation.responses;
+        for response in responses.responses.values_mut().chain(&mut responses.default) {
+            if let RefOr::Object(response) = response {
+                response.extensions.remove(DESCRIPTIONS_EXTENSION);
             }
-            MapEntry::Vacant(e) => {
-                let mut map = HashMap::new();
-                map.insert(op.method, op.operation);
-                e.insert(map);
+        }
+
+        if op.method == OpenApiMethod::Connect {
+            self.diagnostics.push(Diagnostic {
+                path: op.path,
+                method: Some(op.method),
+                message: "CONNECT operations cannot be represented in OpenAPI 3 and are left out"
+                    .to_owned(),
+            });
+            return Ok(());
+        }
+
+        for tag in &self.group_tags {
+            if !op.operation.tags.contains(tag) {
+                op.operation.tags.push(tag.clone());
             }
-        };
+        }
+        if op.operation.tags.is_empty() {
+            let tag = self
+                .settings
+                .tag_inference
+                .infer(&op.path, op.handler.as_deref());
+            op.operation.tags.extend(tag);
+        }
+
+        if op.operation.operation_id.is_none() {
+            if let Some(handler) = &op.handler {
+                op.operation.operation_id = Some(
+                    self.settings
+                        .operation_id_strategy
+                        .operation_id(handler, &op.operation.tags),
+                );
+            }
+        }
+        if let (Some(handler), Some(id)) = (&op.handler, &op.operation.operation_id) {
+            self.handler_operation_ids.insert(handler.clone(), id.clone());
+        }
+        self.insert_operation(op.path, op.method, op.operation)
+    }
+
+    /// Add `operation` at `path`, which may be an axum path or an OpenAPI path template.
+    fn insert_operation(
+        &mut self,
+        path: String,
+        method: OpenApiMethod,
+        operation: Operation,
+    ) -> Result<()> {
+        let template = openapi_path(&path);
+        let key = self
+            .operations
+            .keys()
+            .find(|key| openapi_path(key) == template)
+            .cloned()
+            .unwrap_or(path);
+        let map = self.operations.entry(key).or_default();
+        if map.contains_key(&method) {
+            return Err(OpenApiError::DuplicateOperation {
+                path: template,
+                method,
+            });
+        }
+        map.insert(method, operation);
+        Ok(())
+    }
+
+    /// Merge the operations, schemas and components of `other`, e.g. the generator of another
+    /// crate, into this generator. The settings of `other` are ignored.
+    ///
+    /// Fails, leaving this generator unchanged, when both define the same path and method, the
+    /// same `operationId`, or different components with the same name.
+    pub fn merge(&mut self, other: OpenApiGenerator) -> Result<()> {
+        self.merge_nested("", other)
+    }
+
+    /// Merge `other` like [`Self::merge`], with `prefix` prepended to its paths, e.g. the path
+    /// its router is nested at: `gen.merge_nested("/billing", billing_gen)?`.
+    pub fn merge_nested(&mut self, prefix: &str, mut other: OpenApiGenerator) -> Result<()> {
+        let mut merged = self.clone();
+        for (name, schema) in other.schema_generator.take_definitions() {
+            insert_component(
+                merged.schema_generator.definitions_mut(),
+                "schema",
+                name,
+                schema,
+            )?;
+        }
+        for (name, scheme) in other.security_schemes {
+            insert_component(&mut merged.security_schemes, "security scheme", name, scheme)?;
+        }
+        for (name, example) in other.examples {
+            merged.add_component_example(name, example)?;
+        }
+        for (name, webhook) in other.webhooks {
+            if merged.webhooks.contains_key(&name) {
+                return Err(OpenApiError::DuplicateWebhook { name });
+            }
+            merged.webhooks.insert(name, webhook);
+        }
+        for (key, value) in other.extensions {
+            insert_component(&mut merged.extensions, "extension", key, value)?;
+        }
+        for (key, value) in other.components_extensions {
+            insert_component(
+                &mut merged.components_extensions,
+                "components extension",
+                key,
+                value,
+            )?;
+        }
+        merged.schema_names.merge(other.schema_names);
+        merged.handler_operation_ids.extend(other.handler_operation_ids);
+        merged
+            .diagnostics
+            .extend(other.diagnostics.into_iter().map(|diagnostic| Diagnostic {
+                path: nested_path(prefix, &diagnostic.path),
+                ..diagnostic
+            }));
+
+        for (path, map) in other.operations {
+            let path = nested_path(prefix, &path);
+            validate_path(&path)?;
+            for (method, operation) in map {
+                merged.insert_operation(path.clone(), method, operation)?;
+            }
+        }
+        merged.merge_fragment_parts(prefix, other.path_items, other.components, other.tags)?;
+        merged.check_operation_ids()?;
+        self.replace(merged);
+        Ok(())
+    }
+
+    /// Merge a hand-written specification fragment, with `prefix` prepended to its paths.
+    ///
+    /// The paths, components, tags and root extensions of `fragment` are merged; its `info`,
+    /// `servers` and `security` are ignored. Operations are added as written: no tag is inferred
+    /// and no `operationId` derived.
+    ///
+    /// Fails, leaving this generator unchanged, on the same conflicts as [`Self::merge`], and on
+    /// root fields of `fragment` that are not `x-` extensions, such as the `webhooks` of OpenAPI
+    /// 3.1.
+    pub fn merge_fragment(&mut self, prefix: &str, mut fragment: OpenApi) -> Result<()> {
+        drop_misread_extensions(&mut fragment);
+        let mut merged = self.clone();
+        for (key, value) in fragment.extensions {
+            // Also catches fields okapi does not know, such as the `webhooks` of OpenAPI 3.1
+            check_extension_key(&key)?;
+            insert_component(&mut merged.extensions, "extension", key, value)?;
+        }
+
+        let mut path_items = Map::new();
+        for (path, mut path_item) in fragment.paths {
+            let path = nested_path(prefix, &path);
+            validate_path(&path)?;
+            for (method, operation) in take_operations(&mut path_item) {
+                merged.insert_operation(path.clone(), method, operation)?;
+            }
+            if path_item != PathItem::default() {
+                insert_component(&mut path_items, "path item", openapi_path(&path), path_item)?;
+            }
+        }
+        let components = fragment.components.unwrap_or_default();
+        merged.merge_fragment_parts("", path_items, components, fragment.tags)?;
+        merged.check_operation_ids()?;
+        self.replace(merged);
+        Ok(())
+    }
+
+    /// Load a specification fragment from a JSON file, or a YAML file with the `yaml` feature,
+    /// and merge it like [`Self::merge_fragment`].
+    pub fn merge_fragment_file(&mut self, prefix: &str, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let fragment = load_openapi(path).map_err(|message| OpenApiError::LoadFragment {
+            path: path.display().to_string(),
+            message,
+        })?;
+        self.merge_fragment(prefix, fragment)
     }
 
-    /// Returns a JSON Schema object for the type `T`.
+    /// Replace this generator by `merged`, a clone it was merged into, keeping the types of the
+    /// definitions known to the schema generator, which cloning it forgets.
+    fn replace(&mut self, mut merged: OpenApiGenerator) {
+        let definitions = merged.schema_generator.take_definitions();
+        merged.schema_generator = std::mem::take(&mut self.schema_generator);
+        *merged.schema_generator.definitions_mut() = definitions;
+        *self = merged;
+    }
+
+    /// Merge the path items, components and tags kept from fragments.
+    fn merge_fragment_parts(
+        &mut self,
+        prefix: &str,
+        path_items: Map<String, PathItem>,
+        components: Components,
+        tags: Vec<Tag>,
+    ) -> Result<()> {
+        for (path, path_item) in path_items {
+            insert_component(
+                &mut self.path_items,
+                "path item",
+                openapi_path(&nested_path(prefix, &path)),
+                path_item,
+            )?;
+        }
+
+        for (name, schema) in components.schemas {
+            self.schema_names.add_fragment(name.clone());
+            insert_component(
+                self.schema_generator.definitions_mut(),
+                "schema",
+                name,
+                Schema::Object(schema),
+            )?;
+        }
+        let into = &mut self.components;
+        for (name, response) in components.responses {
+            insert_component(&mut into.responses, "response", name, response)?;
+        }
+        for (name, parameter) in components.parameters {
+            insert_component(&mut into.parameters, "parameter", name, parameter)?;
+        }
+        for (name, example) in components.examples {
+            insert_component(&mut into.examples, "example", name, example)?;
+        }
+        for (name, body) in components.request_bodies {
+            insert_component(&mut into.request_bodies, "request body", name, body)?;
+        }
+        for (name, header) in components.headers {
+            insert_component(&mut into.headers, "header", name, header)?;
+        }
+        for (name, scheme) in components.security_schemes {
+            insert_component(&mut into.security_schemes, "security scheme", name, scheme)?;
+        }
+        for (name, link) in components.links {
+            insert_component(&mut into.links, "link", name, link)?;
+        }
+        for (name, callback) in components.callbacks {
+            insert_component(&mut into.callbacks, "callback", name, callback)?;
+        }
+        for (key, value) in components.extensions {
+            insert_component(&mut into.extensions, "components extension", key, value)?;
+        }
+
+        for tag in tags {
+            match self.tags.iter().find(|t| t.name == tag.name) {
+                Some(existing) if *existing != tag => {
+                    return Err(OpenApiError::ConflictingComponent {
+                        kind: "tag".to_owned(),
+                        name: tag.name,
+                    })
+                }
+                Some(_) => {}
+                None => self.tags.push(tag),
+            }
+        }
+        Ok(())
+    }
+
+    /// Tag every operation added by `f` with `tag`, e.g. all the routes of a nested router.
+    ///
+    /// ```rust,ignore
+    /// gen.with_tag("users", |gen| {
+    ///     create_spec("/users", gen)?;
+    ///     get_spec("/users/:id", gen)
+    /// })?;
+    /// ```
+    pub fn with_tag<F>(&mut self, tag: impl Into<String>, f: F) -> Result<()>
+    where
+        F: FnOnce(&mut Self) -> Result<()>,
+    {
+        self.group_tags.push(tag.into());
+        let result = f(self);
+        self.group_tags.pop();
+        result
+    }
+
+    /// Problems found while adding operations that did not prevent generating the
+    /// specification.
+    #[must_use]
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// Returns a JSON Schema object for the type `T`, a reference for types with a definition in
+    /// `components/schemas`, named according to [`OpenApiSettings::schema_naming`] when the
+    /// specification is generated.
     pub fn json_schema<T: ?Sized + JsonSchema>(&mut self) -> SchemaObject {
-        self.schema_generator.subschema_for::<T>().into()
+        let schema = self.schema_generator.subschema_for::<T>();
+        self.schema_names.record::<T>(&mut self.schema_generator, &schema);
+        schema.into()
     }
 
     /// Obtain the internal `SchemaGenerator` object.
@@ -76,15 +462,56 @@ impl OpenApiGenerator {
     }
 
     /// Generate an `OpenApi` specification for all added operations.
-    #[must_use]
-    pub fn into_openapi(self) -> OpenApi {
+    ///
+    /// Paths and components are ordered according to [`OpenApiSettings::key_order`].
+    ///
+    /// Fails when two operations share the same `operationId`, when the schemas of two different
+    /// types have the same name, or when the target of a link is not registered.
+    pub fn into_openapi(mut self) -> Result<OpenApi> {
+        self.check_operation_ids()?;
+        self.resolve_links()?;
+
         let mut schema_generator = self.schema_generator;
-        let mut schemas = schema_generator.take_definitions();
+        let definitions = schema_generator.take_definitions();
+        let names = self
+            .schema_names
+            .names(&self.settings.schema_naming, &definitions)?;
+        let mut renamer = RenameReferences {
+            definitions_path: &schema_generator.settings().definitions_path,
+            names: &names,
+        };
+        let mut schemas = Map::new();
+        for (name, mut schema) in definitions {
+            renamer.visit_schema(&mut schema);
+            schemas.insert(names[&name].clone(), schema);
+        }
+        for operation in self.operations.values_mut().flat_map(BTreeMap::values_mut) {
+            renamer.visit_operation(operation);
+        }
+        self.webhooks.values_mut().for_each(|webhook| renamer.visit_operation(webhook));
+        self.path_items.values_mut().for_each(|item| renamer.visit_path_item(item));
+        renamer.visit_components(&mut self.components);
 
-        // Add the security schemes
-        let mut schemes: Map<String, RefOr<SecurityScheme>> = Default::default();
-        for (name, schema) in self.security_schemes {
-            schemes.insert(name, schema.into());
+        // Add the security schemes and examples next to those of merged fragments
+        let mut components = self.components;
+        for (name, scheme) in self.security_schemes {
+            insert_component(
+                &mut components.security_schemes,
+                "security scheme",
+                name,
+                scheme.into(),
+            )?;
+        }
+        for (name, example) in self.examples {
+            insert_component(
+                &mut components.examples,
+                "example",
+                name,
+                RefOr::Object(example),
+            )?;
+        }
+        for (key, value) in self.components_extensions {
+            insert_component(&mut components.extensions, "components extension", key, value)?;
         }
 
         for visitor in schema_generator.visitors_mut() {
@@ -93,29 +520,206 @@ impl OpenApiGenerator {
             }
         }
 
-        OpenApi {
-            openapi: "3.0.0".to_owned(),
+        let mut extensions = self.extensions;
+        let key_order = self.settings.key_order;
+        let mut callbacks = components.callbacks;
+        let mut webhooks = serde_json::Map::new();
+        for (name, operation) in ordered(self.webhooks, key_order) {
+            let path_item = PathItem {
+                post: Some(operation),
+                ..PathItem::default()
+            };
+            match self.settings.openapi_version {
+                OpenApiVersion::V3_0 => {
+                    let mut callback = Callback::default();
+                    callback
+                        .callbacks
+                        .insert(self.settings.webhook_url_expression.clone(), path_item);
+                    if callbacks.contains_key(&name) {
+                        return Err(OpenApiError::DuplicateWebhook { name });
+                    }
+                    callbacks.insert(name, RefOr::Object(callback));
+                }
+                OpenApiVersion::V3_1 => {
+                    let path_item = serde_json::to_value(path_item)
+                        .map_err(|e| OpenApiError::schema(name.clone(), e))?;
+                    webhooks.insert(name, path_item);
+                }
+            }
+        }
+        // Extensions all start with `x-`, so they cannot hold `webhooks` already
+        if !webhooks.is_empty() {
+            extensions.insert("webhooks".to_owned(), Value::Object(webhooks));
+        }
+
+        Ok(OpenApi {
+            openapi: self.settings.openapi_version.as_str().to_owned(),
             paths: {
                 let mut paths = Map::new();
-                for (path, map) in self.operations {
+                let mut path_items = self.path_items;
+                for (path, map) in ordered(self.operations, key_order) {
+                    let path = openapi_path(&path);
                     for (method, op) in map {
-                        let path_item = paths.entry(path.clone()).or_default();
+                        let path_item = paths
+                            .entry(path.clone())
+                            .or_insert_with(|| path_items.remove(&path).unwrap_or_default());
                         set_operation(path_item, method, op);
                     }
                 }
-                paths
+                paths.extend(path_items);
+                ordered(paths, key_order).collect()
             },
             components: Some(Components {
-                schemas: schemas.into_iter().map(|(k, v)| (k, v.into())).collect(),
-                security_schemes: schemes,
-                ..Default::default()
+                schemas: ordered(schemas, key_order)
+                    .map(|(k, v)| (k, v.into()))
+                    .collect(),
+                responses: ordered(components.responses, key_order).collect(),
+                parameters: ordered(components.parameters, key_order).collect(),
+                examples: ordered(components.examples, key_order).collect(),
+                request_bodies: ordered(components.request_bodies, key_order).collect(),
+                headers: ordered(components.headers, key_order).collect(),
+                security_schemes: ordered(components.security_schemes, key_order).collect(),
+                links: ordered(components.links, key_order).collect(),
+                callbacks: ordered(callbacks, key_order).collect(),
+                extensions: components.extensions,
             }),
+            tags: self.tags,
+            extensions,
             ..OpenApi::default()
+        })
+    }
+
+    /// Generate the specification and check it with [`validate::validate`], without consuming the
+    /// generator.
+    ///
+    /// Fails when the specification cannot be generated at all, see [`Self::into_openapi`].
+    pub fn validate(&self) -> Result<Vec<ValidationError>> {
+        Ok(validate::validate(&self.clone().into_openapi()?))
+    }
+
+    /// Replace the handler paths of links by the `operationId` of the targets.
+    fn resolve_links(&mut self) -> Result<()> {
+        let ids = &self.handler_operation_ids;
+        for op in self.operations.values_mut().flat_map(BTreeMap::values_mut) {
+            let links = op
+                .responses
+                .responses
+                .values_mut()
+                .filter_map(|response| match response {
+                    RefOr::Object(response) => Some(&mut response.links),
+                    RefOr::Ref(_) => None,
+                })
+                .flat_map(|links| links.iter_mut());
+            for (name, link) in links {
+                let link = match link {
+                    RefOr::Object(link) => link,
+                    RefOr::Ref(_) => continue,
+                };
+                let handler = match link.extensions.remove(HANDLER_EXTENSION) {
+                    Some(Value::String(handler)) => handler,
+                    _ => continue,
+                };
+                let unresolved = |reason: &str| OpenApiError::UnresolvedLink {
+                    link: name.clone(),
+                    handler: handler.clone(),
+                    reason: reason.to_owned(),
+                };
+
+                let id = match ids.get(&handler) {
+                    Some(id) => id,
+                    None => {
+                        let suffix = format!("::{}", handler);
+                        let mut candidates = ids.iter().filter(|(path, _)| path.ends_with(&suffix));
+                        match (candidates.next(), candidates.next()) {
+                            (Some((_, id)), None) => id,
+                            (None, _) => return Err(unresolved("no operation was added for it")),
+                            (Some(_), Some(_)) => {
+                                return Err(unresolved("several handlers match this path"))
+                            }
+                        }
+                    }
+                };
+                link.operation_id = Some(id.clone());
+            }
         }
+        Ok(())
+    }
+
+    fn check_operation_ids(&self) -> Result<()> {
+        let mut seen: HashMap<&str, (&OpenApiMethod, &str)> = HashMap::new();
+        for (path, map) in &self.operations {
+            for (method, op) in map {
+                if let Some(id) = &op.operation_id {
+                    if let Some((other_method, other_path)) = seen.insert(id, (method, path)) {
+                        return Err(OpenApiError::DuplicateOperationId {
+                            operation_id: id.clone(),
+                            first: format!("{} {}", other_method, other_path),
+                            second: format!("{} {}", method, path),
+                        });
+                    }
+                }
+            }
+        }
+        Ok(())
     }
 }
 
-fn set_operation(path_item: &mut PathItem, method: OpenApiMethod, op: Operation) {
+/// Insert the component `name` of `kind` into `map`. Inserting the same value twice is allowed.
+fn insert_component<T: PartialEq>(
+    map: &mut Map<String, T>,
+    kind: &str,
+    name: String,
+    value: T,
+) -> Result<()> {
+    match map.entry(name) {
+        MapEntry::Occupied(e) if *e.get() != value => Err(OpenApiError::ConflictingComponent {
+            kind: kind.to_owned(),
+            name: e.key().clone(),
+        }),
+        MapEntry::Occupied(_) => Ok(()),
+        MapEntry::Vacant(e) => {
+            e.insert(value);
+            Ok(())
+        }
+    }
+}
+
+/// `path` nested under `prefix`, as axum's `Router::nest` does.
+fn nested_path(prefix: &str, path: &str) -> String {
+    let prefix = prefix.trim_end_matches('/');
+    match path {
+        "/" if !prefix.is_empty() => prefix.to_owned(),
+        _ => format!("{}{}", prefix, path),
+    }
+}
+
+/// Remove the operations of `path_item`, leaving its path-level fields.
+fn take_operations(path_item: &mut PathItem) -> Vec<(OpenApiMethod, Operation)> {
+    [
+        (OpenApiMethod::Get, path_item.get.take()),
+        (OpenApiMethod::Put, path_item.put.take()),
+        (OpenApiMethod::Post, path_item.post.take()),
+        (OpenApiMethod::Delete, path_item.delete.take()),
+        (OpenApiMethod::Options, path_item.options.take()),
+        (OpenApiMethod::Head, path_item.head.take()),
+        (OpenApiMethod::Patch, path_item.patch.take()),
+        (OpenApiMethod::Trace, path_item.trace.take()),
+    ]
+    .into_iter()
+    .filter_map(|(method, operation)| Some((method, operation?)))
+    .collect()
+}
+
+/// The entries of `map` in key order with [`KeyOrder::Sorted`], in insertion order otherwise.
+fn ordered<V>(map: Map<String, V>, key_order: KeyOrder) -> impl Iterator<Item = (String, V)> {
+    let mut entries: Vec<_> = map.into_iter().collect();
+    if key_order == KeyOrder::Sorted {
+        entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+    }
+    entries.into_iter()
+}
+
+pub(crate) fn set_operation(path_item: &mut PathItem, method: OpenApiMethod, op: Operation) {
     let option = match method {
         OpenApiMethod::Get => &mut path_item.get,
         OpenApiMethod::Put => &mut path_item.put,
@@ -125,9 +729,331 @@ fn set_operation(path_item: &mut PathItem, method: OpenApiMethod, op: Operation)
         OpenApiMethod::Head => &mut path_item.head,
         OpenApiMethod::Patch => &mut path_item.patch,
         OpenApiMethod::Trace => &mut path_item.trace,
-        // Connect not available in OpenAPI3. Maybe should set in extensions?
+        // Rejected by `add_operation`.
         OpenApiMethod::Connect => return,
     };
-    assert!(option.is_none());
     option.replace(op);
-}
\ No newline at end of file
+}
+
+/// Check that `path` is an absolute path template with well-formed `{variable}` segments.
+fn validate_path(path: &str) -> Result<()> {
+    let invalid = |reason: &str| {
+        Err(OpenApiError::InvalidPath {
+            path: path.to_owned(),
+            reason: reason.to_owned(),
+        })
+    };
+
+    if !path.starts_with('/') {
+        return invalid("paths must start with `/`");
+    }
+    for segment in path.split('/') {
+        let opens = segment.matches('{').count();
+        let closes = segment.matches('}').count();
+        if opens != closes || opens > 1 {
+            return invalid("unbalanced `{` `}` in path template");
+        }
+        if segment == "{}" || segment == ":" || segment == "*" {
+            return invalid("path template variables must have a name");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde::Serialize;
+    use serde_json::json;
+
+    #[derive(Serialize, JsonSchema)]
+    struct Contact {
+        email: Option<String>,
+    }
+
+    fn generator(settings: OpenApiSettings) -> OpenApiGenerator {
+        let mut gen = OpenApiGenerator::new(&settings);
+        gen.json_schema::<Contact>();
+        gen.add_webhook::<Contact>("contactChanged", None).unwrap();
+        gen
+    }
+
+    fn document(gen: OpenApiGenerator) -> Value {
+        serde_json::to_value(gen.into_openapi().unwrap()).unwrap()
+    }
+
+    fn with_operations(operations: &[(&str, &str)]) -> OpenApiGenerator {
+        let mut gen = OpenApiGenerator::new(&OpenApiSettings::new());
+        for (path, id) in operations {
+            gen.add_operation(OperationInfo {
+                path: (*path).to_owned(),
+                method: OpenApiMethod::Get,
+                handler: None,
+                operation: Operation {
+                    operation_id: Some((*id).to_owned()),
+                    ..Operation::default()
+                },
+            })
+            .unwrap();
+        }
+        gen
+    }
+
+    fn bearer(format: &str) -> SecurityScheme {
+        SecurityScheme {
+            description: None,
+            data: okapi::openapi3::SecuritySchemeData::Http {
+                scheme: "bearer".to_owned(),
+                bearer_format: Some(format.to_owned()),
+            },
+            extensions: Object::default(),
+        }
+    }
+
+    fn tag(description: &str) -> Tag {
+        Tag {
+            name: "users".to_owned(),
+            description: Some(description.to_owned()),
+            ..Tag::default()
+        }
+    }
+
+    fn paths(gen: OpenApiGenerator) -> Vec<String> {
+        gen.into_openapi().unwrap().paths.keys().cloned().collect()
+    }
+
+    #[test]
+    fn openapi_3_1_uses_its_schema_dialect_and_webhooks() {
+        let document = document(generator(OpenApiSettings {
+            openapi_version: OpenApiVersion::V3_1,
+            ..OpenApiSettings::new()
+        }));
+
+        assert_eq!(document["openapi"], "3.1.0");
+        assert_eq!(
+            document["components"]["schemas"]["Contact"]["properties"]["email"],
+            json!({ "type": ["string", "null"] })
+        );
+        let webhook = &document["webhooks"]["contactChanged"];
+        assert_eq!(
+            webhook["post"]["requestBody"]["content"]["application/json"]["schema"],
+            json!({ "$ref": "#/components/schemas/Contact" })
+        );
+        assert!(document["components"].get("callbacks").is_none());
+        assert!(!document.to_string().contains("nullable"));
+    }
+
+    #[test]
+    fn openapi_3_0_uses_nullable_and_callbacks() {
+        let document = document(generator(OpenApiSettings::new()));
+
+        assert_eq!(document["openapi"], "3.0.0");
+        assert_eq!(
+            document["components"]["schemas"]["Contact"]["properties"]["email"],
+            json!({ "type": "string", "nullable": true })
+        );
+        let callback = &document["components"]["callbacks"]["contactChanged"];
+        assert!(callback["{$request.body#/callbackUrl}"]["post"].is_object());
+        assert!(document.get("webhooks").is_none());
+    }
+
+    #[test]
+    fn fragments_cannot_set_webhooks_as_an_extension() {
+        let mut gen = generator(OpenApiSettings {
+            openapi_version: OpenApiVersion::V3_1,
+            ..OpenApiSettings::new()
+        });
+        let mut fragment = OpenApi::default();
+        fragment.extensions.insert("webhooks".to_owned(), json!({}));
+
+        assert!(matches!(
+            gen.merge_fragment("", fragment),
+            Err(OpenApiError::InvalidExtension { key }) if key == "webhooks"
+        ));
+    }
+
+    #[test]
+    fn merging_fails_on_a_duplicate_operation() {
+        let mut gen = with_operations(&[("/users", "list_users")]);
+        let other = with_operations(&[("/users", "list_people")]);
+        assert!(matches!(
+            gen.merge(other),
+            Err(OpenApiError::DuplicateOperation { path, method: OpenApiMethod::Get })
+                if path == "/users"
+        ));
+        assert_eq!(paths(gen), ["/users"]);
+    }
+
+    #[test]
+    fn merging_fails_on_a_duplicate_webhook() {
+        let mut gen = generator(OpenApiSettings::new());
+        let mut other = OpenApiGenerator::new(&OpenApiSettings::new());
+        other
+            .add_webhook::<Contact>("contactChanged", Some("Another".to_owned()))
+            .unwrap();
+        assert!(matches!(
+            gen.merge(other),
+            Err(OpenApiError::DuplicateWebhook { name }) if name == "contactChanged"
+        ));
+    }
+
+    #[test]
+    fn merging_fails_on_a_conflicting_component() {
+        let mut gen = OpenApiGenerator::new(&OpenApiSettings::new());
+        gen.add_security_scheme("token".to_owned(), bearer("JWT"));
+        let mut same = OpenApiGenerator::new(&OpenApiSettings::new());
+        same.add_security_scheme("token".to_owned(), bearer("JWT"));
+        gen.merge(same).unwrap();
+
+        let mut other = OpenApiGenerator::new(&OpenApiSettings::new());
+        other.add_security_scheme("token".to_owned(), bearer("opaque"));
+        assert!(matches!(
+            gen.merge(other),
+            Err(OpenApiError::ConflictingComponent { kind, name })
+                if kind == "security scheme" && name == "token"
+        ));
+    }
+
+    #[test]
+    fn merging_fails_on_a_conflicting_tag() {
+        let mut gen = OpenApiGenerator::new(&OpenApiSettings::new());
+        let fragment = |description| OpenApi {
+            tags: vec![tag(description)],
+            ..OpenApi::default()
+        };
+        gen.merge_fragment("", fragment("Accounts")).unwrap();
+        gen.merge_fragment("", fragment("Accounts")).unwrap();
+        assert!(matches!(
+            gen.merge_fragment("", fragment("People")),
+            Err(OpenApiError::ConflictingComponent { kind, name })
+                if kind == "tag" && name == "users"
+        ));
+    }
+
+    #[test]
+    fn nested_merges_prefix_the_paths() {
+        let mut gen = with_operations(&[("/users", "list_users")]);
+        let billing = with_operations(&[("/", "billing"), ("/invoices/:id", "get_invoice")]);
+        gen.merge_nested("/billing/", billing).unwrap();
+
+        let mut fragment = OpenApi::default();
+        let get = Operation {
+            operation_id: Some("get_order".to_owned()),
+            ..Operation::default()
+        };
+        fragment.paths.insert(
+            "/{id}".to_owned(),
+            PathItem {
+                get: Some(get),
+                ..PathItem::default()
+            },
+        );
+        gen.merge_fragment("/orders", fragment).unwrap();
+
+        let mut paths = paths(gen);
+        paths.sort();
+        assert_eq!(
+            paths,
+            ["/billing", "/billing/invoices/{id}", "/orders/{id}", "/users"]
+        );
+    }
+
+    #[test]
+    fn enclosing_tags_are_all_added_and_replace_inference() {
+        let mut gen = OpenApiGenerator::new(&OpenApiSettings::new());
+        let add = |gen: &mut OpenApiGenerator, path: &str| {
+            gen.add_operation(OperationInfo {
+                path: path.to_owned(),
+                method: OpenApiMethod::Get,
+                handler: None,
+                operation: Operation::default(),
+            })
+        };
+        gen.with_tag("admin", |gen| {
+            gen.with_tag("users", |gen| add(gen, "/api/accounts"))?;
+            add(gen, "/api/audit")
+        })
+        .unwrap();
+        add(&mut gen, "/api/v1/orders").unwrap();
+
+        let openapi = gen.into_openapi().unwrap();
+        let tags = |path: &str| openapi.paths[path].get.as_ref().unwrap().tags.clone();
+        assert_eq!(tags("/api/accounts"), ["admin", "users"]);
+        assert_eq!(tags("/api/audit"), ["admin"]);
+        assert_eq!(tags("/api/v1/orders"), ["orders"]);
+    }
+
+    /// A generator with the operations of `handlers` at `/{index}`, the first linking to the
+    /// handler `target`.
+    fn linking(handlers: &[&str], target: &str) -> OpenApiGenerator {
+        let mut gen = OpenApiGenerator::new(&OpenApiSettings::new());
+        for (index, handler) in handlers.iter().enumerate() {
+            let mut operation = Operation::default();
+            if index == 0 {
+                crate::link::add_handler_link(
+                    &mut operation.responses,
+                    None,
+                    "Next",
+                    target,
+                    Vec::new(),
+                    None,
+                )
+                .unwrap();
+            }
+            gen.add_operation(OperationInfo {
+                path: format!("/{}", index),
+                method: OpenApiMethod::Get,
+                handler: Some((*handler).to_owned()),
+                operation,
+            })
+            .unwrap();
+        }
+        gen
+    }
+
+    fn next_link(openapi: &OpenApi) -> okapi::openapi3::Link {
+        let response = &openapi.paths["/0"].get.as_ref().unwrap().responses.responses["200"];
+        match response {
+            RefOr::Object(response) => match &response.links["Next"] {
+                RefOr::Object(link) => link.clone(),
+                RefOr::Ref(_) => panic!("unexpected reference"),
+            },
+            RefOr::Ref(_) => panic!("unexpected reference"),
+        }
+    }
+
+    #[test]
+    fn links_resolve_to_the_operation_id_of_their_handler() {
+        let handlers = ["shop::orders::create", "shop::orders::get", "shop::users::get"];
+        let openapi = linking(&handlers, "shop::orders::get").into_openapi().unwrap();
+        let link = next_link(&openapi);
+        assert_eq!(link.operation_id.as_deref(), Some("orders_get"));
+        assert!(link.extensions.is_empty());
+
+        let openapi = linking(&handlers, "users::get").into_openapi().unwrap();
+        assert_eq!(next_link(&openapi).operation_id.as_deref(), Some("users_get"));
+    }
+
+    #[test]
+    fn unresolved_links_fail() {
+        let handlers = ["shop::orders::create", "shop::orders::get", "shop::users::get"];
+        for (target, reason) in [
+            ("shop::orders::delete", "no operation was added for it"),
+            ("get", "several handlers match this path"),
+        ] {
+            match linking(&handlers, target).into_openapi() {
+                Err(OpenApiError::UnresolvedLink {
+                    link,
+                    handler,
+                    reason: actual,
+                }) => {
+                    assert_eq!(link, "Next");
+                    assert_eq!(handler, target);
+                    assert_eq!(actual, reason);
+                }
+                other => panic!("expected an unresolved link, got {:?}", other.map(|_| ())),
+            }
+        }
+    }
+}
@@ -0,0 +1,508 @@
+//! A validator for the subset of JSON Schema used by OpenAPI 3.0 schemas.
+//!
+//! Schemas are validated as JSON values inside the whole specification document, so that
+//! `$ref`s such as `#/components/schemas/User` resolve. Supported keywords:
+//! `$ref`, `type`, `nullable`, `enum`, `const`, `properties`, `required`,
+//! `additionalProperties`, `minProperties`, `maxProperties`, `items`, `minItems`, `maxItems`,
+//! `uniqueItems`, `minLength`, `maxLength`, `minimum`, `maximum`, `exclusiveMinimum`,
+//! `exclusiveMaximum`, `multipleOf`, `allOf`, `anyOf`, `oneOf` and `not`. `pattern` and `format`
+//! are not checked.
+
+use std::fmt;
+
+use serde_json::{Map, Value};
+
+/// Maximum depth of nested `$ref`s followed before giving up, to stop on recursive schemas that
+/// never consume the instance.
+const MAX_DEPTH: usize = 64;
+
+/// A value not matching its schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaError {
+    /// JSON pointer of the invalid value in the validated instance, e.g. `/items/0/name`.
+    pub instance_path: String,
+    /// What is wrong.
+    pub message: String,
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = if self.instance_path.is_empty() {
+            "/"
+        } else {
+            &self.instance_path
+        };
+        write!(f, "{}: {}", path, self.message)
+    }
+}
+
+/// Validate `instance` against `schema`, resolving references in `document`. Returns every error
+/// found; an empty list means the instance is valid.
+#[must_use]
+pub fn validate(document: &Value, schema: &Value, instance: &Value) -> Vec<SchemaError> {
+    let mut errors = Vec::new();
+    Validator { document }.validate(schema, instance, &mut String::new(), 0, &mut errors);
+    errors
+}
+
+struct Validator<'a> {
+    document: &'a Value,
+}
+
+impl<'a> Validator<'a> {
+    fn is_valid(&self, schema: &Value, instance: &Value, depth: usize) -> bool {
+        let mut errors = Vec::new();
+        self.validate(schema, instance, &mut String::new(), depth, &mut errors);
+        errors.is_empty()
+    }
+
+    fn validate(
+        &self,
+        schema: &Value,
+        instance: &Value,
+        path: &mut String,
+        depth: usize,
+        errors: &mut Vec<SchemaError>,
+    ) {
+        let mut error = |message: String| {
+            errors.push(SchemaError {
+                instance_path: path.clone(),
+                message,
+            })
+        };
+
+        let schema = match schema {
+            Value::Bool(true) => return,
+            Value::Bool(false) => return error("no value is allowed here".to_owned()),
+            Value::Object(schema) => schema,
+            _ => return,
+        };
+
+        if let Some(Value::String(reference)) = schema.get("$ref") {
+            if depth >= MAX_DEPTH {
+                return error(format!("`$ref` `{}` nests too deeply", reference));
+            }
+            match reference
+                .strip_prefix('#')
+                .and_then(|pointer| self.document.pointer(pointer))
+            {
+                Some(target) => return self.validate(target, instance, path, depth + 1, errors),
+                None => return error(format!("`$ref` `{}` does not resolve", reference)),
+            }
+        }
+
+        if instance.is_null() && schema.get("nullable") == Some(&Value::Bool(true)) {
+            return;
+        }
+
+        if let Some(expected) = schema.get("type") {
+            let types: Vec<&str> = match expected {
+                Value::String(t) => vec![t.as_str()],
+                Value::Array(ts) => ts.iter().filter_map(Value::as_str).collect(),
+                _ => vec![],
+            };
+            if !types.is_empty() && !types.iter().any(|t| has_type(instance, t)) {
+                return error(format!(
+                    "expected {}, found {}",
+                    types.join(" or "),
+                    type_name(instance)
+                ));
+            }
+        }
+
+        if let Some(Value::Array(values)) = schema.get("enum") {
+            if !values.contains(instance) {
+                error(format!("{} is not one of {}", instance, Value::Array(values.clone())));
+            }
+        }
+        if let Some(value) = schema.get("const") {
+            if value != instance {
+                error(format!("expected {}, found {}", value, instance));
+            }
+        }
+
+        match instance {
+            Value::Object(object) => self.validate_object(schema, object, path, depth, errors),
+            Value::Array(items) => self.validate_array(schema, items, path, depth, errors),
+            Value::String(s) => validate_string(schema, s, path, errors),
+            Value::Number(_) => validate_number(schema, instance, path, errors),
+            _ => {}
+        }
+
+        self.validate_combinators(schema, instance, path, depth, errors);
+    }
+
+    fn validate_object(
+        &self,
+        schema: &Map<String, Value>,
+        object: &Map<String, Value>,
+        path: &mut String,
+        depth: usize,
+        errors: &mut Vec<SchemaError>,
+    ) {
+        if let Some(Value::Array(required)) = schema.get("required") {
+            for name in required.iter().filter_map(Value::as_str) {
+                if !object.contains_key(name) {
+                    errors.push(SchemaError {
+                        instance_path: path.clone(),
+                        message: format!("missing required property `{}`", name),
+                    });
+                }
+            }
+        }
+
+        let empty = Map::new();
+        let properties = match schema.get("properties") {
+            Some(Value::Object(properties)) => properties,
+            _ => &empty,
+        };
+        for (name, value) in object {
+            let property_schema = match properties.get(name) {
+                Some(property_schema) => property_schema,
+                None => match schema.get("additionalProperties") {
+                    Some(Value::Bool(false)) => {
+                        errors.push(SchemaError {
+                            instance_path: path.clone(),
+                            message: format!("property `{}` is not allowed", name),
+                        });
+                        continue;
+                    }
+                    Some(additional) => additional,
+                    None => continue,
+                },
+            };
+            let len = path.len();
+            path.push('/');
+            path.push_str(&name.replace('~', "~0").replace('/', "~1"));
+            self.validate(property_schema, value, path, depth, errors);
+            path.truncate(len);
+        }
+
+        check_bounds(
+            schema,
+            object.len(),
+            ("minProperties", "maxProperties"),
+            "properties",
+            path,
+            errors,
+        );
+    }
+
+    fn validate_array(
+        &self,
+        schema: &Map<String, Value>,
+        items: &[Value],
+        path: &mut String,
+        depth: usize,
+        errors: &mut Vec<SchemaError>,
+    ) {
+        for (index, item) in items.iter().enumerate() {
+            let item_schema = match schema.get("items") {
+                Some(Value::Array(schemas)) => match schemas.get(index) {
+                    Some(item_schema) => item_schema,
+                    None => continue,
+                },
+                Some(item_schema) => item_schema,
+                None => continue,
+            };
+            let len = path.len();
+            path.push('/');
+            path.push_str(&index.to_string());
+            self.validate(item_schema, item, path, depth, errors);
+            path.truncate(len);
+        }
+
+        check_bounds(schema, items.len(), ("minItems", "maxItems"), "items", path, errors);
+
+        if schema.get("uniqueItems") == Some(&Value::Bool(true)) {
+            let duplicated = items
+                .iter()
+                .enumerate()
+                .any(|(i, item)| items[..i].contains(item));
+            if duplicated {
+                errors.push(SchemaError {
+                    instance_path: path.clone(),
+                    message: "items are not unique".to_owned(),
+                });
+            }
+        }
+    }
+
+    fn validate_combinators(
+        &self,
+        schema: &Map<String, Value>,
+        instance: &Value,
+        path: &mut String,
+        depth: usize,
+        errors: &mut Vec<SchemaError>,
+    ) {
+        if let Some(Value::Array(schemas)) = schema.get("allOf") {
+            for sub in schemas {
+                self.validate(sub, instance, path, depth, errors);
+            }
+        }
+        if let Some(Value::Array(schemas)) = schema.get("anyOf") {
+            if !schemas.iter().any(|sub| self.is_valid(sub, instance, depth)) {
+                errors.push(SchemaError {
+                    instance_path: path.clone(),
+                    message: "does not match any schema of `anyOf`".to_owned(),
+                });
+            }
+        }
+        if let Some(Value::Array(schemas)) = schema.get("oneOf") {
+            let matching = schemas
+                .iter()
+                .filter(|sub| self.is_valid(sub, instance, depth))
+                .count();
+            if matching != 1 {
+                errors.push(SchemaError {
+                    instance_path: path.clone(),
+                    message: format!("matches {} schemas of `oneOf` instead of one", matching),
+                });
+            }
+        }
+        if let Some(sub) = schema.get("not") {
+            if self.is_valid(sub, instance, depth) {
+                errors.push(SchemaError {
+                    instance_path: path.clone(),
+                    message: "matches the schema of `not`".to_owned(),
+                });
+            }
+        }
+    }
+}
+
+fn validate_string(
+    schema: &Map<String, Value>,
+    value: &str,
+    path: &str,
+    errors: &mut Vec<SchemaError>,
+) {
+    check_bounds(
+        schema,
+        value.chars().count(),
+        ("minLength", "maxLength"),
+        "characters",
+        path,
+        errors,
+    );
+}
+
+fn validate_number(
+    schema: &Map<String, Value>,
+    instance: &Value,
+    path: &str,
+    errors: &mut Vec<SchemaError>,
+) {
+    let value = match instance.as_f64() {
+        Some(value) => value,
+        None => return,
+    };
+    let mut error = |message: String| {
+        errors.push(SchemaError {
+            instance_path: path.to_owned(),
+            message,
+        })
+    };
+    let number = |key: &str| schema.get(key).and_then(Value::as_f64);
+
+    // OpenAPI 3.0 uses boolean `exclusiveMinimum`/`exclusiveMaximum`, JSON Schema numbers.
+    let exclusive = |key: &str| schema.get(key) == Some(&Value::Bool(true));
+    if let Some(minimum) = number("minimum") {
+        if value < minimum || (exclusive("exclusiveMinimum") && value == minimum) {
+            error(format!("{} is less than the minimum {}", instance, minimum));
+        }
+    }
+    if let Some(maximum) = number("maximum") {
+        if value > maximum || (exclusive("exclusiveMaximum") && value == maximum) {
+            error(format!("{} is greater than the maximum {}", instance, maximum));
+        }
+    }
+    if let Some(minimum) = number("exclusiveMinimum") {
+        if value <= minimum {
+            error(format!("{} is not greater than {}", instance, minimum));
+        }
+    }
+    if let Some(maximum) = number("exclusiveMaximum") {
+        if value >= maximum {
+            error(format!("{} is not less than {}", instance, maximum));
+        }
+    }
+    if let Some(multiple) = number("multipleOf") {
+        if multiple > 0.0 && (value / multiple).fract().abs() > f64::EPSILON {
+            error(format!("{} is not a multiple of {}", instance, multiple));
+        }
+    }
+}
+
+/// Check the `min`/`max` keywords of `keys` against the size `len` of the instance.
+fn check_bounds(
+    schema: &Map<String, Value>,
+    len: usize,
+    (min_key, max_key): (&str, &str),
+    unit: &str,
+    path: &str,
+    errors: &mut Vec<SchemaError>,
+) {
+    let len = len as u64;
+    if let Some(min) = schema.get(min_key).and_then(Value::as_u64) {
+        if len < min {
+            errors.push(SchemaError {
+                instance_path: path.to_owned(),
+                message: format!("has {} {}, fewer than the minimum {}", len, unit, min),
+            });
+        }
+    }
+    if let Some(max) = schema.get(max_key).and_then(Value::as_u64) {
+        if len > max {
+            errors.push(SchemaError {
+                instance_path: path.to_owned(),
+                message: format!("has {} {}, more than the maximum {}", len, unit, max),
+            });
+        }
+    }
+}
+
+fn has_type(instance: &Value, expected: &str) -> bool {
+    match expected {
+        "null" => instance.is_null(),
+        "boolean" => instance.is_boolean(),
+        "object" => instance.is_object(),
+        "array" => instance.is_array(),
+        "string" => instance.is_string(),
+        "number" => instance.is_number(),
+        "integer" => {
+            instance.is_i64()
+                || instance.is_u64()
+                || instance.as_f64().is_some_and(|n| n.fract() == 0.0)
+        }
+        _ => true,
+    }
+}
+
+fn type_name(instance: &Value) -> &'static str {
+    match instance {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    fn errors(schema: Value, instance: Value) -> Vec<String> {
+        validate(&json!({}), &schema, &instance)
+            .into_iter()
+            .map(|e| e.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn types_and_nullable() {
+        assert!(errors(json!({ "type": "integer" }), json!(3)).is_empty());
+        assert!(errors(json!({ "type": "integer" }), json!(3.0)).is_empty());
+        assert_eq!(
+            errors(json!({ "type": "integer" }), json!(3.5)),
+            ["/: expected integer, found number"]
+        );
+        assert!(errors(json!({ "type": "string", "nullable": true }), Value::Null).is_empty());
+        assert_eq!(
+            errors(json!({ "type": "string" }), Value::Null),
+            ["/: expected string, found null"]
+        );
+    }
+
+    #[test]
+    fn objects_report_the_path_of_invalid_properties() {
+        let schema = json!({
+            "type": "object",
+            "required": ["id"],
+            "properties": { "a/b": { "type": "string" } },
+            "additionalProperties": false,
+        });
+        assert_eq!(
+            errors(schema, json!({ "a/b": 1, "extra": true })),
+            [
+                "/: missing required property `id`",
+                "/a~1b: expected string, found number",
+                "/: property `extra` is not allowed",
+            ]
+        );
+    }
+
+    #[test]
+    fn arrays_strings_and_numbers_are_bounded() {
+        let schema = json!({ "type": "array", "items": { "minLength": 2 }, "maxItems": 2, "uniqueItems": true });
+        assert_eq!(
+            errors(schema, json!(["a", "bb", "bb"])),
+            [
+                "/0: has 1 characters, fewer than the minimum 2",
+                "/: has 3 items, more than the maximum 2",
+                "/: items are not unique",
+            ]
+        );
+        assert_eq!(
+            errors(json!({ "minimum": 1, "exclusiveMinimum": true }), json!(1)),
+            ["/: 1 is less than the minimum 1"]
+        );
+        assert_eq!(
+            errors(json!({ "exclusiveMaximum": 10 }), json!(10)),
+            ["/: 10 is not less than 10"]
+        );
+        assert!(errors(json!({ "multipleOf": 0.5 }), json!(2.5)).is_empty());
+    }
+
+    #[test]
+    fn combinators() {
+        let one_of = json!({ "oneOf": [{ "type": "integer" }, { "type": "number" }] });
+        assert_eq!(
+            errors(one_of.clone(), json!(1)),
+            ["/: matches 2 schemas of `oneOf` instead of one"]
+        );
+        assert!(errors(one_of, json!(1.5)).is_empty());
+        assert!(errors(json!({ "anyOf": [{ "enum": ["a"] }, { "const": "b" }] }), json!("b")).is_empty());
+        assert_eq!(
+            errors(json!({ "not": { "type": "string" } }), json!("a")),
+            ["/: matches the schema of `not`"]
+        );
+    }
+
+    #[test]
+    fn references_resolve_in_the_document() {
+        let document = json!({
+            "components": { "schemas": { "Node": {
+                "type": "object",
+                "properties": { "next": { "$ref": "#/components/schemas/Node" }, "value": { "type": "integer" } },
+            } } }
+        });
+        let schema = json!({ "$ref": "#/components/schemas/Node" });
+        let instance = json!({ "value": 1, "next": { "value": 2, "next": { "value": "3" } } });
+        let errors = validate(&document, &schema, &instance);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].instance_path, "/next/next/value");
+
+        let missing = validate(&document, &json!({ "$ref": "#/components/schemas/Missing" }), &json!(1));
+        assert_eq!(missing[0].message, "`$ref` `#/components/schemas/Missing` does not resolve");
+    }
+
+    #[test]
+    fn recursive_references_that_consume_nothing_stop() {
+        let document = json!({ "components": { "schemas": { "Loop": { "$ref": "#/components/schemas/Loop" } } } });
+        let errors = validate(&document, &json!({ "$ref": "#/components/schemas/Loop" }), &json!(1));
+        assert_eq!(errors[0].message, "`$ref` `#/components/schemas/Loop` nests too deeply");
+    }
+
+    #[test]
+    fn patterns_and_formats_are_not_checked() {
+        let schema = json!({ "type": "string", "pattern": "^[a-z]+$", "format": "email" });
+        assert!(errors(schema.clone(), json!("NOT AN ADDRESS")).is_empty());
+        assert_eq!(errors(schema, json!(1)), ["/: expected string, found number"]);
+    }
+}
@@ -3,10 +3,10 @@ use std::fmt::Display;
 use anyhow::Result;
 use axum::http::Method;
 use gen::OpenApiGenerator;
-use okapi::openapi3::{Object, Parameter, ParameterValue, RequestBody, SchemaObject};
+use okapi::openapi3::RequestBody;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum OpenApiMethod {
     Get,
     Post,
@@ -23,32 +23,68 @@ pub enum OpenApiMethod {
 impl Display for OpenApiMethod {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            OpenApiMethod::Get => write!(f, "{}", "GET"),
-            OpenApiMethod::Post => write!(f, "{}", "POST"),
-            OpenApiMethod::Put => write!(f, "{}", "PUT"),
-            OpenApiMethod::Patch => write!(f, "{}", "PATCH"),
-            OpenApiMethod::Delete => write!(f, "{}", "DELETE"),
-            OpenApiMethod::Head => write!(f, "{}", "HEAD"),
-            OpenApiMethod::Connect => write!(f, "{}", "CONNECT"),
-            OpenApiMethod::Options => write!(f, "{}", "OPTIONS"),
-            OpenApiMethod::Trace => write!(f, "{}", "TRACE"),
+            OpenApiMethod::Get => write!(f, "GET"),
+            OpenApiMethod::Post => write!(f, "POST"),
+            OpenApiMethod::Put => write!(f, "PUT"),
+            OpenApiMethod::Patch => write!(f, "PATCH"),
+            OpenApiMethod::Delete => write!(f, "DELETE"),
+            OpenApiMethod::Head => write!(f, "HEAD"),
+            OpenApiMethod::Connect => write!(f, "CONNECT"),
+            OpenApiMethod::Options => write!(f, "OPTIONS"),
+            OpenApiMethod::Trace => write!(f, "TRACE"),
         }
     }
 }
 
+pub mod callback;
+#[cfg(feature = "cli")]
+pub mod cli;
+#[cfg(feature = "cookie")]
+pub mod cookie;
+pub mod deprecation;
+pub mod diff;
 pub mod error;
+pub mod example;
+pub mod extension;
 pub mod gen;
+pub mod json_schema;
+pub mod link;
+mod matcher;
+pub mod mock;
+pub mod parameter;
+pub mod render;
 pub mod request;
+pub mod request_validation;
 pub mod response;
+mod schema_names;
+pub mod serve;
 pub mod settings;
+#[cfg(feature = "test-support")]
+pub mod snapshot;
+#[cfg(feature = "test-support")]
+pub mod testing;
 pub mod utils;
+pub mod validate;
 
-#[macro_use]
+pub use okapi;
 pub use openapi_proc_macro;
+pub use openapi_proc_macro::{handler, OpenApiHeaders, OpenApiPath, OpenApiQuery};
+pub use parameter::{
+    parameter_from_schema, parameters_from_schema, ParameterBuilder, ParameterLocation,
+};
+pub use schemars;
 
-impl Into<OpenApiMethod> for Method {
-    fn into(self) -> OpenApiMethod {
-        match self {
+/// Re-exports used by the code generated from `openapi_proc_macro`. Not public API.
+#[doc(hidden)]
+pub mod __private {
+    pub use anyhow;
+    pub use axum;
+    pub use serde_json;
+}
+
+impl From<Method> for OpenApiMethod {
+    fn from(method: Method) -> Self {
+        match method {
             Method::GET => OpenApiMethod::Get,
             Method::POST => OpenApiMethod::Post,
             Method::PUT => OpenApiMethod::Put,
@@ -63,9 +99,9 @@ impl Into<OpenApiMethod> for Method {
     }
 }
 
-impl Into<OpenApiMethod> for &str {
-    fn into(self) -> OpenApiMethod {
-        match self {
+impl From<&str> for OpenApiMethod {
+    fn from(method: &str) -> Self {
+        match method {
             "GET" => OpenApiMethod::Get,
             "POST" => OpenApiMethod::Post,
             "PUT" => OpenApiMethod::Put,
@@ -80,9 +116,9 @@ impl Into<OpenApiMethod> for &str {
     }
 }
 
-impl Into<OpenApiMethod> for String {
-    fn into(self) -> OpenApiMethod {
-        match self.as_str() {
+impl From<String> for OpenApiMethod {
+    fn from(method: String) -> Self {
+        match method.as_str() {
             "GET" => OpenApiMethod::Get,
             "POST" => OpenApiMethod::Post,
             "PUT" => OpenApiMethod::Put,
@@ -102,41 +138,13 @@ pub struct OperationInfo {
     pub path: String,
     /// The HTTP Method of this endpoint.
     pub method: OpenApiMethod,
+    /// The path of the handler function (`module_path!()` and function name). Used to generate
+    /// the `operationId` when `operation.operation_id` is not set.
+    pub handler: Option<String>,
     /// Contains information to be showed in the documentation about this endpoint.
     pub operation: okapi::openapi3::Operation,
 }
 
-pub fn parameter_from_schema(schema: SchemaObject, name: String, mut required: bool) -> Parameter {
-    // Check if parameter is optional (only is not already optional)
-    if required {
-        for (key, value) in &schema.extensions {
-            if key == "nullable" {
-                if let Some(nullable) = value.as_bool() {
-                    required = !nullable;
-                }
-            }
-        }
-    }
-    let description = schema.metadata.as_ref().and_then(|m| m.description.clone());
-    Parameter {
-        name,
-        location: "query".to_owned(),
-        description,
-        required,
-        deprecated: false,
-        allow_empty_value: false,
-        value: ParameterValue::Schema {
-            style: None,
-            explode: None,
-            allow_reserved: false,
-            schema,
-            example: None,
-            examples: None,
-        },
-        extensions: Object::default(),
-    }
-}
-
 pub trait OpenApiFromData<'r> {
     /// Return a [`RequestBody`] containing the information required to document the
     /// [`FromData`](rocket::data::FromData) object.
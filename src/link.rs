@@ -0,0 +1,124 @@
+//! Links from the responses of an operation to other operations.
+//!
+//! Links reference the target by the path of its handler function, resolved to its
+//! `operationId` by [`OpenApiGenerator::into_openapi`](crate::gen::OpenApiGenerator::into_openapi):
+//! ```rust,ignore
+//! #[handler(
+//!     method = "POST",
+//!     link(name = "GetOrder", handler = "get_order", parameter(name = "id", expression = "$response.body#/id")),
+//! )]
+//! async fn create_order(Json(order): Json<NewOrder>) -> Json<Order> { ... }
+//! ```
+//! Responders add links with [`add_handler_link`].
+
+use anyhow::Result;
+use okapi::openapi3::{Link, Object, RefOr, Responses};
+use serde_json::Value;
+
+use crate::utils::{ensure_not_ref, ensure_status_code_exists, first_success_status};
+
+/// Extension temporarily holding the handler path of the target of a link, until it is resolved.
+pub(crate) const HANDLER_EXTENSION: &str = "x-openapi-rs-handler";
+
+/// Resolve the path of a handler function as written in the module `module` (its
+/// `module_path!()`) to the full path of the function: `crate::`, `self::` and `super::` prefixes
+/// are resolved and single identifiers are looked up in `module`. Other paths are returned as is
+/// and matched by suffix at resolution.
+#[must_use]
+pub fn resolve_handler_path(module: &str, target: &str) -> String {
+    let mut segments: Vec<&str> = module.split("::").collect();
+    let mut rest: Vec<&str> = target.split("::").collect();
+
+    match rest.first().copied() {
+        Some("crate") => {
+            segments.truncate(1);
+            rest.remove(0);
+        }
+        Some("self") => {
+            rest.remove(0);
+        }
+        Some("super") => {
+            while rest.first() == Some(&"super") {
+                rest.remove(0);
+                if segments.len() > 1 {
+                    segments.pop();
+                }
+            }
+        }
+        _ if rest.len() == 1 => {}
+        _ => return target.to_owned(),
+    }
+
+    segments.extend(rest);
+    segments.join("::")
+}
+
+/// Add the link `name` to the response for `status` (the first successful response when `None`)
+/// targeting the operation of the handler at `handler`, a full path as given by
+/// [`resolve_handler_path`].
+///
+/// `parameters` maps the parameters of the target operation to runtime expressions such as
+/// `$response.body#/id`.
+pub fn add_handler_link(
+    responses: &mut Responses,
+    status: Option<u16>,
+    name: impl Into<String>,
+    handler: impl Into<String>,
+    parameters: Vec<(String, String)>,
+    description: Option<String>,
+) -> Result<()> {
+    let status = status.unwrap_or_else(|| first_success_status(responses));
+
+    let mut extensions = Object::default();
+    extensions.insert(HANDLER_EXTENSION.to_owned(), Value::String(handler.into()));
+    let link = Link {
+        parameters: parameters
+            .into_iter()
+            .map(|(name, expression)| (name, Value::String(expression)))
+            .collect(),
+        description,
+        extensions,
+        ..Link::default()
+    };
+
+    let response = ensure_not_ref(ensure_status_code_exists(responses, status))?;
+    response.links.insert(name.into(), RefOr::Object(link));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn handler_paths_resolve_like_use_paths() {
+        let resolve = |target| resolve_handler_path("shop::routes::orders", target);
+        assert_eq!(resolve("get_order"), "shop::routes::orders::get_order");
+        assert_eq!(resolve("self::get_order"), "shop::routes::orders::get_order");
+        assert_eq!(resolve("super::users::get_user"), "shop::routes::users::get_user");
+        assert_eq!(resolve("super::super::health"), "shop::health");
+        assert_eq!(resolve("crate::health"), "shop::health");
+        assert_eq!(resolve("users::get_user"), "users::get_user");
+    }
+
+    #[test]
+    fn links_go_to_the_first_successful_response_by_default() {
+        let mut responses = Responses::default();
+        ensure_status_code_exists(&mut responses, 404);
+        ensure_status_code_exists(&mut responses, 201);
+        let parameters = vec![("id".to_owned(), "$response.body#/id".to_owned())];
+        add_handler_link(&mut responses, None, "GetOrder", "shop::get_order", parameters, None)
+            .unwrap();
+
+        let link = match &responses.responses["201"] {
+            RefOr::Object(response) => &response.links["GetOrder"],
+            RefOr::Ref(_) => panic!("unexpected reference"),
+        };
+        let link = match link {
+            RefOr::Object(link) => link,
+            RefOr::Ref(_) => panic!("unexpected reference"),
+        };
+        assert_eq!(link.parameters["id"], "$response.body#/id");
+        assert_eq!(link.extensions[HANDLER_EXTENSION], "shop::get_order");
+    }
+}
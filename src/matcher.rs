@@ -0,0 +1,107 @@
+//! Matching of request paths against the path templates of a specification.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Static(String),
+    Variable(String),
+}
+
+/// Routes request paths to values registered for path templates such as `/users/{id}` (or the
+/// axum syntax `/users/:id`). Static segments take precedence over variables, so `/users/me`
+/// matches `/users/me` before `/users/{id}`.
+#[derive(Debug, Clone)]
+pub(crate) struct PathMatcher<T> {
+    routes: Vec<(Vec<Segment>, T)>,
+}
+
+impl<T> Default for PathMatcher<T> {
+    fn default() -> Self {
+        PathMatcher { routes: Vec::new() }
+    }
+}
+
+impl<T> PathMatcher<T> {
+    pub fn insert(&mut self, template: &str, value: T) {
+        let segments = split(template)
+            .map(|segment| {
+                match segment
+                    .strip_prefix(':')
+                    .or_else(|| segment.strip_prefix('*'))
+                    .or_else(|| segment.strip_prefix('{').and_then(|s| s.strip_suffix('}')))
+                {
+                    Some(name) => Segment::Variable(name.to_owned()),
+                    None => Segment::Static(segment.to_owned()),
+                }
+            })
+            .collect();
+        self.routes.push((segments, value));
+    }
+
+    /// Find the value of the template matching `path`, ignoring any query string.
+    pub fn find(&self, path: &str) -> Option<&T> {
+        self.find_with_variables(path).map(|(value, _)| value)
+    }
+
+    /// Find the value of the template matching `path` and the raw (still percent-encoded) values
+    /// of its variables, ignoring any query string.
+    pub fn find_with_variables<'p>(&self, path: &'p str) -> Option<(&T, Vec<(&str, &'p str)>)> {
+        let path = path.split('?').next().unwrap_or_default();
+        let parts: Vec<&str> = split(path).collect();
+
+        // Reversed so that the first registered template wins among equally specific ones.
+        self.routes
+            .iter()
+            .rev()
+            .filter(|(segments, _)| segments.len() == parts.len())
+            .filter_map(|(segments, value)| {
+                let mut statics = 0;
+                let mut variables = Vec::new();
+                for (segment, part) in segments.iter().zip(&parts) {
+                    match segment {
+                        Segment::Static(s) if s == part => statics += 1,
+                        Segment::Static(_) => return None,
+                        Segment::Variable(name) => variables.push((name.as_str(), *part)),
+                    }
+                }
+                Some((statics, value, variables))
+            })
+            .max_by_key(|(statics, _, _)| *statics)
+            .map(|(_, value, variables)| (value, variables))
+    }
+}
+
+fn split(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/').filter(|s| !s.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn static_segments_win_over_variables() {
+        let mut matcher = PathMatcher::default();
+        matcher.insert("/users/{id}", "by id");
+        matcher.insert("/users/me", "me");
+        assert_eq!(matcher.find("/users/me"), Some(&"me"));
+        assert_eq!(matcher.find("/users/42?fields=name"), Some(&"by id"));
+        assert_eq!(matcher.find("/users"), None);
+        assert_eq!(matcher.find("/users/42/orders"), None);
+    }
+
+    #[test]
+    fn variables_are_captured_in_both_syntaxes() {
+        let mut matcher = PathMatcher::default();
+        matcher.insert("/users/:user/orders/{order}", ());
+        let (_, variables) = matcher.find_with_variables("/users/a%20b/orders/7/").unwrap();
+        assert_eq!(variables, [("user", "a%20b"), ("order", "7")]);
+    }
+
+    #[test]
+    fn the_first_template_wins_among_equally_specific_ones() {
+        let mut matcher = PathMatcher::default();
+        matcher.insert("/{a}", 1);
+        matcher.insert("/{b}", 2);
+        assert_eq!(matcher.find("/x"), Some(&1));
+    }
+}
@@ -0,0 +1,522 @@
+//! A mock server answering every operation of a specification with example responses.
+//!
+//! ```no_run
+//! # use okapi::openapi3::OpenApi;
+//! # use openapi_rs::mock::MockServer;
+//! # async fn serve() -> Result<(), Box<dyn std::error::Error>> {
+//! let openapi: OpenApi = serde_json::from_str(&std::fs::read_to_string("openapi.json")?)?;
+//! let app = MockServer::new(&openapi).validate_requests(true).into_router();
+//! axum::Server::bind(&"127.0.0.1:4010".parse()?)
+//!     .serve(app.into_make_service())
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The body of a response is the `example` of its media type, else its `default` or first
+//! `examples` entry, else a value synthesized from its schema. The status is the first documented
+//! success status unless the request selects another one with a `Prefer` header, e.g.
+//! `Prefer: code=404` or `Prefer: code=200, example=admin`. The `openapi-mock` binary (feature
+//! `cli`) serves a specification file this way.
+
+use std::sync::Arc;
+
+use axum::body::{Body, Bytes};
+use axum::handler::Handler;
+use axum::http::header::{HeaderValue, CONTENT_TYPE};
+use axum::http::{Request, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::{Json, Router};
+use okapi::openapi3::OpenApi;
+use serde_json::{json, Map, Value};
+use tower_layer::Layer;
+
+use crate::matcher::PathMatcher;
+use crate::request_validation::{RequestValidationLayer, PROBLEM_JSON};
+use crate::utils::{is_json, resolve_ref};
+
+/// Maximum number of items of the synthesized arrays, whatever their `minItems`.
+const MAX_ITEMS: u64 = 16;
+
+/// Builds a [`Router`] mocking the operations of a specification, see the
+/// [module documentation](self).
+#[derive(Debug, Clone)]
+pub struct MockServer {
+    openapi: OpenApi,
+    validate_requests: bool,
+}
+
+impl MockServer {
+    /// Mock the operations of `openapi`.
+    #[must_use]
+    pub fn new(openapi: &OpenApi) -> Self {
+        MockServer {
+            openapi: openapi.clone(),
+            validate_requests: false,
+        }
+    }
+
+    /// Reject the requests not matching the specification with the
+    /// [`RequestValidationLayer`] before answering them. Disabled by default.
+    #[must_use]
+    pub fn validate_requests(mut self, validate_requests: bool) -> Self {
+        self.validate_requests = validate_requests;
+        self
+    }
+
+    /// Create the router answering the operations.
+    ///
+    /// # Panics
+    /// When the specification cannot be serialized.
+    #[must_use]
+    pub fn into_router(self) -> Router {
+        let mut paths = PathMatcher::default();
+        for path in self.openapi.paths.keys() {
+            paths.insert(path, path.clone());
+        }
+        let mock = Arc::new(Mock {
+            document: serde_json::to_value(&self.openapi).expect("the specification serializes"),
+            paths,
+        });
+
+        let handler = move |request: Request<Body>| {
+            let mock = mock.clone();
+            async move { mock.respond(&request) }
+        };
+        if self.validate_requests {
+            Router::new()
+                .fallback(RequestValidationLayer::new(&self.openapi).layer(handler.into_service()))
+        } else {
+            Router::new().fallback(handler.into_service())
+        }
+    }
+}
+
+struct Mock {
+    document: Value,
+    paths: PathMatcher<String>,
+}
+
+/// The preferences of a `Prefer` header understood by the mock.
+#[derive(Debug, Default)]
+struct Preferences {
+    code: Option<String>,
+    example: Option<String>,
+}
+
+impl Mock {
+    fn respond(&self, request: &Request<Body>) -> Response {
+        let template = match self.paths.find(request.uri().path()) {
+            Some(template) => template,
+            None => {
+                return problem(
+                    StatusCode::NOT_FOUND,
+                    format!("No operation is documented for {}.", request.uri().path()),
+                )
+            }
+        };
+        let operation = match self.document["paths"][template]
+            .get(request.method().as_str().to_ascii_lowercase())
+        {
+            Some(operation) => operation,
+            None => {
+                return problem(
+                    StatusCode::METHOD_NOT_ALLOWED,
+                    format!("{} is not documented for {}.", request.method(), template),
+                )
+            }
+        };
+
+        let preferences = preferences(request);
+        let responses = operation.get("responses").and_then(Value::as_object);
+        let selected = match (responses, &preferences.code) {
+            (Some(responses), Some(code)) => select_response(responses, code),
+            (Some(responses), None) => default_response(responses),
+            (None, _) => None,
+        };
+        let (status, response) = match selected {
+            Some(selected) => selected,
+            None => {
+                let detail = match &preferences.code {
+                    Some(code) => format!(
+                        "Status {} is not documented for {} {}.",
+                        code,
+                        request.method(),
+                        template
+                    ),
+                    None => format!("No response is documented for {} {}.", request.method(), template),
+                };
+                return problem(StatusCode::BAD_REQUEST, detail);
+            }
+        };
+        let response = resolve_ref(&self.document, response);
+
+        let content = response.get("content").and_then(Value::as_object);
+        let media = content.and_then(|content| {
+            content
+                .iter()
+                .find(|(content_type, _)| is_json(content_type))
+                .or_else(|| content.iter().next())
+        });
+        let mut http_response = match media {
+            Some((content_type, media)) => {
+                let body = self.media_example(media, preferences.example.as_deref());
+                let bytes = match body {
+                    Value::String(s) if !is_json(content_type) => s.into_bytes(),
+                    body => serde_json::to_vec(&body).expect("JSON values serialize"),
+                };
+                let mut http_response = Bytes::from(bytes).into_response();
+                if let Ok(content_type) = HeaderValue::from_str(content_type) {
+                    http_response.headers_mut().insert(CONTENT_TYPE, content_type);
+                }
+                http_response
+            }
+            None => ().into_response(),
+        };
+        *http_response.status_mut() = status;
+        http_response
+    }
+
+    /// The example of `media`: the named example `name` if given, else `example`, else the
+    /// `default` or first of `examples`, else a value synthesized from the schema.
+    fn media_example(&self, media: &Value, name: Option<&str>) -> Value {
+        let examples = media.get("examples").and_then(Value::as_object);
+        let value = |example: &Value| {
+            resolve_ref(&self.document, example)
+                .get("value")
+                .cloned()
+                .unwrap_or(Value::Null)
+        };
+
+        if let Some(example) = name.and_then(|name| examples?.get(name)) {
+            return value(example);
+        }
+        if let Some(example) = media.get("example") {
+            return example.clone();
+        }
+        if let Some(example) =
+            examples.and_then(|examples| examples.get("default").or_else(|| examples.values().next()))
+        {
+            return value(example);
+        }
+        media
+            .get("schema")
+            .map(|schema| synthesize(&self.document, schema))
+            .unwrap_or(Value::Null)
+    }
+}
+
+/// Parse the `code` and `example` preferences of the `Prefer` headers of `request`.
+fn preferences<B>(request: &Request<B>) -> Preferences {
+    let mut preferences = Preferences::default();
+    let values = request
+        .headers()
+        .get_all("prefer")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split([',', ';']));
+    for preference in values {
+        match preference.trim().split_once('=') {
+            Some(("code", code)) => preferences.code = Some(code.trim_matches('"').to_owned()),
+            Some(("example", example)) => {
+                preferences.example = Some(example.trim_matches('"').to_owned());
+            }
+            _ => {}
+        }
+    }
+    preferences
+}
+
+/// The response documented for the status `code`: exact, then its range (`4XX`), then `default`.
+fn select_response<'a>(responses: &'a Map<String, Value>, code: &str) -> Option<(StatusCode, &'a Value)> {
+    let status = StatusCode::from_bytes(code.as_bytes()).ok()?;
+    let range = format!("{}XX", &code[..1]);
+    let response = responses
+        .get(code)
+        .or_else(|| responses.get(&range))
+        .or_else(|| responses.get(&range.to_ascii_lowercase()))
+        .or_else(|| responses.get("default"))?;
+    Some((status, response))
+}
+
+/// The first documented success response, else the first documented response, else `default`
+/// answered with `200 OK`.
+fn default_response(responses: &Map<String, Value>) -> Option<(StatusCode, &Value)> {
+    let status = |code: &str| StatusCode::from_bytes(code.replace(['X', 'x'], "0").as_bytes()).ok();
+    let mut documented: Vec<(StatusCode, &Value)> = responses
+        .iter()
+        .filter_map(|(code, response)| Some((status(code)?, response)))
+        .collect();
+    documented.sort_by_key(|(status, _)| *status);
+    documented
+        .iter()
+        .find(|(status, _)| status.is_success())
+        .or_else(|| documented.first())
+        .copied()
+        .or_else(|| Some((StatusCode::OK, responses.get("default")?)))
+}
+
+fn problem(status: StatusCode, detail: String) -> Response {
+    let mut response = (
+        status,
+        Json(json!({
+            "type": "about:blank",
+            "title": status.canonical_reason(),
+            "status": status.as_u16(),
+            "detail": detail,
+        })),
+    )
+        .into_response();
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static(PROBLEM_JSON));
+    response
+}
+
+/// Synthesize a value valid against `schema`: its `example`, `examples`, `default`, `enum` or
+/// `const` when present, else a placeholder of its type honouring the usual formats and bounds.
+/// References are resolved in `document`.
+///
+/// Recursive schemas stop at the first `$ref` already being synthesized: optional properties
+/// and arrays without `minItems` are left empty there, other values are `null`. Arrays have at
+/// most 16 items.
+#[must_use]
+pub fn synthesize(document: &Value, schema: &Value) -> Value {
+    synthesize_at(document, schema, &mut Vec::new()).unwrap_or(Value::Null)
+}
+
+/// Synthesize a value for `schema`, `None` when it refers back to one of the `$ref`s on the
+/// current path.
+fn synthesize_at<'a>(
+    document: &'a Value,
+    schema: &'a Value,
+    path: &mut Vec<&'a str>,
+) -> Option<Value> {
+    if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+        if path.contains(&reference) {
+            return None;
+        }
+        path.push(reference);
+        let value = synthesize_at(document, resolve_ref(document, schema), path);
+        path.pop();
+        return value;
+    }
+    let schema = match schema {
+        Value::Object(schema) => schema,
+        _ => return Some(Value::Null),
+    };
+
+    if let Some(value) = schema
+        .get("example")
+        .or_else(|| schema.get("examples").and_then(|examples| examples.get(0)))
+        .or_else(|| schema.get("default"))
+        .or_else(|| schema.get("enum").and_then(|values| values.get(0)))
+        .or_else(|| schema.get("const"))
+    {
+        return Some(value.clone());
+    }
+    if let Some(Value::Array(schemas)) = schema.get("allOf") {
+        let mut merged = Map::new();
+        for sub in schemas {
+            match synthesize_at(document, sub, path)? {
+                Value::Object(object) => merged.extend(object),
+                other if schemas.len() == 1 => return Some(other),
+                _ => {}
+            }
+        }
+        return Some(Value::Object(merged));
+    }
+    if let Some(first) = ["oneOf", "anyOf"]
+        .iter()
+        .find_map(|key| schema.get(*key)?.get(0))
+    {
+        return synthesize_at(document, first, path);
+    }
+
+    let instance_type = match schema.get("type") {
+        Some(Value::String(t)) => Some(t.as_str()),
+        Some(Value::Array(types)) => types.iter().filter_map(Value::as_str).find(|t| *t != "null"),
+        _ => None,
+    }
+    .or_else(|| schema.contains_key("properties").then_some("object"));
+    let number = |key: &str| schema.get(key).and_then(Value::as_f64);
+    let value = match instance_type {
+        Some("object") => {
+            let required = |name: &str| {
+                schema
+                    .get("required")
+                    .and_then(Value::as_array)
+                    .is_some_and(|required| required.iter().any(|r| r == name))
+            };
+            let properties = schema.get("properties").and_then(Value::as_object);
+            let mut object = Map::new();
+            for (name, property) in properties.into_iter().flatten() {
+                match synthesize_at(document, property, path) {
+                    Some(value) => {
+                        object.insert(name.clone(), value);
+                    }
+                    None if required(name) => {
+                        object.insert(name.clone(), Value::Null);
+                    }
+                    None => {}
+                }
+            }
+            Value::Object(object)
+        }
+        Some("array") => {
+            let min_items = schema.get("minItems").and_then(Value::as_u64);
+            let item = match schema.get("items") {
+                Some(items) => synthesize_at(document, items, path),
+                None => Some(Value::Null),
+            };
+            match item {
+                Some(item) => {
+                    let len = min_items.unwrap_or(1).clamp(1, MAX_ITEMS);
+                    Value::Array(vec![item; len as usize])
+                }
+                None if min_items.unwrap_or(0) == 0 => Value::Array(Vec::new()),
+                None => Value::Null,
+            }
+        }
+        Some("string") => {
+            let value = match schema.get("format").and_then(Value::as_str) {
+                Some("date-time") => "1970-01-01T00:00:00Z",
+                Some("date") => "1970-01-01",
+                Some("time") => "00:00:00",
+                Some("uuid") => "00000000-0000-0000-0000-000000000000",
+                Some("email") => "user@example.com",
+                Some("uri") | Some("url") => "https://example.com",
+                Some("hostname") => "example.com",
+                Some("ipv4") => "127.0.0.1",
+                Some("ipv6") => "::1",
+                _ => "string",
+            };
+            let mut value = value.to_owned();
+            if let Some(min_length) = schema.get("minLength").and_then(Value::as_u64) {
+                while (value.chars().count() as u64) < min_length {
+                    value.push('x');
+                }
+            }
+            if let Some(max_length) = schema.get("maxLength").and_then(Value::as_u64) {
+                value = value.chars().take(max_length as usize).collect();
+            }
+            Value::String(value)
+        }
+        Some("integer") => {
+            let value = number("minimum").map_or(0, |minimum| minimum.ceil() as i64);
+            let value = number("maximum").map_or(value, |maximum| value.min(maximum.floor() as i64));
+            Value::from(value)
+        }
+        Some("number") => {
+            let value = number("minimum").unwrap_or(0.0);
+            Value::from(number("maximum").map_or(value, |maximum| value.min(maximum)))
+        }
+        Some("boolean") => Value::Bool(true),
+        _ => Value::Null,
+    };
+    Some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use axum::body::HttpBody;
+    use tower::ServiceExt;
+
+    fn document() -> Value {
+        json!({ "components": { "schemas": {
+            "Node": {
+                "type": "object",
+                "required": ["value", "parent"],
+                "properties": {
+                    "value": { "type": "integer", "minimum": 3 },
+                    "parent": { "$ref": "#/components/schemas/Node" },
+                    "next": { "$ref": "#/components/schemas/Node" },
+                    "children": {
+                        "type": "array",
+                        "items": { "$ref": "#/components/schemas/Node" },
+                    },
+                    "tree": { "$ref": "#/components/schemas/Tree" },
+                },
+            },
+            "Tree": {
+                "type": "object",
+                "properties": {
+                    "root": { "$ref": "#/components/schemas/Node" },
+                    "size": { "type": "integer" },
+                },
+            },
+        } } })
+    }
+
+    #[test]
+    fn recursive_schemas_stop_at_the_first_repeated_reference() {
+        let document = document();
+        let node = synthesize(&document, &json!({ "$ref": "#/components/schemas/Node" }));
+        assert_eq!(
+            node,
+            json!({ "value": 3, "parent": null, "children": [], "tree": { "size": 0 } })
+        );
+    }
+
+    #[test]
+    fn arrays_honour_min_items_up_to_a_limit() {
+        let document = json!({});
+        let strings = |min_items: u64| {
+            let items = json!({ "type": "string", "format": "uuid" });
+            synthesize(&document, &json!({ "type": "array", "minItems": min_items, "items": items }))
+        };
+        assert_eq!(strings(3).as_array().unwrap().len(), 3);
+        assert_eq!(strings(1_000_000_000).as_array().unwrap().len(), 16);
+        assert_eq!(strings(0), json!(["00000000-0000-0000-0000-000000000000"]));
+    }
+
+    #[test]
+    fn examples_take_precedence_over_synthesized_values() {
+        let document = json!({});
+        let schema = json!({ "type": "string", "minLength": 8, "maxLength": 10 });
+        assert_eq!(synthesize(&document, &schema), json!("stringxx"));
+        let schema = json!({ "type": "string", "enum": ["a", "b"] });
+        assert_eq!(synthesize(&document, &schema), json!("a"));
+        let schema = json!({ "allOf": [
+            { "properties": { "a": { "example": 1 } } },
+            { "properties": { "b": { "type": "boolean" } } },
+        ] });
+        assert_eq!(synthesize(&document, &schema), json!({ "a": 1, "b": true }));
+    }
+
+    #[tokio::test]
+    async fn the_mock_answers_with_the_preferred_example() {
+        let openapi: OpenApi = serde_json::from_value(json!({
+            "openapi": "3.0.0",
+            "info": { "title": "test", "version": "1" },
+            "paths": { "/users/{id}": { "get": { "responses": {
+                "200": { "description": "OK", "content": { "application/json": {
+                    "examples": {
+                        "ada": { "value": { "name": "Ada" } },
+                        "root": { "value": { "name": "root" } },
+                    },
+                } } },
+                "404": { "description": "Not Found" },
+            } } } },
+        }))
+        .unwrap();
+        let router = MockServer::new(&openapi).into_router();
+        let send = |prefer: &str| {
+            let request = Request::get("/users/1")
+                .header("prefer", prefer)
+                .body(Body::empty())
+                .unwrap();
+            router.clone().oneshot(request)
+        };
+
+        let mut response = send("example=root").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.body_mut().data().await.unwrap().unwrap();
+        assert_eq!(serde_json::from_slice::<Value>(&body).unwrap(), json!({ "name": "root" }));
+
+        assert_eq!(send("code=404").await.unwrap().status(), StatusCode::NOT_FOUND);
+        assert_eq!(send("code=500").await.unwrap().status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,442 @@
+use std::fmt::Display;
+
+use okapi::openapi3::{Object, Parameter, ParameterStyle, ParameterValue, RefOr};
+use schemars::schema::{InstanceType, Schema, SchemaObject, SingleOrVec};
+use serde_json::Value;
+
+/// The location of a [`Parameter`], the `in` field of the OpenAPI Parameter Object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ParameterLocation {
+    Query,
+    Path,
+    Header,
+    Cookie,
+}
+
+impl ParameterLocation {
+    /// The value of the `in` field for this location.
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ParameterLocation::Query => "query",
+            ParameterLocation::Path => "path",
+            ParameterLocation::Header => "header",
+            ParameterLocation::Cookie => "cookie",
+        }
+    }
+}
+
+impl Display for ParameterLocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Builds a [`Parameter`] from a schema for any [`ParameterLocation`], following the rules of the
+/// OpenAPI specification:
+/// - path parameters are always required,
+/// - `allowEmptyValue` and `allowReserved` are only kept for query parameters.
+///
+/// ```rust,ignore
+/// let parameter = ParameterBuilder::new("id", ParameterLocation::Path, gen.json_schema::<u64>())
+///     .description("The id of the user")
+///     .build();
+/// ```
+#[derive(Debug, Clone)]
+pub struct ParameterBuilder {
+    name: String,
+    location: ParameterLocation,
+    schema: SchemaObject,
+    description: Option<String>,
+    required: bool,
+    deprecated: bool,
+    allow_empty_value: bool,
+    style: Option<ParameterStyle>,
+    explode: Option<bool>,
+    allow_reserved: bool,
+    example: Option<Value>,
+    extensions: Object,
+}
+
+impl ParameterBuilder {
+    /// Start a parameter named `name` in `location`. The description and example are taken from
+    /// the schema and the parameter is required unless the schema is nullable.
+    pub fn new(name: impl Into<String>, location: ParameterLocation, schema: SchemaObject) -> Self {
+        let nullable = schema
+            .extensions
+            .get("nullable")
+            .and_then(Value::as_bool)
+            .unwrap_or(false)
+            || schema
+                .instance_type
+                .as_ref()
+                .is_some_and(|types| types.contains(&InstanceType::Null));
+        let description = schema.metadata.as_ref().and_then(|m| m.description.clone());
+        let example = schema.metadata.as_ref().and_then(|m| m.examples.first().cloned());
+
+        ParameterBuilder {
+            name: name.into(),
+            location,
+            schema,
+            description,
+            required: !nullable,
+            deprecated: false,
+            allow_empty_value: false,
+            style: None,
+            explode: None,
+            allow_reserved: false,
+            example,
+            extensions: Object::default(),
+        }
+    }
+
+    /// Set whether the parameter is required. Ignored for path parameters, which always are.
+    #[must_use]
+    pub fn required(mut self, required: bool) -> Self {
+        self.required = required;
+        self
+    }
+
+    /// Set the description of the parameter.
+    #[must_use]
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Mark the parameter as deprecated.
+    #[must_use]
+    pub fn deprecated(mut self, deprecated: bool) -> Self {
+        self.deprecated = deprecated;
+        self
+    }
+
+    /// Allow sending the parameter with an empty value. Only applies to query parameters.
+    #[must_use]
+    pub fn allow_empty_value(mut self, allow_empty_value: bool) -> Self {
+        self.allow_empty_value = allow_empty_value;
+        self
+    }
+
+    /// Set the serialization style of the parameter value.
+    #[must_use]
+    pub fn style(mut self, style: ParameterStyle) -> Self {
+        self.style = Some(style);
+        self
+    }
+
+    /// Set whether arrays and objects generate separate parameters for each value.
+    #[must_use]
+    pub fn explode(mut self, explode: bool) -> Self {
+        self.explode = Some(explode);
+        self
+    }
+
+    /// Allow reserved characters (RFC 3986) without percent-encoding. Only applies to query
+    /// parameters.
+    #[must_use]
+    pub fn allow_reserved(mut self, allow_reserved: bool) -> Self {
+        self.allow_reserved = allow_reserved;
+        self
+    }
+
+    /// Set the example value of the parameter.
+    #[must_use]
+    pub fn example(mut self, example: Value) -> Self {
+        self.example = Some(example);
+        self
+    }
+
+    /// Add a specification extension (`x-*`) to the parameter.
+    #[must_use]
+    pub fn extension(mut self, key: impl Into<String>, value: Value) -> Self {
+        self.extensions.insert(key.into(), value);
+        self
+    }
+
+    /// Apply the metadata declared with `#[param(...)]` on a struct field.
+    #[must_use]
+    pub fn attributes(mut self, attributes: ParameterAttributes) -> Self {
+        attributes.constraints.apply(&mut self.schema);
+        if let Some(description) = attributes.description {
+            self.description = Some(description);
+        }
+        if let Some(example) = attributes.example {
+            self.example = Some(example_value(&self.schema, example));
+        }
+        self.deprecated |= attributes.deprecated;
+        self.allow_empty_value |= attributes.allow_empty_value;
+        if attributes.style.is_some() {
+            self.style = attributes.style;
+        }
+        if attributes.explode.is_some() {
+            self.explode = attributes.explode;
+        }
+        self.allow_reserved |= attributes.allow_reserved;
+        self
+    }
+
+    /// Create the [`Parameter`].
+    #[must_use]
+    pub fn build(self) -> Parameter {
+        let is_query = self.location == ParameterLocation::Query;
+        let mut extensions = self.extensions;
+        // okapi serializes its `allow_reserved` field under that name instead of `allowReserved`.
+        if self.allow_reserved && is_query {
+            extensions.insert("allowReserved".to_owned(), Value::Bool(true));
+        }
+
+        Parameter {
+            name: self.name,
+            location: self.location.as_str().to_owned(),
+            description: self.description,
+            required: self.required || self.location == ParameterLocation::Path,
+            deprecated: self.deprecated,
+            allow_empty_value: self.allow_empty_value && is_query,
+            value: ParameterValue::Schema {
+                style: self.style,
+                explode: self.explode,
+                allow_reserved: false,
+                schema: self.schema,
+                example: self.example,
+                examples: None,
+            },
+            extensions,
+        }
+    }
+}
+
+/// Create a [`Parameter`] in `location` from a schema.
+///
+/// A `nullable` schema makes the parameter optional, even when `required` is set.
+pub fn parameter_from_schema(
+    schema: SchemaObject,
+    name: String,
+    required: bool,
+    location: ParameterLocation,
+) -> Parameter {
+    let builder = ParameterBuilder::new(name, location, schema);
+    let required = required && builder.required;
+    builder.required(required).build()
+}
+
+/// Metadata declared with `#[param(...)]` on a field of a parameter struct.
+#[derive(Debug, Clone, Default)]
+pub struct ParameterAttributes {
+    /// Overrides the description taken from the schema.
+    pub description: Option<String>,
+    /// Example value. Parsed as JSON unless the parameter schema is a string.
+    pub example: Option<String>,
+    /// Marks the parameter as deprecated.
+    pub deprecated: bool,
+    /// Allow sending the parameter with an empty value. Only applies to query parameters.
+    pub allow_empty_value: bool,
+    /// Serialization style of the parameter value.
+    pub style: Option<ParameterStyle>,
+    /// Whether arrays and objects generate separate parameters for each value.
+    pub explode: Option<bool>,
+    /// Allow reserved characters (RFC 3986) without percent-encoding. Only applies to query
+    /// parameters.
+    pub allow_reserved: bool,
+    /// Validation constraints that surface as JSON Schema keywords.
+    pub constraints: Constraints,
+}
+
+/// `validator`-style constraints of a field, e.g. `#[validate(length(min = 1), range(max = 100))]`.
+#[derive(Debug, Clone, Default)]
+pub struct Constraints {
+    /// `range(min = ..)`
+    pub minimum: Option<f64>,
+    /// `range(max = ..)`
+    pub maximum: Option<f64>,
+    /// `length(min = ..)`, applied as `minLength` or `minItems` depending on the schema type.
+    pub min_length: Option<u32>,
+    /// `length(max = ..)`, applied as `maxLength` or `maxItems` depending on the schema type.
+    pub max_length: Option<u32>,
+    /// Regular expression the value has to match.
+    pub pattern: Option<String>,
+    /// `email` or `url`, applied as the `format` of the schema.
+    pub format: Option<String>,
+}
+
+impl Constraints {
+    /// Add the constraints as keywords to `schema`.
+    pub fn apply(&self, schema: &mut SchemaObject) {
+        if self.minimum.is_some() {
+            schema.number().minimum = self.minimum;
+        }
+        if self.maximum.is_some() {
+            schema.number().maximum = self.maximum;
+        }
+        if schema.has_type(InstanceType::Array) {
+            if self.min_length.is_some() {
+                schema.array().min_items = self.min_length;
+            }
+            if self.max_length.is_some() {
+                schema.array().max_items = self.max_length;
+            }
+        } else {
+            if self.min_length.is_some() {
+                schema.string().min_length = self.min_length;
+            }
+            if self.max_length.is_some() {
+                schema.string().max_length = self.max_length;
+            }
+        }
+        if self.pattern.is_some() {
+            schema.string().pattern = self.pattern.clone();
+        }
+        if self.format.is_some() {
+            schema.format = self.format.clone();
+        }
+    }
+}
+
+/// A field of a parameter struct, as passed by the derive macros.
+#[derive(Debug, Clone)]
+pub struct ParameterField {
+    /// The name of the field after serde renaming.
+    pub name: &'static str,
+    /// Metadata declared on the field.
+    pub attributes: ParameterAttributes,
+}
+
+/// Create one [`Parameter`] for every property of an object schema, in `location`.
+///
+/// When the schema is not an inline object (e.g. it only holds a reference) a parameter with
+/// the whole schema is created for each of the `fields` instead.
+pub fn parameters_from_schema(
+    schema: SchemaObject,
+    location: ParameterLocation,
+    fields: Vec<ParameterField>,
+) -> Vec<RefOr<Parameter>> {
+    let is_object = matches!(
+        &schema.instance_type,
+        Some(SingleOrVec::Single(instance_type)) if **instance_type == InstanceType::Object
+    );
+
+    if !is_object {
+        return fields
+            .into_iter()
+            .map(|field| {
+                let parameter = ParameterBuilder::new(field.name, location, schema.clone())
+                    .attributes(field.attributes)
+                    .build();
+                RefOr::Object(parameter)
+            })
+            .collect();
+    }
+
+    let mut fields = fields;
+    let object = schema.object.unwrap_or_default();
+    object
+        .properties
+        .into_iter()
+        .map(|(key, property)| {
+            let prop_schema = match property {
+                Schema::Object(x) => x,
+                _ => SchemaObject::default(),
+            };
+            let required = object.required.contains(&key);
+            let mut builder = ParameterBuilder::new(key, location, prop_schema);
+            let required = required && builder.required;
+            builder = builder.required(required);
+            if let Some(index) = fields.iter().position(|f| f.name == builder.name) {
+                builder = builder.attributes(fields.remove(index).attributes);
+            }
+            RefOr::Object(builder.build())
+        })
+        .collect()
+}
+
+/// Names of the variables in a path template, in order.
+///
+/// Both the axum syntax (`/users/:id/*rest`) and the OpenAPI syntax (`/users/{id}`) are
+/// understood.
+#[must_use]
+pub fn path_template_variables(path: &str) -> Vec<String> {
+    path.split('/')
+        .filter_map(|segment| {
+            if let Some(name) = segment.strip_prefix(':').or_else(|| segment.strip_prefix('*')) {
+                Some(name.to_owned())
+            } else {
+                segment
+                    .strip_prefix('{')
+                    .and_then(|s| s.strip_suffix('}'))
+                    .map(str::to_owned)
+            }
+        })
+        .collect()
+}
+
+/// Convert an axum route (`/users/:id/*rest`) to an OpenAPI path template (`/users/{id}/{rest}`).
+/// Paths already using the OpenAPI syntax are returned unchanged.
+#[must_use]
+pub fn openapi_path(path: &str) -> String {
+    path.split('/')
+        .map(|segment| match segment.strip_prefix(':').or_else(|| segment.strip_prefix('*')) {
+            Some(name) => format!("{{{}}}", name),
+            None => segment.to_owned(),
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Create `in: path` parameters by matching `schemas` to the variables of `path` by position.
+/// Used for path extractors that do not name their variables, such as `Path<(u32, String)>`.
+pub fn positional_path_parameters(path: &str, schemas: Vec<SchemaObject>) -> Vec<RefOr<Parameter>> {
+    path_template_variables(path)
+        .into_iter()
+        .zip(schemas)
+        .map(|(name, schema)| {
+            RefOr::Object(ParameterBuilder::new(name, ParameterLocation::Path, schema).build())
+        })
+        .collect()
+}
+
+fn example_value(schema: &SchemaObject, value: String) -> Value {
+    if schema.has_type(InstanceType::String) {
+        return Value::String(value);
+    }
+    serde_json::from_str(&value).unwrap_or(Value::String(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn string_schema() -> SchemaObject {
+        SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            ..SchemaObject::default()
+        }
+    }
+
+    #[test]
+    fn allow_reserved_serializes_as_camel_case() {
+        let parameter = ParameterBuilder::new("redirect", ParameterLocation::Query, string_schema())
+            .allow_reserved(true)
+            .build();
+        let json = serde_json::to_value(&parameter).unwrap();
+        assert_eq!(json["allowReserved"], true);
+        assert!(json.get("allow_reserved").is_none());
+    }
+
+    #[test]
+    fn allow_reserved_is_dropped_outside_query() {
+        let parameter = ParameterBuilder::new("x-redirect", ParameterLocation::Header, string_schema())
+            .allow_reserved(true)
+            .build();
+        let json = serde_json::to_value(&parameter).unwrap();
+        assert!(json.get("allowReserved").is_none());
+        assert!(json.get("allow_reserved").is_none());
+    }
+
+    #[test]
+    fn path_parameters_are_required() {
+        let parameter = ParameterBuilder::new("id", ParameterLocation::Path, string_schema())
+            .required(false)
+            .build();
+        assert!(parameter.required);
+    }
+}
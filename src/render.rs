@@ -0,0 +1,51 @@
+//! Rendering of a finished [`OpenApi`] document.
+//!
+//! Objects are written in the field order of the OpenAPI specification and maps in the order of
+//! the document, see [`crate::settings::KeyOrder`]. [`to_canonical_json`] ignores both and sorts
+//! every key, for output that only changes when the content does.
+
+use okapi::openapi3::OpenApi;
+use serde_json::Value;
+
+use crate::error::{OpenApiError, Result};
+
+/// Render `openapi` as pretty-printed JSON.
+pub fn to_json(openapi: &OpenApi) -> Result<String> {
+    serde_json::to_string_pretty(openapi).map_err(|e| OpenApiError::Serialize(e.to_string()))
+}
+
+/// Render `openapi` as canonical JSON: pretty-printed with two spaces, the keys of every object
+/// sorted, and a trailing newline. Suited to committed snapshots of the specification.
+pub fn to_canonical_json(openapi: &OpenApi) -> Result<String> {
+    let value =
+        serde_json::to_value(openapi).map_err(|e| OpenApiError::Serialize(e.to_string()))?;
+    let mut json = serde_json::to_string_pretty(&canonicalize(value))
+        .map_err(|e| OpenApiError::Serialize(e.to_string()))?;
+    json.push('\n');
+    Ok(json)
+}
+
+/// Render `openapi` as YAML.
+#[cfg(feature = "yaml")]
+pub fn to_yaml(openapi: &OpenApi) -> Result<String> {
+    serde_yaml::to_string(openapi).map_err(|e| OpenApiError::Serialize(e.to_string()))
+}
+
+/// Sort the keys of every object in `value`. Only needed with `serde_json/preserve_order`, where
+/// objects keep their insertion order.
+pub(crate) fn canonicalize(value: Value) -> Value {
+    match value {
+        Value::Object(object) => {
+            let mut entries: Vec<_> = object.into_iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key, canonicalize(value)))
+                    .collect(),
+            )
+        }
+        Value::Array(items) => Value::Array(items.into_iter().map(canonicalize).collect()),
+        value => value,
+    }
+}
@@ -1,10 +1,15 @@
 use std::borrow::Cow;
 
 use super::OpenApiFromData;
+use super::example::schema_example;
 use super::gen::OpenApiGenerator;
+use super::parameter::{positional_path_parameters, ParameterLocation};
 use axum::{Json, http::Error, body::Bytes};
 use okapi::{
-    openapi3::{MediaType, RequestBody, SecurityScheme, Parameter, SecurityRequirement, Responses},
+    openapi3::{
+        MediaType, Object, Parameter, RefOr, RequestBody, Responses, SecurityRequirement,
+        SecurityScheme, SecuritySchemeData,
+    },
     Map,
 };
 use schemars::JsonSchema;
@@ -14,6 +19,7 @@ use anyhow::Result;
 macro_rules! fn_request_body {
     ($gen:ident, $ty:path, $mime_type:expr) => {{
         let schema = $gen.json_schema::<$ty>();
+        let example = schema_example($gen, &schema);
         Ok(RequestBody {
             content: {
                 let mut map = Map::new();
@@ -21,6 +27,7 @@ macro_rules! fn_request_body {
                     $mime_type.to_owned(),
                     MediaType {
                         schema: Some(schema),
+                        example,
                         ..MediaType::default()
                     },
                 );
@@ -94,9 +101,16 @@ impl<'r, T: OpenApiFromData<'r>> OpenApiFromData<'r> for Option<T> {
 pub enum RequestHeaderInput {
     /// This request header requires no input anywhere
     None,
-    /// Useful for when you want to set a header per route.
+    /// Useful for when you want to set a header per route. Build the parameter with
+    /// [`ParameterBuilder`](crate::ParameterBuilder):
+    /// ```rust,ignore
+    /// let schema = gen.json_schema::<String>();
+    /// let parameter = ParameterBuilder::new("x-api-version", ParameterLocation::Header, schema).build();
+    /// Ok(RequestHeaderInput::Parameter(parameter))
+    /// ```
     Parameter(Parameter),
-    /// The request guard implements a security scheme.
+    /// The request guard implements a security scheme. Handlers document it by naming the
+    /// extractor in `#[handler(security = "BearerAuth")]`.
     ///
     /// Parameters:
     /// - The name of the [`SecurityScheme`].
@@ -105,6 +119,30 @@ pub enum RequestHeaderInput {
     Security(String, SecurityScheme, SecurityRequirement),
 }
 
+impl RequestHeaderInput {
+    /// A security scheme authenticating with the value of the cookie `cookie_name`, e.g. a
+    /// browser session cookie. `scheme_name` is the name of the scheme in
+    /// `components/securitySchemes`.
+    pub fn cookie_security(
+        scheme_name: impl Into<String>,
+        cookie_name: impl Into<String>,
+        description: Option<String>,
+    ) -> Self {
+        let scheme_name = scheme_name.into();
+        let scheme = SecurityScheme {
+            description,
+            data: SecuritySchemeData::ApiKey {
+                name: cookie_name.into(),
+                location: ParameterLocation::Cookie.as_str().to_owned(),
+            },
+            extensions: Object::default(),
+        };
+        let mut requirement = SecurityRequirement::new();
+        requirement.insert(scheme_name.clone(), Vec::new());
+        RequestHeaderInput::Security(scheme_name, scheme, requirement)
+    }
+}
+
 /// Trait that needs to be implemented for all types that implement
 /// [`FromRequest`](rocket::request::FromRequest).
 /// This trait specifies what headers or other parameters are required for this
@@ -134,3 +172,75 @@ pub trait OpenApiFromRequest<B>: axum::extract::FromRequest<B> {
         Ok(Responses::default())
     }
 }
+
+/// Trait implemented by the structs used with [`Query`](axum::extract::Query), listing the
+/// query parameters they accept.
+///
+/// Implement it with the derive macro:
+/// ```rust,ignore
+/// use openapi_rs::OpenApiQuery;
+///
+/// #[derive(Deserialize, JsonSchema, OpenApiQuery)]
+/// pub struct Pagination {
+///     #[param(example = "2")]
+///     page: Option<u32>,
+///     #[validate(range(min = 1, max = 100))]
+///     per_page: Option<u32>,
+/// }
+/// ```
+pub trait QueryParams {
+    /// Return one `in: query` [`Parameter`] for every field of the struct.
+    fn query_parameters(gen: &mut OpenApiGenerator) -> Result<Vec<RefOr<Parameter>>>;
+}
+
+/// Trait implemented by the types used with [`Path`](axum::extract::Path), listing the path
+/// parameters they bind.
+///
+/// It is implemented for scalars and tuples, which bind the variables of the path template by
+/// position. Structs implement it with `#[derive(OpenApiPath)]`.
+pub trait PathParams {
+    /// Return one `in: path` [`Parameter`] for every variable of the template `path` bound by
+    /// this type.
+    fn path_parameters(gen: &mut OpenApiGenerator, path: &str) -> Result<Vec<RefOr<Parameter>>>;
+}
+
+macro_rules! impl_path_params_scalar {
+    ($($ty:ty),*) => {
+        $(
+            impl PathParams for $ty {
+                fn path_parameters(gen: &mut OpenApiGenerator, path: &str) -> Result<Vec<RefOr<Parameter>>> {
+                    Ok(positional_path_parameters(path, vec![gen.json_schema::<$ty>()]))
+                }
+            }
+        )*
+    };
+}
+
+impl_path_params_scalar!(String, bool, char, u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64);
+
+macro_rules! impl_path_params_tuple {
+    ($($name:ident),+) => {
+        impl<$($name: JsonSchema),+> PathParams for ($($name,)+) {
+            fn path_parameters(gen: &mut OpenApiGenerator, path: &str) -> Result<Vec<RefOr<Parameter>>> {
+                Ok(positional_path_parameters(path, vec![$(gen.json_schema::<$name>()),+]))
+            }
+        }
+    };
+}
+
+impl_path_params_tuple!(T1);
+impl_path_params_tuple!(T1, T2);
+impl_path_params_tuple!(T1, T2, T3);
+impl_path_params_tuple!(T1, T2, T3, T4);
+impl_path_params_tuple!(T1, T2, T3, T4, T5);
+impl_path_params_tuple!(T1, T2, T3, T4, T5, T6);
+
+/// Trait implemented by structs describing a group of request headers, used with
+/// `#[handler(headers = "MyHeaders")]` or from the [`OpenApiFromRequest`] implementation of a
+/// custom extractor.
+///
+/// Implement it with `#[derive(OpenApiHeaders)]`.
+pub trait HeaderParams {
+    /// Return one `in: header` [`Parameter`] for every field of the struct.
+    fn header_parameters(gen: &mut OpenApiGenerator) -> Result<Vec<RefOr<Parameter>>>;
+}
@@ -0,0 +1,780 @@
+//! A middleware validating requests against the specification before they reach the handlers.
+//!
+//! ```no_run
+//! # use axum::routing::get;
+//! # use axum::Router;
+//! # use openapi_rs::gen::OpenApiGenerator;
+//! # use openapi_rs::request_validation::RequestValidationLayer;
+//! # async fn get_user() {}
+//! # fn app(gen: OpenApiGenerator) -> openapi_rs::error::Result<Router> {
+//! let openapi = gen.into_openapi()?;
+//! let app = Router::new()
+//!     .route("/users/:id", get(get_user))
+//!     .layer(RequestValidationLayer::new(&openapi).max_body_size(64 * 1024));
+//! # Ok(app)
+//! # }
+//! ```
+//!
+//! The path, query, header and cookie parameters of the operation are checked for presence and
+//! against their schema, following their `style` and `explode` for arrays. `Content-Type`,
+//! `Accept` and `Authorization` header parameters are ignored, as OpenAPI requires. The request
+//! body is checked against the documented media types and, for JSON bodies, their schema: only
+//! those bodies are buffered, up to [`RequestValidationLayer::max_body_size`], other bodies reach
+//! the handler untouched.
+//!
+//! Schemas are checked by [`json_schema`](crate::json_schema), which ignores `pattern` and
+//! `format`: the patterns and the `email` or `uri` formats of `#[param(...)]` and
+//! `#[validate(...)]` are documented, not enforced.
+//!
+//! Invalid requests are rejected with an `application/problem+json` response listing every
+//! [`Violation`]: `413 Payload Too Large` when a JSON body exceeds the maximum size,
+//! `415 Unsupported Media Type` when the `Content-Type` is not documented, `400 Bad Request`
+//! otherwise. Requests to undocumented operations are passed through untouched.
+
+use std::borrow::Cow;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use axum::body::{Body, HttpBody};
+use axum::http::header::{
+    HeaderName, HeaderValue, ACCEPT, AUTHORIZATION, CONTENT_LENGTH, CONTENT_TYPE, COOKIE,
+};
+use axum::http::{Request, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use okapi::openapi3::OpenApi;
+use percent_encoding::percent_decode_str;
+use serde::Serialize;
+use serde_json::{json, Number, Value};
+use tower_layer::Layer;
+use tower_service::Service;
+
+use crate::json_schema;
+use crate::matcher::PathMatcher;
+use crate::utils::{essence, is_json, media_type, resolve_ref};
+
+/// Content type of the rejection responses.
+pub const PROBLEM_JSON: &str = "application/problem+json";
+
+/// Default maximum size in bytes of the JSON bodies buffered for validation.
+pub const DEFAULT_MAX_BODY_SIZE: usize = 2 * 1024 * 1024;
+
+/// Header parameters that OpenAPI ignores, described by other fields of the specification.
+const IGNORED_HEADERS: [HeaderName; 3] = [CONTENT_TYPE, ACCEPT, AUTHORIZATION];
+
+/// A part of a request not matching the specification.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Violation {
+    /// Where the invalid value is: `path`, `query`, `header`, `cookie` or `body`.
+    #[serde(rename = "in")]
+    pub location: String,
+    /// The name of the parameter, or the JSON pointer of the invalid value in the body.
+    pub name: String,
+    /// What is wrong.
+    pub message: String,
+}
+
+impl Violation {
+    fn new(location: &str, name: impl Into<String>, message: impl Into<String>) -> Self {
+        Violation {
+            location: location.to_owned(),
+            name: name.into(),
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Validator {
+    document: Value,
+    paths: PathMatcher<String>,
+    max_body_size: usize,
+}
+
+/// A [`Layer`] rejecting the requests that do not match the specification, see the
+/// [module documentation](self).
+#[derive(Clone)]
+pub struct RequestValidationLayer {
+    validator: Arc<Validator>,
+}
+
+impl std::fmt::Debug for RequestValidationLayer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RequestValidationLayer").finish_non_exhaustive()
+    }
+}
+
+impl RequestValidationLayer {
+    /// Create the layer validating requests against `openapi`.
+    ///
+    /// # Panics
+    /// When `openapi` cannot be serialized.
+    #[must_use]
+    pub fn new(openapi: &OpenApi) -> Self {
+        let mut paths = PathMatcher::default();
+        for path in openapi.paths.keys() {
+            paths.insert(path, path.clone());
+        }
+        RequestValidationLayer {
+            validator: Arc::new(Validator {
+                document: serde_json::to_value(openapi).expect("the specification serializes"),
+                paths,
+                max_body_size: DEFAULT_MAX_BODY_SIZE,
+            }),
+        }
+    }
+
+    /// Set the maximum size in bytes of the JSON bodies buffered for validation,
+    /// [`DEFAULT_MAX_BODY_SIZE`] by default. Larger bodies are rejected with
+    /// `413 Payload Too Large`.
+    #[must_use]
+    pub fn max_body_size(mut self, max_body_size: usize) -> Self {
+        Arc::make_mut(&mut self.validator).max_body_size = max_body_size;
+        self
+    }
+}
+
+impl<S> Layer<S> for RequestValidationLayer {
+    type Service = RequestValidationService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequestValidationService {
+            inner,
+            validator: self.validator.clone(),
+        }
+    }
+}
+
+/// The [`Service`] created by [`RequestValidationLayer`].
+#[derive(Clone)]
+pub struct RequestValidationService<S> {
+    inner: S,
+    validator: Arc<Validator>,
+}
+
+impl<S: std::fmt::Debug> std::fmt::Debug for RequestValidationService<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RequestValidationService")
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S> Service<Request<Body>> for RequestValidationService<S>
+where
+    S: Service<Request<Body>, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        // The service polled ready is the one called, see `tower::Service`.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let validator = self.validator.clone();
+
+        Box::pin(async move {
+            match validator.check(request).await {
+                Ok(request) => inner.call(request).await,
+                Err(rejection) => Ok(rejection),
+            }
+        })
+    }
+}
+
+impl Validator {
+    /// Check `request`, returning it (with its body buffered if it was read) when valid and the
+    /// rejection otherwise.
+    async fn check(&self, request: Request<Body>) -> Result<Request<Body>, Response> {
+        let path = request.uri().path().to_owned();
+        let (template, variables) = match self.paths.find_with_variables(&path) {
+            Some(found) => found,
+            None => return Ok(request),
+        };
+        let path_item = &self.document["paths"][template];
+        let operation = match path_item.get(request.method().as_str().to_ascii_lowercase()) {
+            Some(operation) => operation,
+            None => return Ok(request),
+        };
+
+        let mut violations = Vec::new();
+        for parameter in parameters(&self.document, path_item, operation) {
+            let (location, name) = match (
+                parameter.get("in").and_then(Value::as_str),
+                parameter.get("name").and_then(Value::as_str),
+            ) {
+                (Some(location), Some(name)) => (location, name),
+                _ => continue,
+            };
+            let ignored = |header: &HeaderName| header.as_str().eq_ignore_ascii_case(name);
+            if location == "header" && IGNORED_HEADERS.iter().any(ignored) {
+                continue;
+            }
+            let values: Vec<Cow<'_, str>> = match location {
+                "path" => variables
+                    .iter()
+                    .filter(|(variable, _)| *variable == name)
+                    .map(|(_, value)| Cow::Borrowed(*value))
+                    .collect(),
+                "query" => form_urlencoded::parse(request.uri().query().unwrap_or_default().as_bytes())
+                    .filter(|(key, _)| key == name)
+                    .map(|(_, value)| value)
+                    .collect(),
+                "header" => request
+                    .headers()
+                    .get_all(name)
+                    .iter()
+                    .filter_map(|value| value.to_str().ok())
+                    .map(Cow::Borrowed)
+                    .collect(),
+                "cookie" => request
+                    .headers()
+                    .get_all(COOKIE)
+                    .iter()
+                    .filter_map(|value| value.to_str().ok())
+                    .flat_map(|cookies| cookies.split(';'))
+                    .filter_map(|cookie| cookie.trim().split_once('='))
+                    .filter(|(key, _)| *key == name)
+                    .map(|(_, value)| Cow::Borrowed(value))
+                    .collect(),
+                _ => continue,
+            };
+
+            if values.is_empty() {
+                let required = location == "path"
+                    || parameter.get("required").and_then(Value::as_bool) == Some(true);
+                if required {
+                    violations.push(Violation::new(location, name, "is required"));
+                }
+                continue;
+            }
+            if let Some(schema) = parameter.get("schema") {
+                let is_array = schema_type(resolve_ref(&self.document, schema)) == Some("array");
+                let mut values = split_values(parameter, location, name, &values, is_array);
+                if location == "path" {
+                    values = values
+                        .into_iter()
+                        .map(|value| percent_decode_str(&value).decode_utf8_lossy().into_owned())
+                        .collect();
+                }
+                let values: Vec<&str> = values.iter().map(AsRef::as_ref).collect();
+                self.check_parameter(schema, &values, location, name, &mut violations);
+            }
+        }
+
+        let request_body = match operation.get("requestBody") {
+            Some(request_body) => resolve_ref(&self.document, request_body),
+            None => return self.rejection(&request, template, operation, violations).map_or(Ok(request), Err),
+        };
+
+        let (parts, body) = request.into_parts();
+        let content_length = parts
+            .headers
+            .get(CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<u64>().ok());
+        let content_type = match essence(&parts.headers) {
+            None if body.is_end_stream() || content_length == Some(0) => {
+                if request_body.get("required").and_then(Value::as_bool) == Some(true) {
+                    violations.push(Violation::new("body", "", "is required"));
+                }
+                let request = Request::from_parts(parts, body);
+                return self
+                    .rejection(&request, template, operation, violations)
+                    .map_or(Ok(request), Err);
+            }
+            content_type => content_type.unwrap_or_else(|| "application/octet-stream".to_owned()),
+        };
+
+        let content = request_body.get("content").and_then(Value::as_object);
+        let schema = match content.and_then(|content| media_type(content, &content_type)) {
+            Some(media) => media.get("schema").filter(|_| is_json(&content_type)),
+            None => {
+                let documented = content
+                    .map(|content| content.keys().cloned().collect::<Vec<_>>().join(", "))
+                    .unwrap_or_default();
+                violations.push(Violation::new(
+                    "header",
+                    CONTENT_TYPE.as_str(),
+                    format!("`{}` is not one of the documented media types: {}", content_type, documented),
+                ));
+                None
+            }
+        };
+        // Only the bodies validated here are buffered, the others are passed on as they are.
+        let schema = match schema {
+            Some(schema) => schema,
+            None => {
+                let request = Request::from_parts(parts, body);
+                return self
+                    .rejection(&request, template, operation, violations)
+                    .map_or(Ok(request), Err);
+            }
+        };
+
+        // The announced length rejects large bodies before reading them, the running total the
+        // bodies without one.
+        let bytes = if content_length.is_some_and(|length| length > self.max_body_size as u64) {
+            Err(None)
+        } else {
+            self.read_body(body).await
+        };
+        let bytes = match bytes {
+            Ok(bytes) => bytes,
+            Err(None) => {
+                violations.push(Violation::new(
+                    "body",
+                    "",
+                    format!("exceeds the maximum size of {} bytes", self.max_body_size),
+                ));
+                let request = Request::from_parts(parts, Body::empty());
+                return Err(self.problem(
+                    &request,
+                    template,
+                    operation,
+                    StatusCode::PAYLOAD_TOO_LARGE,
+                    violations,
+                ));
+            }
+            Err(Some(e)) => {
+                violations.push(Violation::new("body", "", format!("cannot be read: {}", e)));
+                let request = Request::from_parts(parts, Body::empty());
+                return self
+                    .rejection(&request, template, operation, violations)
+                    .map_or(Ok(request), Err);
+            }
+        };
+
+        self.check_json_body(schema, &bytes, &mut violations);
+        let request = Request::from_parts(parts, Body::from(bytes));
+        self.rejection(&request, template, operation, violations).map_or(Ok(request), Err)
+    }
+
+    /// Buffer `body`, failing with `None` once it exceeds the maximum size.
+    async fn read_body(&self, mut body: Body) -> Result<Vec<u8>, Option<axum::Error>> {
+        let mut bytes = Vec::new();
+        while let Some(chunk) = body.data().await {
+            let chunk = chunk.map_err(|e| Some(axum::Error::new(e)))?;
+            if bytes.len() + chunk.len() > self.max_body_size {
+                return Err(None);
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+        Ok(bytes)
+    }
+
+    fn check_parameter(
+        &self,
+        schema: &Value,
+        values: &[&str],
+        location: &str,
+        name: &str,
+        violations: &mut Vec<Violation>,
+    ) {
+        let value = match coerce(&self.document, schema, values) {
+            Ok(Some(value)) => value,
+            Ok(None) => return,
+            Err(message) => return violations.push(Violation::new(location, name, message)),
+        };
+        for error in json_schema::validate(&self.document, schema, &value) {
+            let message = if error.instance_path.is_empty() {
+                error.message
+            } else {
+                format!("{}: {}", error.instance_path, error.message)
+            };
+            violations.push(Violation::new(location, name, message));
+        }
+    }
+
+    fn check_json_body(&self, schema: &Value, bytes: &[u8], violations: &mut Vec<Violation>) {
+        let body: Value = match serde_json::from_slice(bytes) {
+            Ok(body) => body,
+            Err(e) => {
+                return violations.push(Violation::new("body", "", format!("is not valid JSON: {}", e)))
+            }
+        };
+        for error in json_schema::validate(&self.document, schema, &body) {
+            violations.push(Violation::new("body", error.instance_path, error.message));
+        }
+    }
+
+    /// The problem response listing `violations`, if any.
+    fn rejection(
+        &self,
+        request: &Request<Body>,
+        template: &str,
+        operation: &Value,
+        violations: Vec<Violation>,
+    ) -> Option<Response> {
+        if violations.is_empty() {
+            return None;
+        }
+        let status = if violations
+            .iter()
+            .any(|violation| violation.location == "header" && violation.name == CONTENT_TYPE.as_str())
+        {
+            StatusCode::UNSUPPORTED_MEDIA_TYPE
+        } else {
+            StatusCode::BAD_REQUEST
+        };
+        Some(self.problem(request, template, operation, status, violations))
+    }
+
+    /// The problem response with `status` listing `violations`.
+    fn problem(
+        &self,
+        request: &Request<Body>,
+        template: &str,
+        operation: &Value,
+        status: StatusCode,
+        violations: Vec<Violation>,
+    ) -> Response {
+        let operation = match operation.get("operationId").and_then(Value::as_str) {
+            Some(id) => format!("{} {} ({})", request.method(), template, id),
+            None => format!("{} {}", request.method(), template),
+        };
+        let problem = json!({
+            "type": "about:blank",
+            "title": status.canonical_reason(),
+            "status": status.as_u16(),
+            "detail": format!("The request does not match the specification of {}.", operation),
+            "errors": violations,
+        });
+
+        let mut response = (status, Json(problem)).into_response();
+        response
+            .headers_mut()
+            .insert(CONTENT_TYPE, HeaderValue::from_static(PROBLEM_JSON));
+        response
+    }
+}
+
+/// The parameters of `operation`, overriding those of `path_item` with the same name and location.
+fn parameters<'a>(document: &'a Value, path_item: &'a Value, operation: &'a Value) -> Vec<&'a Value> {
+    let list = |item: &'a Value| -> Vec<&'a Value> {
+        item.get("parameters")
+            .and_then(Value::as_array)
+            .map(|parameters| parameters.iter().map(|p| resolve_ref(document, p)).collect())
+            .unwrap_or_default()
+    };
+    let key = |p: &Value| (p.get("name").cloned(), p.get("in").cloned());
+
+    let own = list(operation);
+    let mut parameters: Vec<&Value> = list(path_item)
+        .into_iter()
+        .filter(|inherited| !own.iter().any(|p| key(p) == key(inherited)))
+        .collect();
+    parameters.extend(own);
+    parameters
+}
+
+/// Split the raw values of a parameter into the values of its items following its `style` and
+/// `explode`, or only strip the `label` and `matrix` prefixes from the value of a scalar.
+///
+/// Exploded `form`, `spaceDelimited` and `pipeDelimited` arrays repeat the parameter
+/// (`id=1&id=2`), the other styles separate the items with `,`, ` `, `|`, or `.` and `;` for
+/// exploded `label` and `matrix` arrays.
+fn split_values(
+    parameter: &Value,
+    location: &str,
+    name: &str,
+    values: &[Cow<'_, str>],
+    is_array: bool,
+) -> Vec<String> {
+    let style = parameter.get("style").and_then(Value::as_str).unwrap_or(match location {
+        "query" | "cookie" => "form",
+        _ => "simple",
+    });
+    let explode = parameter
+        .get("explode")
+        .and_then(Value::as_bool)
+        .unwrap_or(style == "form");
+
+    let values = values.iter().flat_map(|value| -> Vec<&str> {
+        match style {
+            "label" => {
+                let value = value.strip_prefix('.').unwrap_or(value);
+                match (is_array, explode) {
+                    (false, _) => vec![value],
+                    (true, true) => value.split('.').collect(),
+                    (true, false) => value.split(',').collect(),
+                }
+            }
+            "matrix" => {
+                let parts = value.split(';').filter(|part| !part.is_empty()).map(|part| {
+                    part.strip_prefix(name)
+                        .and_then(|part| part.strip_prefix('='))
+                        .unwrap_or(part)
+                });
+                match (is_array, explode) {
+                    (false, _) | (true, true) => parts.collect(),
+                    (true, false) => parts.flat_map(|part| part.split(',')).collect(),
+                }
+            }
+            _ if !is_array => vec![value],
+            "form" | "spaceDelimited" | "pipeDelimited" if explode => vec![value],
+            "spaceDelimited" => value.split(' ').collect(),
+            "pipeDelimited" => value.split('|').collect(),
+            _ => value.split(',').collect(),
+        }
+    });
+    values.map(str::to_owned).collect()
+}
+
+/// Convert the values of a parameter, split by [`split_values`], to the JSON value described by
+/// `schema`. Returns `None` for values that are not checked (objects and schemas without a type).
+fn coerce(document: &Value, schema: &Value, values: &[&str]) -> Result<Option<Value>, String> {
+    let schema = resolve_ref(document, schema);
+    match schema_type(schema) {
+        Some("array") => {
+            let items = schema.get("items").unwrap_or(&Value::Bool(true));
+            values
+                .iter()
+                .map(|value| coerce_scalar(resolve_ref(document, items), value))
+                .collect::<Result<Vec<_>, _>>()
+                .map(|items| Some(Value::Array(items)))
+        }
+        Some("object") | None => Ok(None),
+        Some(_) => coerce_scalar(schema, values[0]).map(Some),
+    }
+}
+
+fn coerce_scalar(schema: &Value, value: &str) -> Result<Value, String> {
+    let invalid = |expected: &str| format!("`{}` is not a valid {}", value, expected);
+    match schema_type(schema) {
+        Some("integer") => value
+            .parse::<i64>()
+            .map(Value::from)
+            .or_else(|_| value.parse::<u64>().map(Value::from))
+            .map_err(|_| invalid("integer")),
+        Some("number") => value
+            .parse::<f64>()
+            .ok()
+            .and_then(Number::from_f64)
+            .map(Value::Number)
+            .ok_or_else(|| invalid("number")),
+        Some("boolean") => match value {
+            "true" => Ok(Value::Bool(true)),
+            "false" => Ok(Value::Bool(false)),
+            _ => Err(invalid("boolean")),
+        },
+        _ => Ok(Value::String(value.to_owned())),
+    }
+}
+
+/// The first non-null `type` of `schema`.
+fn schema_type(schema: &Value) -> Option<&str> {
+    match schema.get("type")? {
+        Value::String(t) => Some(t.as_str()),
+        Value::Array(types) => types.iter().filter_map(Value::as_str).find(|t| *t != "null"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tower::{service_fn, ServiceExt};
+
+    fn openapi() -> OpenApi {
+        let integers = json!({ "type": "array", "items": { "type": "integer" } });
+        let query = |style: &str, explode: bool| {
+            json!({
+                "name": "ids",
+                "in": "query",
+                "style": style,
+                "explode": explode,
+                "schema": integers,
+            })
+        };
+        let path = |style: &str, explode: bool, schema: &Value| {
+            json!({
+                "name": "ids",
+                "in": "path",
+                "required": true,
+                "style": style,
+                "explode": explode,
+                "schema": schema,
+            })
+        };
+        let body = json!({
+            "required": true,
+            "content": {
+                "application/json": { "schema": {
+                    "type": "object",
+                    "required": ["name"],
+                    "properties": { "name": { "type": "string" } },
+                } },
+                "text/plain": {},
+            },
+        });
+        let header = |name: &str, schema: &str| {
+            json!({ "name": name, "in": "header", "required": true, "schema": { "type": schema } })
+        };
+        let get = |parameters: Value| {
+            json!({ "get": { "parameters": parameters, "responses": {} } })
+        };
+        serde_json::from_value(json!({
+            "openapi": "3.0.0",
+            "info": { "title": "test", "version": "1" },
+            "paths": {
+                "/form": get(json!([query("form", true)])),
+                "/csv": get(json!([query("form", false)])),
+                "/pipes": get(json!([query("pipeDelimited", false)])),
+                "/spaces": get(json!([query("spaceDelimited", false)])),
+                "/simple/{ids}": get(json!([path("simple", false, &integers)])),
+                "/matrix/{ids}": get(json!([path("matrix", true, &integers)])),
+                "/label/{ids}": get(json!([path("label", false, &json!({ "type": "integer" }))])),
+                "/headers": get(json!([
+                    header("Accept", "integer"),
+                    header("authorization", "string"),
+                    header("X-Count", "integer"),
+                ])),
+                "/users": { "post": { "requestBody": body, "responses": {} } },
+            },
+        }))
+        .unwrap()
+    }
+
+    async fn bytes<B: HttpBody<Data = axum::body::Bytes> + Unpin>(mut body: B) -> Vec<u8>
+    where
+        B::Error: std::fmt::Debug,
+    {
+        let mut bytes = Vec::new();
+        while let Some(chunk) = body.data().await {
+            bytes.extend_from_slice(&chunk.unwrap());
+        }
+        bytes
+    }
+
+    /// Send `request` through `layer` to a handler echoing the body it receives.
+    async fn send(layer: RequestValidationLayer, request: Request<Body>) -> (StatusCode, Value) {
+        let echo = service_fn(|request: Request<Body>| async move {
+            let body = bytes(request.into_body()).await;
+            let echo = json!({ "echo": String::from_utf8_lossy(&body) });
+            Ok::<_, std::convert::Infallible>(Json(echo).into_response())
+        });
+        let response = layer.layer(echo).oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = bytes(response.into_body()).await;
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    async fn get(uri: &str) -> (StatusCode, Value) {
+        let request = Request::get(uri).body(Body::empty()).unwrap();
+        send(RequestValidationLayer::new(&openapi()), request).await
+    }
+
+    fn post(content_type: &str, body: impl Into<Body>) -> Request<Body> {
+        Request::post("/users")
+            .header(CONTENT_TYPE, content_type)
+            .body(body.into())
+            .unwrap()
+    }
+
+    fn messages(problem: &Value) -> Vec<String> {
+        let errors = problem["errors"].as_array().cloned().unwrap_or_default();
+        errors
+            .iter()
+            .map(|e| {
+                let field = |key: &str| e[key].as_str().unwrap().to_owned();
+                format!("{} {}: {}", field("in"), field("name"), field("message"))
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn query_arrays_follow_style_and_explode() {
+        assert_eq!(get("/form?ids=1&ids=2").await.0, StatusCode::OK);
+        assert_eq!(
+            messages(&get("/form?ids=1,2").await.1),
+            ["query ids: `1,2` is not a valid integer"]
+        );
+        assert_eq!(get("/csv?ids=1,2").await.0, StatusCode::OK);
+        assert_eq!(get("/pipes?ids=1|2").await.0, StatusCode::OK);
+        assert_eq!(get("/spaces?ids=1%202").await.0, StatusCode::OK);
+        assert_eq!(
+            messages(&get("/spaces?ids=1,2").await.1),
+            ["query ids: `1,2` is not a valid integer"]
+        );
+    }
+
+    #[tokio::test]
+    async fn path_values_follow_style_and_explode() {
+        assert_eq!(get("/simple/1,2").await.0, StatusCode::OK);
+        assert_eq!(
+            messages(&get("/simple/1%2Cx").await.1),
+            ["path ids: `1,x` is not a valid integer"]
+        );
+        assert_eq!(get("/matrix/;ids=1;ids=2").await.0, StatusCode::OK);
+        assert_eq!(get("/label/.3").await.0, StatusCode::OK);
+        assert_eq!(
+            messages(&get("/label/.x").await.1),
+            ["path ids: `x` is not a valid integer"]
+        );
+    }
+
+    #[tokio::test]
+    async fn content_type_accept_and_authorization_headers_are_ignored() {
+        assert_eq!(
+            messages(&get("/headers").await.1),
+            ["header X-Count: is required"]
+        );
+        let request = Request::get("/headers")
+            .header(ACCEPT, "application/json")
+            .header("x-count", "2")
+            .body(Body::empty())
+            .unwrap();
+        let (status, _) = send(RequestValidationLayer::new(&openapi()), request).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn json_bodies_are_validated_and_passed_on() {
+        let layer = RequestValidationLayer::new(&openapi());
+        let request = post("application/json", r#"{"name":"Ada"}"#);
+        let (status, echo) = send(layer.clone(), request).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(echo["echo"], r#"{"name":"Ada"}"#);
+
+        let (status, problem) = send(layer.clone(), post("application/json", "{}")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(messages(&problem), ["body : missing required property `name`"]);
+
+        let (status, _) = send(layer, post("application/xml", "<user/>")).await;
+        assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    }
+
+    #[tokio::test]
+    async fn json_bodies_over_the_maximum_size_are_rejected() {
+        let layer = RequestValidationLayer::new(&openapi()).max_body_size(8);
+        let announced = Request::post("/users")
+            .header(CONTENT_TYPE, "application/json")
+            .header(CONTENT_LENGTH, "100")
+            .body(Body::from(r#"{"name":"Ada"}"#))
+            .unwrap();
+        let (status, problem) = send(layer.clone(), announced).await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(messages(&problem), ["body : exceeds the maximum size of 8 bytes"]);
+
+        let (status, _) = send(layer, post("application/json", r#"{"name":"Ada"}"#)).await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn other_bodies_are_not_buffered() {
+        let layer = RequestValidationLayer::new(&openapi()).max_body_size(8);
+        let (status, echo) = send(layer, post("text/plain", "longer than eight bytes")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(echo["echo"], "longer than eight bytes");
+    }
+}
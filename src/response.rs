@@ -1,15 +1,22 @@
+use axum::http::StatusCode;
+use axum::response::Html;
 use axum::Json;
-use okapi::{openapi3::{Responses}};
+use okapi::{openapi3::{MediaType, Responses}};
 use schemars::JsonSchema;
 use serde::Serialize;
 
-use crate::{gen::OpenApiGenerator, utils::{produce_any_responses, add_schema_response}};
+use crate::{example::schema_example, gen::OpenApiGenerator, utils::{produce_any_responses, add_schema_response, add_content_response}};
 
 use anyhow::Result;
 
 use super::utils::{ensure_status_code_exists};
 
 
+/// Documents the responses of a handler return type.
+///
+/// `#[handler]` requires it of the return type, unless that is `impl IntoResponse`. Types whose
+/// status is only known at run time, such as `StatusCode`, document a `200` response; declare the
+/// others with `#[handler(response(status = ..., description = "..."))]`.
 pub trait OpenApiResponderInner {
     /// Create the responses type, which is a list of responses that can be
     /// rendered in `openapi.json` format.
@@ -20,7 +27,12 @@ impl<T: Serialize + JsonSchema + Send> OpenApiResponderInner for Json<T> {
     fn responses(gen: &mut OpenApiGenerator) -> Result<Responses> {
         let mut responses = Responses::default();
         let schema = gen.json_schema::<T>();
-        add_schema_response(&mut responses, 200, "application/json", schema)?;
+        let media = MediaType {
+            example: schema_example(gen, &schema),
+            schema: Some(schema),
+            ..MediaType::default()
+        };
+        add_content_response(&mut responses, 200, "application/json", media)?;
         // 500 status is not added because an endpoint can handle this, so it might never return
         // this error type.
         Ok(responses)
@@ -64,6 +76,29 @@ impl OpenApiResponderInner for std::fs::File {
 }
 
 
+impl<T> OpenApiResponderInner for Html<T> {
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses> {
+        let mut responses = Responses::default();
+        let schema = gen.json_schema::<String>();
+        add_schema_response(&mut responses, 200, "text/html", schema)?;
+        Ok(responses)
+    }
+}
+
+impl OpenApiResponderInner for StatusCode {
+    fn responses(_: &mut OpenApiGenerator) -> Result<Responses> {
+        let mut responses = Responses::default();
+        ensure_status_code_exists(&mut responses, 200);
+        Ok(responses)
+    }
+}
+
+impl<T: OpenApiResponderInner> OpenApiResponderInner for (StatusCode, T) {
+    fn responses(gen: &mut OpenApiGenerator) -> Result<Responses> {
+        T::responses(gen)
+    }
+}
+
 impl OpenApiResponderInner for () {
     fn responses(_: &mut OpenApiGenerator) -> Result<Responses> {
         let mut responses = Responses::default();
@@ -80,7 +115,7 @@ impl<T: OpenApiResponderInner> OpenApiResponderInner for Option<T> {
     }
 }
 
-impl<'r, 'o: 'r, T> OpenApiResponderInner for std::borrow::Cow<'o, T>
+impl<'o, T> OpenApiResponderInner for std::borrow::Cow<'o, T>
 where
     T: OpenApiResponderInner + Clone,
 {
@@ -91,7 +126,7 @@ where
     }
 }
 
-impl<'r, 'o, T, E> OpenApiResponderInner for std::result::Result<T, E>
+impl<T, E> OpenApiResponderInner for std::result::Result<T, E>
 where
     T: OpenApiResponderInner,
     E: OpenApiResponderInner,
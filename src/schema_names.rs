@@ -0,0 +1,573 @@
+//! Naming of the schemas of `components/schemas` with a [`SchemaNaming`] strategy.
+//!
+//! The generator walks every type with a single `SchemaGenerator`, which keys definitions by the
+//! identity of their type (`JsonSchema::schema_id`) and numbers the name of a second type with
+//! the same name, e.g. `Error2`. The types passed to the generator are recorded with the
+//! definition schemars gave them, and the final names are only chosen when the specification is
+//! generated, from all the definitions at once, so they do not depend on the order the types were
+//! added in. A numbered definition whose type was not recorded cannot be named by the strategy,
+//! and is reported as a conflict like the other same-named types.
+
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+
+use okapi::openapi3::{
+    Callback, Components, Header, MediaType, Operation, ParameterValue, PathItem, RefOr, Response,
+};
+use okapi::Map;
+use schemars::gen::SchemaGenerator;
+use schemars::schema::{Schema, SchemaObject};
+use schemars::visit::{self, Visitor};
+use schemars::JsonSchema;
+
+use crate::error::{OpenApiError, Result};
+use crate::settings::{SchemaName, SchemaNaming};
+
+/// The types of the definitions of a `SchemaGenerator`.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SchemaNames {
+    /// The types passed to the generator, by the name schemars gave their definition.
+    types: Map<String, SchemaType>,
+    /// The definitions of merged fragments, which keep their name.
+    fragments: BTreeSet<String>,
+    /// The first conflict found, reported when the specification is generated.
+    conflict: Option<OpenApiError>,
+}
+
+#[derive(Debug, Clone)]
+struct SchemaType {
+    schema_id: Cow<'static, str>,
+    schema_name: String,
+    /// The Rust path of the type, unless it is a wrapper such as `Box` whose schema is that of
+    /// its argument.
+    type_path: Option<&'static str>,
+}
+
+impl SchemaType {
+    /// The type for error messages.
+    fn owner(&self) -> String {
+        format!("`{}`", self.type_path.unwrap_or(&self.schema_id))
+    }
+}
+
+impl SchemaNames {
+    /// Record the type of the definition `schema`, the schema returned by `generator` for `T`,
+    /// refers to.
+    pub(crate) fn record<T: ?Sized + JsonSchema>(
+        &mut self,
+        generator: &mut SchemaGenerator,
+        schema: &Schema,
+    ) {
+        let definitions_path = &generator.settings().definitions_path;
+        let name = match schema {
+            Schema::Object(SchemaObject {
+                reference: Some(reference),
+                ..
+            }) => match reference.strip_prefix(definitions_path.as_str()) {
+                Some(name) => name.to_owned(),
+                None => return,
+            },
+            _ => return,
+        };
+        let schema_name = T::schema_name();
+        let type_path = std::any::type_name::<T>();
+        let schema_type = SchemaType {
+            schema_id: T::schema_id(),
+            type_path: names_type(type_path, &schema_name).then_some(type_path),
+            schema_name,
+        };
+
+        // schemars reuses a definition of a merged fragment with the name of the type
+        if self.fragments.contains(&name) && !self.types.contains_key(&name) {
+            let generated = T::json_schema(generator);
+            if generator.definitions().get(&name) != Some(&generated) {
+                self.conflict
+                    .get_or_insert(OpenApiError::ConflictingSchema {
+                        name: name.clone(),
+                        first: "a merged fragment".to_owned(),
+                        second: schema_type.owner(),
+                    });
+            }
+        }
+        self.insert(name, schema_type);
+    }
+
+    fn insert(&mut self, name: String, schema_type: SchemaType) {
+        match self.types.get_mut(&name) {
+            // A definition merged from another generator has the name of a different type
+            Some(recorded) if recorded.schema_id != schema_type.schema_id => {
+                self.conflict
+                    .get_or_insert(OpenApiError::ConflictingSchema {
+                        name,
+                        first: recorded.owner(),
+                        second: schema_type.owner(),
+                    });
+            }
+            Some(recorded) => {
+                recorded.type_path = recorded.type_path.or(schema_type.type_path);
+            }
+            None => {
+                self.types.insert(name, schema_type);
+            }
+        }
+    }
+
+    /// Record that the definition `name` comes from a merged fragment.
+    pub(crate) fn add_fragment(&mut self, name: String) {
+        self.fragments.insert(name);
+    }
+
+    /// Keep the types recorded by the generator `other` merged into this one.
+    pub(crate) fn merge(&mut self, other: SchemaNames) {
+        for (name, schema_type) in other.types {
+            self.insert(name, schema_type);
+        }
+        self.fragments.extend(other.fragments);
+        if self.conflict.is_none() {
+            self.conflict = other.conflict;
+        }
+    }
+
+    /// The final name of each of `definitions`, by the name schemars gave it.
+    ///
+    /// Fails when different schemas end up with the same name.
+    pub(crate) fn names(
+        &self,
+        naming: &SchemaNaming,
+        definitions: &Map<String, Schema>,
+    ) -> Result<Map<String, String>> {
+        if let Some(conflict) = &self.conflict {
+            return Err(conflict.clone());
+        }
+
+        let mut names = Map::new();
+        let mut owners: Map<String, (&str, String)> = Map::new();
+        for (key, definition) in definitions {
+            let (name, owner) = if self.fragments.contains(key) {
+                (key.clone(), "a merged fragment".to_owned())
+            } else {
+                match self.types.get(key) {
+                    Some(schema_type) => (
+                        naming.name(&SchemaName {
+                            schema_name: &schema_type.schema_name,
+                            type_path: schema_type.type_path,
+                        }),
+                        schema_type.owner(),
+                    ),
+                    None => {
+                        if let Some(base) = numbered_base(key, definitions) {
+                            return Err(OpenApiError::ConflictingSchema {
+                                name: base.to_owned(),
+                                first: self.owner(base),
+                                second: format!(
+                                    "a type only used inside other types, numbered `{}`",
+                                    key
+                                ),
+                            });
+                        }
+                        (
+                            naming.name(&SchemaName {
+                                schema_name: key,
+                                type_path: None,
+                            }),
+                            format!("`{}`", key),
+                        )
+                    }
+                }
+            };
+            match owners.get(&name) {
+                Some((first_key, _)) if definitions[*first_key] == *definition => {}
+                Some((_, first)) => {
+                    return Err(OpenApiError::ConflictingSchema {
+                        name,
+                        first: first.clone(),
+                        second: owner,
+                    })
+                }
+                None => {
+                    owners.insert(name.clone(), (key, owner));
+                }
+            }
+            names.insert(key.clone(), name);
+        }
+        Ok(names)
+    }
+
+    /// The owner of the definition `key`, for error messages.
+    fn owner(&self, key: &str) -> String {
+        match self.types.get(key) {
+            _ if self.fragments.contains(key) => "a merged fragment".to_owned(),
+            Some(schema_type) => schema_type.owner(),
+            None => format!("`{}`", key),
+        }
+    }
+}
+
+/// The name `key` numbers, when schemars numbered it because a different type already had that
+/// name: `Error` for `Error2` when there is an `Error`. Only meaningful for definitions whose type
+/// was not recorded, as a type named `Error2` cannot be told apart otherwise.
+fn numbered_base<'a>(key: &'a str, definitions: &Map<String, Schema>) -> Option<&'a str> {
+    let base = key.trim_end_matches(|c: char| c.is_ascii_digit());
+    let number = &key[base.len()..];
+    let numbered = !base.is_empty()
+        && !number.starts_with('0')
+        && number.parse::<u32>().is_ok_and(|n| n >= 2);
+    (numbered && definitions.contains_key(base)).then_some(base)
+}
+
+/// Whether the last segment of the Rust path `type_path` is the type named `schema_name`, and
+/// not a wrapper such as `Box` whose schema is that of its argument.
+fn names_type(type_path: &str, schema_name: &str) -> bool {
+    let path = type_path.split('<').next().unwrap_or(type_path);
+    let type_name = path.rsplit("::").next().unwrap_or(path);
+    schema_name.split('_').next() == Some(type_name)
+}
+
+/// Rewrites `$ref`s to definitions to their final names.
+pub(crate) struct RenameReferences<'a> {
+    pub(crate) definitions_path: &'a str,
+    pub(crate) names: &'a Map<String, String>,
+}
+
+impl RenameReferences<'_> {
+    /// Rename the references of the schemas of `operation` and of its callbacks.
+    pub(crate) fn visit_operation(&mut self, operation: &mut Operation) {
+        for parameter in &mut operation.parameters {
+            if let RefOr::Object(parameter) = parameter {
+                self.visit_parameter_value(&mut parameter.value);
+            }
+        }
+        if let Some(RefOr::Object(body)) = &mut operation.request_body {
+            self.visit_content(&mut body.content);
+        }
+        let responses = &mut operation.responses;
+        for response in responses.default.iter_mut().chain(responses.responses.values_mut()) {
+            if let RefOr::Object(response) = response {
+                self.visit_response(response);
+            }
+        }
+        self.visit_callbacks(&mut operation.callbacks);
+    }
+
+    /// Rename the references of the schemas of components merged from fragments, which may refer
+    /// to the definitions of Rust types.
+    pub(crate) fn visit_components(&mut self, components: &mut Components) {
+        for response in components.responses.values_mut() {
+            if let RefOr::Object(response) = response {
+                self.visit_response(response);
+            }
+        }
+        for parameter in components.parameters.values_mut() {
+            if let RefOr::Object(parameter) = parameter {
+                self.visit_parameter_value(&mut parameter.value);
+            }
+        }
+        for body in components.request_bodies.values_mut() {
+            if let RefOr::Object(body) = body {
+                self.visit_content(&mut body.content);
+            }
+        }
+        self.visit_headers(&mut components.headers);
+        self.visit_callbacks(&mut components.callbacks);
+    }
+
+    fn visit_response(&mut self, response: &mut Response) {
+        self.visit_headers(&mut response.headers);
+        self.visit_content(&mut response.content);
+    }
+
+    fn visit_callbacks(&mut self, callbacks: &mut Map<String, RefOr<Callback>>) {
+        for callback in callbacks.values_mut() {
+            if let RefOr::Object(callback) = callback {
+                callback.callbacks.values_mut().for_each(|item| self.visit_path_item(item));
+            }
+        }
+    }
+
+    /// Rename the references of the schemas of the parameters and operations of `item`.
+    pub(crate) fn visit_path_item(&mut self, item: &mut PathItem) {
+        for parameter in &mut item.parameters {
+            if let RefOr::Object(parameter) = parameter {
+                self.visit_parameter_value(&mut parameter.value);
+            }
+        }
+        let operations = [
+            &mut item.get,
+            &mut item.put,
+            &mut item.post,
+            &mut item.delete,
+            &mut item.options,
+            &mut item.head,
+            &mut item.patch,
+            &mut item.trace,
+        ];
+        for operation in operations.into_iter().flatten() {
+            self.visit_operation(operation);
+        }
+    }
+
+    fn visit_parameter_value(&mut self, value: &mut ParameterValue) {
+        match value {
+            ParameterValue::Schema { schema, .. } => self.visit_schema_object(schema),
+            ParameterValue::Content { content } => self.visit_content(content),
+        }
+    }
+
+    fn visit_content(&mut self, content: &mut Map<String, MediaType>) {
+        for media_type in content.values_mut() {
+            if let Some(schema) = &mut media_type.schema {
+                self.visit_schema_object(schema);
+            }
+            for encoding in media_type.encoding.values_mut() {
+                self.visit_headers(&mut encoding.headers);
+            }
+        }
+    }
+
+    fn visit_headers(&mut self, headers: &mut Map<String, RefOr<Header>>) {
+        for header in headers.values_mut() {
+            if let RefOr::Object(header) = header {
+                self.visit_parameter_value(&mut header.value);
+            }
+        }
+    }
+}
+
+impl Visitor for RenameReferences<'_> {
+    fn visit_schema_object(&mut self, schema: &mut SchemaObject) {
+        if let Some(reference) = &mut schema.reference {
+            let renamed = reference
+                .strip_prefix(self.definitions_path)
+                .and_then(|name| self.names.get(name));
+            if let Some(name) = renamed {
+                *reference = format!("{}{}", self.definitions_path, name);
+            }
+        }
+        visit::visit_schema_object(self, schema);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::gen::OpenApiGenerator;
+    use crate::settings::OpenApiSettings;
+
+    use super::*;
+
+    use okapi::openapi3::{Components, OpenApi};
+    use serde::Serialize;
+
+    mod users {
+        use schemars::JsonSchema;
+        use serde::Serialize;
+
+        #[derive(Serialize, JsonSchema)]
+        pub struct User {
+            pub name: String,
+        }
+
+        #[derive(Serialize, JsonSchema)]
+        pub struct Error {
+            pub message: String,
+        }
+    }
+
+    mod billing {
+        use schemars::JsonSchema;
+        use serde::Serialize;
+
+        #[derive(Serialize, JsonSchema)]
+        pub struct Error {
+            pub code: u32,
+        }
+    }
+
+    #[derive(Serialize, JsonSchema)]
+    struct Page<T> {
+        items: Vec<T>,
+    }
+
+    #[derive(Serialize, JsonSchema)]
+    struct Failures {
+        user: users::Error,
+        billing: billing::Error,
+    }
+
+    fn generator(naming: SchemaNaming) -> OpenApiGenerator {
+        OpenApiGenerator::new(&OpenApiSettings {
+            schema_naming: naming,
+            ..OpenApiSettings::new()
+        })
+    }
+
+    fn schemas(gen: OpenApiGenerator) -> Map<String, SchemaObject> {
+        gen.into_openapi().unwrap().components.unwrap().schemas
+    }
+
+    fn conflict(gen: OpenApiGenerator) -> (String, String, String) {
+        match gen.into_openapi() {
+            Err(OpenApiError::ConflictingSchema {
+                name,
+                first,
+                second,
+            }) => (name, first, second),
+            other => panic!("expected a conflict, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn same_named_types_conflict_in_any_order() {
+        let mut gen = generator(SchemaNaming::default());
+        gen.json_schema::<users::Error>();
+        gen.json_schema::<billing::Error>();
+        let (name, first, second) = conflict(gen);
+        assert_eq!(name, "Error");
+        assert!(first.contains("users::Error") && second.contains("billing::Error"));
+
+        let mut gen = generator(SchemaNaming::default());
+        gen.json_schema::<Failures>();
+        gen.json_schema::<billing::Error>();
+        assert_eq!(conflict(gen).0, "Error");
+    }
+
+    #[test]
+    fn same_named_types_in_one_type_conflict_until_recorded() {
+        let mut gen = generator(SchemaNaming::default());
+        gen.json_schema::<Failures>();
+        let (name, first, second) = conflict(gen);
+        assert_eq!(name, "Error");
+        assert_eq!(first, "`Error`");
+        assert!(second.contains("`Error2`"));
+
+        let mut gen = generator(SchemaNaming::ModuleQualified);
+        gen.json_schema::<Failures>();
+        gen.json_schema::<users::Error>();
+        gen.json_schema::<billing::Error>();
+        let schemas = schemas(gen);
+        let mut names: Vec<_> = schemas.keys().collect();
+        names.sort();
+        assert_eq!(
+            names,
+            [
+                "schema_names.tests.Failures",
+                "schema_names.tests.billing.Error",
+                "schema_names.tests.users.Error",
+            ]
+        );
+        let failures = serde_json::to_value(&schemas["schema_names.tests.Failures"]).unwrap();
+        assert_eq!(
+            failures["properties"]["billing"]["$ref"],
+            "#/components/schemas/schema_names.tests.billing.Error"
+        );
+    }
+
+    #[test]
+    fn module_qualified_names_do_not_depend_on_order() {
+        let render = |nested_first: bool| {
+            let mut gen = generator(SchemaNaming::ModuleQualified);
+            if nested_first {
+                gen.json_schema::<Failures>();
+                gen.json_schema::<Page<users::User>>();
+            }
+            gen.json_schema::<users::User>();
+            gen.json_schema::<users::Error>();
+            gen.json_schema::<billing::Error>();
+            if !nested_first {
+                gen.json_schema::<Page<users::User>>();
+                gen.json_schema::<Failures>();
+            }
+            gen.into_openapi().unwrap()
+        };
+        let openapi = render(true);
+        let schemas = &openapi.components.as_ref().unwrap().schemas;
+
+        let mut names: Vec<_> = schemas.keys().collect();
+        names.sort();
+        assert_eq!(openapi, render(false));
+        assert_eq!(
+            names,
+            [
+                "schema_names.tests.Failures",
+                "schema_names.tests.PageOfUser",
+                "schema_names.tests.billing.Error",
+                "schema_names.tests.users.Error",
+                "schema_names.tests.users.User",
+            ]
+        );
+        let json = crate::render::to_json(&openapi).unwrap();
+        assert!(json.contains("\"#/components/schemas/schema_names.tests.billing.Error\""));
+        assert!(json.contains("\"#/components/schemas/schema_names.tests.users.User\""));
+    }
+
+    #[test]
+    fn names_of_different_schemas_must_differ() {
+        let mut gen = generator(SchemaNaming::custom(|_| "Model".to_owned()));
+        gen.json_schema::<users::User>();
+        gen.json_schema::<users::Error>();
+        assert_eq!(conflict(gen).0, "Model");
+
+        let mut gen = generator(SchemaNaming::Short);
+        gen.json_schema::<Page<users::User>>();
+        assert!(schemas(gen).contains_key("PageOfUser"));
+    }
+
+    #[test]
+    fn merged_definitions_keep_their_type() {
+        let mut gen = generator(SchemaNaming::default());
+        let mut other = generator(SchemaNaming::default());
+        other.json_schema::<users::Error>();
+        gen.merge(other).unwrap();
+        gen.json_schema::<billing::Error>();
+        let (name, first, second) = conflict(gen);
+        assert_eq!(name, "Error");
+        assert!(first.contains("users::Error") && second.contains("billing::Error"));
+
+        let mut gen = generator(SchemaNaming::default());
+        let fragment = OpenApi {
+            components: Some(Components {
+                schemas: [("Error".to_owned(), SchemaObject::default())].into_iter().collect(),
+                ..Components::default()
+            }),
+            ..OpenApi::default()
+        };
+        gen.merge_fragment("", fragment).unwrap();
+        gen.json_schema::<users::Error>();
+        assert_eq!(conflict(gen).1, "a merged fragment");
+    }
+
+    #[test]
+    fn references_of_merged_fragments_follow_the_renaming() {
+        let mut gen = generator(SchemaNaming::Short);
+        gen.json_schema::<Page<users::User>>();
+        let page = serde_json::json!({ "$ref": "#/components/schemas/Page_for_User" });
+        let fragment: OpenApi = serde_json::from_value(serde_json::json!({
+            "openapi": "3.0.0",
+            "info": { "title": "fragment", "version": "1" },
+            "paths": { "/users": {
+                "parameters": [{ "name": "after", "in": "query", "schema": page }],
+            } },
+            "components": {
+                "responses": { "Users": {
+                    "description": "A page of users",
+                    "content": { "application/json": { "schema": page } },
+                } },
+                "requestBodies": { "Users": {
+                    "content": { "application/json": { "schema": page } },
+                } },
+            },
+        }))
+        .unwrap();
+        gen.merge_fragment("", fragment).unwrap();
+
+        let openapi = serde_json::to_value(gen.into_openapi().unwrap()).unwrap();
+        let renamed = "#/components/schemas/PageOfUser";
+        let components = &openapi["components"];
+        let content = |component: &str| {
+            components[component]["Users"]["content"]["application/json"]["schema"]["$ref"].clone()
+        };
+        assert_eq!(content("responses"), renamed);
+        assert_eq!(content("requestBodies"), renamed);
+        assert_eq!(openapi["paths"]["/users"]["parameters"][0]["schema"]["$ref"], renamed);
+        assert!(!openapi.to_string().contains("Page_for_User"));
+    }
+}
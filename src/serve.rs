@@ -0,0 +1,177 @@
+//! Routes serving the specification.
+//!
+//! ```rust,ignore
+//! let settings = OpenApiSettings::new();
+//! let openapi = gen.into_openapi()?;
+//! let app = Router::new()
+//!     .route("/users", get(list_users))
+//!     .merge(openapi_routes(&openapi, &settings)?);
+//! ```
+//!
+//! The specification is served as JSON at [`OpenApiSettings::json_path`]. With the `yaml`
+//! feature it is also served as YAML at [`OpenApiSettings::yaml_path`], and at the JSON path to
+//! requests preferring `application/yaml` in their `Accept` header, whose responses then carry
+//! `Vary: Accept` for caches.
+
+use axum::body::Bytes;
+use axum::http::header::{HeaderValue, ACCEPT, CONTENT_TYPE, VARY};
+use axum::http::HeaderMap;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use okapi::openapi3::OpenApi;
+
+use crate::error::Result;
+use crate::render;
+use crate::settings::OpenApiSettings;
+
+const JSON: &str = "application/json";
+
+/// A rendered document with its content type.
+#[derive(Clone)]
+struct Rendered {
+    content_type: &'static str,
+    body: Bytes,
+}
+
+impl IntoResponse for Rendered {
+    fn into_response(self) -> Response {
+        let mut response = self.body.into_response();
+        response
+            .headers_mut()
+            .insert(CONTENT_TYPE, HeaderValue::from_static(self.content_type));
+        response
+    }
+}
+
+/// Create the routes serving `openapi` at the paths of `settings`, see the
+/// [module documentation](self). The document is rendered once, when the routes are created.
+pub fn openapi_routes(openapi: &OpenApi, settings: &OpenApiSettings) -> Result<Router> {
+    let json = Rendered {
+        content_type: JSON,
+        body: Bytes::from(render::to_json(openapi)?),
+    };
+    let yaml = render_yaml(openapi)?;
+
+    let negotiated = yaml.clone();
+    let router = Router::new().route(
+        &settings.json_path,
+        get(move |headers: HeaderMap| async move {
+            match negotiated {
+                Some(yaml) => {
+                    let rendered = if prefers_yaml(&headers) { yaml } else { json };
+                    let mut response = rendered.into_response();
+                    response
+                        .headers_mut()
+                        .insert(VARY, HeaderValue::from_static("accept"));
+                    response
+                }
+                None => json.into_response(),
+            }
+        }),
+    );
+    Ok(match yaml {
+        Some(yaml) => router.route(&settings.yaml_path, get(move || async move { yaml })),
+        None => router,
+    })
+}
+
+#[cfg(feature = "yaml")]
+fn render_yaml(openapi: &OpenApi) -> Result<Option<Rendered>> {
+    Ok(Some(Rendered {
+        content_type: "application/yaml",
+        body: Bytes::from(render::to_yaml(openapi)?),
+    }))
+}
+
+#[cfg(not(feature = "yaml"))]
+fn render_yaml(_: &OpenApi) -> Result<Option<Rendered>> {
+    Ok(None)
+}
+
+/// Whether the `Accept` header of a request ranks YAML above JSON.
+fn prefers_yaml(headers: &HeaderMap) -> bool {
+    let mut yaml = 0.0_f32;
+    let mut json = 0.0_f32;
+    let ranges = headers
+        .get_all(ACCEPT)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','));
+    for range in ranges {
+        let mut parts = range.split(';').map(str::trim);
+        let media_type = parts.next().unwrap_or_default().to_ascii_lowercase();
+        let quality = parts
+            .find_map(|parameter| parameter.strip_prefix("q="))
+            .and_then(|q| q.parse().ok())
+            .unwrap_or(1.0);
+        match media_type.as_str() {
+            "application/yaml" | "application/x-yaml" | "text/yaml" | "text/x-yaml" => {
+                yaml = yaml.max(quality);
+            }
+            "application/json" | "application/*" | "*/*" => json = json.max(quality),
+            _ => {}
+        }
+    }
+    yaml > json
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn accept(values: &[&str]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for value in values {
+            headers.append(ACCEPT, HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn json_is_served_without_a_preference() {
+        assert!(!prefers_yaml(&accept(&[])));
+        assert!(!prefers_yaml(&accept(&["*/*"])));
+        assert!(!prefers_yaml(&accept(&["text/html"])));
+    }
+
+    #[test]
+    fn yaml_is_served_when_asked_for() {
+        assert!(prefers_yaml(&accept(&["application/yaml"])));
+        assert!(prefers_yaml(&accept(&["text/html", "application/x-yaml"])));
+        assert!(!prefers_yaml(&accept(&["application/yaml, application/json"])));
+    }
+
+    #[test]
+    fn quality_values_rank_the_media_types() {
+        assert!(prefers_yaml(&accept(&["application/json;q=0.5, application/yaml"])));
+        assert!(prefers_yaml(&accept(&["*/*; q=0.1, text/yaml; q=0.9"])));
+        assert!(!prefers_yaml(&accept(&["application/yaml;q=0.2, */*"])));
+        assert!(!prefers_yaml(&accept(&["application/yaml;q=0"])));
+    }
+
+    #[cfg(feature = "yaml")]
+    #[tokio::test]
+    async fn negotiated_responses_vary_on_accept() {
+        use axum::body::Body;
+        use axum::http::Request;
+        use tower::ServiceExt;
+
+        let settings = OpenApiSettings::new();
+        let router = openapi_routes(&OpenApi::default(), &settings).unwrap();
+        for (accept, content_type) in [(None, JSON), (Some("application/yaml"), "application/yaml")]
+        {
+            let mut request = Request::get(&settings.json_path);
+            if let Some(accept) = accept {
+                request = request.header(ACCEPT, accept);
+            }
+            let response = router
+                .clone()
+                .oneshot(request.body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.headers()[CONTENT_TYPE], content_type);
+            assert_eq!(response.headers()[VARY], "accept");
+        }
+    }
+}
@@ -1,5 +1,8 @@
 use schemars::gen::SchemaSettings;
+use schemars::visit::{RemoveRefSiblings, ReplaceBoolSchemas};
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Arc;
 
 /// Settings which are used to customize the behavior of the `OpenApiGenerator`.
 #[derive(Debug, Clone)]
@@ -9,6 +12,22 @@ pub struct OpenApiSettings {
     /// The path to the json file that contains the API specification. Then default is
     /// `openapi.json`.
     pub json_path: String,
+    /// The path serving the specification as YAML with the `yaml` feature. The default is
+    /// `/openapi.yaml`.
+    pub yaml_path: String,
+    /// How `operationId`s are derived from the handler path when `#[handler]` does not set one.
+    pub operation_id_strategy: OperationIdStrategy,
+    /// How a tag is inferred for operations that have none.
+    pub tag_inference: TagInference,
+    /// The OpenAPI version of the generated document.
+    pub openapi_version: OpenApiVersion,
+    /// The runtime expression of the URL webhooks are sent to, used to render them as callbacks
+    /// with [`OpenApiVersion::V3_0`]. The default is `{$request.body#/callbackUrl}`.
+    pub webhook_url_expression: String,
+    /// The order of paths and components in the generated document.
+    pub key_order: KeyOrder,
+    /// How the schemas of Rust types are named in `components/schemas`.
+    pub schema_naming: SchemaNaming,
 }
 
 impl Default for OpenApiSettings {
@@ -16,6 +35,13 @@ impl Default for OpenApiSettings {
         OpenApiSettings {
             schema_settings: SchemaSettings::openapi3(),
             json_path: "/openapi.json".to_owned(),
+            yaml_path: "/openapi.yaml".to_owned(),
+            operation_id_strategy: OperationIdStrategy::default(),
+            tag_inference: TagInference::default(),
+            openapi_version: OpenApiVersion::default(),
+            webhook_url_expression: "{$request.body#/callbackUrl}".to_owned(),
+            key_order: KeyOrder::default(),
+            schema_naming: SchemaNaming::default(),
         }
     }
 }
@@ -30,6 +56,278 @@ impl OpenApiSettings {
     }
 }
 
+/// Naming strategy for generated `operationId`s.
+///
+/// The handler path is the `module_path!()` of the handler plus its function name, e.g.
+/// `my_app::routes::users::create`. The crate name is dropped in every strategy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OperationIdStrategy {
+    /// `routes_users_create`
+    #[default]
+    Snake,
+    /// `routesUsersCreate`
+    Camel,
+    /// The first tag of the operation followed by the function name: `users_create`. Falls back
+    /// to [`OperationIdStrategy::Snake`] for operations without tags.
+    TagPrefixed,
+}
+
+impl OperationIdStrategy {
+    /// Create the `operationId` of the handler at `handler_path` with the given tags.
+    #[must_use]
+    pub fn operation_id(self, handler_path: &str, tags: &[String]) -> String {
+        let segments: Vec<&str> = handler_path
+            .trim_start_matches(':')
+            .split("::")
+            .filter(|s| !s.is_empty())
+            .collect();
+        let segments = match segments.split_first() {
+            Some((_crate_name, rest)) if !rest.is_empty() => rest.to_vec(),
+            _ => segments,
+        };
+
+        match self {
+            OperationIdStrategy::Snake => segments.join("_"),
+            OperationIdStrategy::Camel => {
+                let words = segments.iter().flat_map(|s| s.split('_')).filter(|w| !w.is_empty());
+                let mut id = String::new();
+                for (i, word) in words.enumerate() {
+                    if i == 0 {
+                        id.push_str(word);
+                    } else {
+                        let mut chars = word.chars();
+                        if let Some(first) = chars.next() {
+                            id.extend(first.to_uppercase());
+                            id.push_str(chars.as_str());
+                        }
+                    }
+                }
+                id
+            }
+            OperationIdStrategy::TagPrefixed => match tags.first() {
+                Some(tag) => {
+                    let tag: String = tag
+                        .chars()
+                        .map(|c| if c.is_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+                        .collect();
+                    format!("{}_{}", tag, segments.last().copied().unwrap_or_default())
+                }
+                None => OperationIdStrategy::Snake.operation_id(handler_path, tags),
+            },
+        }
+    }
+}
+
+/// Version of the OpenAPI specification the generated document declares.
+///
+/// Schemas are generated according to [`OpenApiSettings::schema_settings`], adapted to the schema
+/// dialect of the version by [`Self::schema_settings`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OpenApiVersion {
+    /// `3.0.0`. Webhooks are rendered as `components/callbacks`.
+    #[default]
+    V3_0,
+    /// `3.1.0`. Webhooks are rendered as `webhooks`.
+    V3_1,
+}
+
+impl OpenApiVersion {
+    /// The value of the `openapi` field.
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            OpenApiVersion::V3_0 => "3.0.0",
+            OpenApiVersion::V3_1 => "3.1.0",
+        }
+    }
+
+    /// Adapt `settings` to the schema dialect of this version. `3.1.0` uses JSON Schema 2020-12:
+    /// `null` is a type instead of `nullable`, and `$ref`s keep their sibling keywords and
+    /// boolean schemas are allowed, so the visitors removing those are dropped.
+    #[must_use]
+    pub fn schema_settings(self, mut settings: SchemaSettings) -> SchemaSettings {
+        if self == OpenApiVersion::V3_1 {
+            settings.option_nullable = false;
+            settings.option_add_null_type = true;
+            settings.visitors.retain(|visitor| {
+                let visitor = visitor.as_any();
+                !visitor.is::<RemoveRefSiblings>() && !visitor.is::<ReplaceBoolSchemas>()
+            });
+        }
+        settings
+    }
+}
+
+/// Naming strategy for the schemas of Rust types in `components/schemas`.
+///
+/// Names are chosen when the specification is generated, so they do not depend on the order the
+/// types were added in. Different schemas ending up with the same name make
+/// [`OpenApiGenerator::into_openapi`](crate::gen::OpenApiGenerator::into_openapi) fail. A type
+/// only used inside other types is known by the name schemars gave its definition; when another
+/// type has the same name, which schemars numbers as `Error2`, generating fails in the same way
+/// until the type is also passed to
+/// [`OpenApiGenerator::json_schema`](crate::gen::OpenApiGenerator::json_schema), so that the
+/// strategy can name it.
+#[derive(Clone, Default)]
+pub enum SchemaNaming {
+    /// The name schemars gives the type: `Page_for_User` for `Page<User>`.
+    #[default]
+    Schemars,
+    /// The name of the type with its generic arguments spelled out: `PageOfUser` for
+    /// `Page<User>`.
+    Short,
+    /// The modules of the type without the crate name, then its short name: `users.User` for
+    /// `my_app::users::User`. The module is only known for types passed to the generator, such as
+    /// request and response bodies; types only used inside other types keep their short name.
+    ModuleQualified,
+    /// A custom function of the type.
+    Custom(Arc<dyn Fn(&SchemaName<'_>) -> String + Send + Sync>),
+}
+
+impl fmt::Debug for SchemaNaming {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaNaming::Schemars => f.write_str("Schemars"),
+            SchemaNaming::Short => f.write_str("Short"),
+            SchemaNaming::ModuleQualified => f.write_str("ModuleQualified"),
+            SchemaNaming::Custom(_) => f.write_str("Custom(..)"),
+        }
+    }
+}
+
+impl SchemaNaming {
+    /// Create a [`SchemaNaming::Custom`] strategy from `f`.
+    #[must_use]
+    pub fn custom<F>(f: F) -> Self
+    where
+        F: Fn(&SchemaName<'_>) -> String + Send + Sync + 'static,
+    {
+        SchemaNaming::Custom(Arc::new(f))
+    }
+
+    /// The name of the schema of `name` in `components/schemas`.
+    #[must_use]
+    pub fn name(&self, name: &SchemaName<'_>) -> String {
+        match self {
+            SchemaNaming::Schemars => name.schema_name.to_owned(),
+            SchemaNaming::Short => name.short_name(),
+            SchemaNaming::ModuleQualified => name.module_qualified_name(),
+            SchemaNaming::Custom(f) => f(name),
+        }
+    }
+}
+
+/// A type whose schema is added to `components/schemas`, see [`SchemaNaming`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SchemaName<'a> {
+    /// The name given by schemars, e.g. `Page_for_User`.
+    pub schema_name: &'a str,
+    /// The Rust path of the type when it was passed to the generator, e.g.
+    /// `my_app::api::Page<my_app::users::User>`.
+    pub type_path: Option<&'a str>,
+}
+
+impl SchemaName<'_> {
+    /// The schemars name with generic arguments spelled out: `PageOfUser` for `Page_for_User`,
+    /// `MapOfStringAndUser` for `Map_for_String_and_User`.
+    #[must_use]
+    pub fn short_name(&self) -> String {
+        self.schema_name
+            .split('_')
+            .map(|word| match word {
+                "for" => "Of".to_owned(),
+                word => {
+                    let mut chars = word.chars();
+                    match chars.next() {
+                        Some(first) => first.to_uppercase().chain(chars).collect(),
+                        None => String::new(),
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// The modules of [`Self::type_path`] without the crate name, then the short name:
+    /// `api.PageOfUser` for `my_app::api::Page<my_app::users::User>`. The short name when the
+    /// path is unknown or the type is at the root of its crate.
+    #[must_use]
+    pub fn module_qualified_name(&self) -> String {
+        let short_name = self.short_name();
+        let path = match self.type_path {
+            Some(path) => path.split('<').next().unwrap_or(path),
+            None => return short_name,
+        };
+        let segments: Vec<&str> = path.split("::").collect();
+        match segments.as_slice() {
+            [_crate_name, modules @ .., _type_name] if !modules.is_empty() => {
+                format!("{}.{}", modules.join("."), short_name)
+            }
+            _ => short_name,
+        }
+    }
+}
+
+/// Order of the paths, webhooks and components of the generated document.
+///
+/// Methods within a path are always in the order of the OpenAPI specification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KeyOrder {
+    /// Sorted by key.
+    #[default]
+    Sorted,
+    /// In the order the operations and types were added to the generator. Only available with
+    /// the `preserve_order` feature, without which `okapi` maps are always sorted.
+    #[cfg(feature = "preserve_order")]
+    Registration,
+}
+
+/// How a default tag is inferred for operations declared without `tag = "..."`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TagInference {
+    /// The first static segment of the path, i.e. the router nesting prefix: `users` for
+    /// `/api/v1/users/:id`. `api` and version segments such as `v1` are skipped.
+    #[default]
+    PathPrefix,
+    /// The module containing the handler: `users` for `my_app::routes::users::create`.
+    ModulePath,
+    /// Leave operations without tags untagged.
+    Disabled,
+}
+
+impl TagInference {
+    /// Infer the tag of the operation at `path` handled by `handler_path`, if any.
+    #[must_use]
+    pub fn infer(self, path: &str, handler_path: Option<&str>) -> Option<String> {
+        match self {
+            TagInference::PathPrefix => path
+                .split('/')
+                .find(|segment| {
+                    !segment.is_empty()
+                        && !segment.starts_with([':', '*', '{'])
+                        && *segment != "api"
+                        && !is_version(segment)
+                })
+                .map(str::to_owned),
+            TagInference::ModulePath => {
+                let segments: Vec<&str> = handler_path?.split("::").collect();
+                // The crate name and the function name are not modules.
+                match segments.as_slice() {
+                    [_crate_name, .., module, _function] => Some((*module).to_owned()),
+                    _ => None,
+                }
+            }
+            TagInference::Disabled => None,
+        }
+    }
+}
+
+/// `v1`, `v2`, ...
+fn is_version(segment: &str) -> bool {
+    segment
+        .strip_prefix('v')
+        .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+}
+
 /// Contains a named url.
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct UrlObject {
@@ -48,4 +346,131 @@ impl UrlObject {
             url: url.to_string(),
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use okapi::openapi3::Operation;
+
+    use crate::error::OpenApiError;
+    use crate::gen::OpenApiGenerator;
+    use crate::{OpenApiMethod, OperationInfo};
+
+    const HANDLER: &str = "my_app::routes::user_accounts::create_user";
+
+    fn tags(tags: &[&str]) -> Vec<String> {
+        tags.iter().map(|tag| (*tag).to_owned()).collect()
+    }
+
+    /// Add a `GET` operation at `path` handled by `handler`, with the `operationId` the handler
+    /// sets, if any.
+    fn add(
+        gen: &mut OpenApiGenerator,
+        path: &str,
+        handler: &str,
+        operation_id: Option<&str>,
+    ) -> crate::error::Result<()> {
+        gen.add_operation(OperationInfo {
+            path: path.to_owned(),
+            method: OpenApiMethod::Get,
+            handler: Some(handler.to_owned()),
+            operation: Operation {
+                operation_id: operation_id.map(str::to_owned),
+                ..Operation::default()
+            },
+        })
+    }
+
+    fn operation_ids(gen: OpenApiGenerator) -> Vec<String> {
+        let openapi = gen.into_openapi().unwrap();
+        let mut ids: Vec<String> = openapi
+            .paths
+            .values()
+            .filter_map(|item| item.get.as_ref()?.operation_id.clone())
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn snake_case_joins_the_modules_and_function() {
+        let id = OperationIdStrategy::Snake.operation_id(HANDLER, &tags(&["users"]));
+        assert_eq!(id, "routes_user_accounts_create_user");
+        assert_eq!(OperationIdStrategy::Snake.operation_id("my_app::health", &[]), "health");
+    }
+
+    #[test]
+    fn camel_case_capitalizes_every_word_but_the_first() {
+        let id = OperationIdStrategy::Camel.operation_id(HANDLER, &tags(&["users"]));
+        assert_eq!(id, "routesUserAccountsCreateUser");
+    }
+
+    #[test]
+    fn tag_prefixed_uses_the_first_tag_and_function() {
+        let strategy = OperationIdStrategy::TagPrefixed;
+        let id = strategy.operation_id(HANDLER, &tags(&["User Accounts", "admin"]));
+        assert_eq!(id, "user_accounts_create_user");
+    }
+
+    #[test]
+    fn tag_prefixed_falls_back_to_snake_case_without_tags() {
+        let id = OperationIdStrategy::TagPrefixed.operation_id(HANDLER, &[]);
+        assert_eq!(id, "routes_user_accounts_create_user");
+    }
+
+    #[test]
+    fn explicit_operation_ids_override_the_strategy() {
+        let mut gen = OpenApiGenerator::new(&OpenApiSettings {
+            operation_id_strategy: OperationIdStrategy::Camel,
+            ..OpenApiSettings::new()
+        });
+        add(&mut gen, "/users", HANDLER, Some("signUp")).unwrap();
+        add(&mut gen, "/health", "my_app::health", None).unwrap();
+        assert_eq!(operation_ids(gen), ["health", "signUp"]);
+    }
+
+    #[test]
+    fn derived_operation_ids_must_be_unique() {
+        let mut gen = OpenApiGenerator::new(&OpenApiSettings {
+            operation_id_strategy: OperationIdStrategy::TagPrefixed,
+            ..OpenApiSettings::new()
+        });
+        add(&mut gen, "/users", "my_app::users::list", None).unwrap();
+        add(&mut gen, "/users/admins", "my_app::admins::list", None).unwrap();
+        match gen.into_openapi() {
+            Err(OpenApiError::DuplicateOperationId {
+                operation_id,
+                first,
+                second,
+            }) => {
+                assert_eq!(operation_id, "users_list");
+                assert_eq!([first, second], ["GET /users", "GET /users/admins"]);
+            }
+            other => panic!("expected a duplicate operationId, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn path_prefix_skips_api_and_versions() {
+        let infer = |path| TagInference::PathPrefix.infer(path, Some(HANDLER));
+        assert_eq!(infer("/api/v1/users/:id").as_deref(), Some("users"));
+        assert_eq!(infer("/v2/orders/{id}/items").as_deref(), Some("orders"));
+        assert_eq!(infer("/version/:id").as_deref(), Some("version"));
+        assert_eq!(infer("/api/v1/:id"), None);
+        assert_eq!(infer("/"), None);
+    }
+
+    #[test]
+    fn module_path_uses_the_module_of_the_handler() {
+        let infer = |handler| TagInference::ModulePath.infer("/api/users", handler);
+        assert_eq!(infer(Some(HANDLER)).as_deref(), Some("user_accounts"));
+        assert_eq!(infer(Some("my_app::health")), None);
+        assert_eq!(infer(None), None);
+    }
+
+    #[test]
+    fn disabled_inference_leaves_operations_untagged() {
+        assert_eq!(TagInference::Disabled.infer("/users", Some(HANDLER)), None);
+    }
+}
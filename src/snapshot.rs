@@ -0,0 +1,362 @@
+//! Snapshot tests of the generated specification (feature `test-support`).
+//!
+//! ```rust,ignore
+//! #[test]
+//! fn spec_is_up_to_date() {
+//!     let openapi = my_api::openapi().unwrap();
+//!     openapi_rs::snapshot::assert_snapshot(&openapi, "openapi.json");
+//! }
+//! ```
+//!
+//! The specification is compared with the committed file as a JSON value, so only differences in
+//! content fail the test, and they are reported one JSON pointer per line. Run the tests with
+//! `OPENAPI_RS_UPDATE_SNAPSHOTS=1` to write the current specification instead. Files ending in
+//! `.yaml` or `.yml` are read and written as YAML with the `yaml` feature, others as canonical
+//! JSON (see [`render::to_canonical_json`]). Relative paths are resolved against the working
+//! directory, which is the crate root under `cargo test`.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use okapi::openapi3::OpenApi;
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::render;
+
+/// Environment variable that makes snapshot assertions write the snapshot file instead.
+pub const UPDATE_VAR: &str = "OPENAPI_RS_UPDATE_SNAPSHOTS";
+
+/// Differences printed in a mismatch message before the rest are elided.
+const MAX_REPORTED: usize = 50;
+
+/// A snapshot that could not be checked, or does not match the specification.
+#[derive(Debug, Clone, Error)]
+pub enum SnapshotError {
+    /// The snapshot file does not exist yet.
+    #[error("snapshot `{}` does not exist, run the tests with {}=1 to create it", .path.display(), UPDATE_VAR)]
+    Missing { path: PathBuf },
+    /// The snapshot file could not be read, parsed or written, or the specification rendered.
+    #[error("snapshot `{}`: {message}", .path.display())]
+    Io { path: PathBuf, message: String },
+    /// The specification differs from the snapshot.
+    #[error("the specification differs from snapshot `{}`, run the tests with {}=1 to update it:\n{}", .path.display(), UPDATE_VAR, format_differences(.differences))]
+    Mismatch {
+        path: PathBuf,
+        differences: Vec<Difference>,
+    },
+}
+
+fn format_differences(differences: &[Difference]) -> String {
+    let mut lines: Vec<_> = differences
+        .iter()
+        .take(MAX_REPORTED)
+        .map(|d| format!("  {}", d))
+        .collect();
+    if differences.len() > MAX_REPORTED {
+        lines.push(format!(
+            "  ... and {} more",
+            differences.len() - MAX_REPORTED
+        ));
+    }
+    lines.join("\n")
+}
+
+/// A difference between the snapshot and the specification, at a JSON pointer.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Difference {
+    /// The specification has a value the snapshot does not.
+    Added { pointer: String, value: Value },
+    /// The snapshot has a value the specification does not.
+    Removed { pointer: String, value: Value },
+    /// The value differs.
+    Changed {
+        pointer: String,
+        snapshot: Value,
+        actual: Value,
+    },
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let at = |pointer: &str| if pointer.is_empty() { "/" } else { pointer }.to_owned();
+        match self {
+            Difference::Added { pointer, value } => {
+                write!(f, "+ {}: {}", at(pointer), summary(value))
+            }
+            Difference::Removed { pointer, value } => {
+                write!(f, "- {}: {}", at(pointer), summary(value))
+            }
+            Difference::Changed {
+                pointer,
+                snapshot,
+                actual,
+            } => write!(
+                f,
+                "~ {}: {} -> {}",
+                at(pointer),
+                summary(snapshot),
+                summary(actual)
+            ),
+        }
+    }
+}
+
+/// Compact JSON of `value`, shortened to keep one difference per line.
+fn summary(value: &Value) -> String {
+    const MAX_CHARS: usize = 80;
+    let json = value.to_string();
+    if json.chars().count() <= MAX_CHARS {
+        json
+    } else {
+        let short: String = json.chars().take(MAX_CHARS).collect();
+        format!("{}...", short)
+    }
+}
+
+/// Compare `openapi` with the snapshot at `path`, or write it there when `OPENAPI_RS_UPDATE_SNAPSHOTS`
+/// is set.
+///
+/// # Panics
+/// When the snapshot is missing, unreadable or differs from `openapi`, failing the test.
+#[track_caller]
+pub fn assert_snapshot(openapi: &OpenApi, path: impl AsRef<Path>) {
+    if let Err(error) = check_snapshot(openapi, path) {
+        panic!("{}", error);
+    }
+}
+
+/// Compare `openapi` with the snapshot at `path`, or write it there when `OPENAPI_RS_UPDATE_SNAPSHOTS`
+/// is set, returning the problem instead of panicking.
+pub fn check_snapshot(openapi: &OpenApi, path: impl AsRef<Path>) -> Result<(), SnapshotError> {
+    let path = path.as_ref();
+    let io = |message: String| SnapshotError::Io {
+        path: path.to_owned(),
+        message,
+    };
+
+    if std::env::var_os(UPDATE_VAR).is_some_and(|v| !v.is_empty() && v != "0") {
+        let rendered = render_snapshot(openapi, path).map_err(io)?;
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent).map_err(|e| io(e.to_string()))?;
+        }
+        return std::fs::write(path, rendered).map_err(|e| io(e.to_string()));
+    }
+
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(SnapshotError::Missing {
+                path: path.to_owned(),
+            })
+        }
+        Err(e) => return Err(io(e.to_string())),
+    };
+    let snapshot = parse_snapshot(&content, path).map_err(io)?;
+    let actual = serde_json::to_value(openapi).map_err(|e| io(e.to_string()))?;
+
+    let differences = differences(&snapshot, &actual);
+    if differences.is_empty() {
+        Ok(())
+    } else {
+        Err(SnapshotError::Mismatch {
+            path: path.to_owned(),
+            differences,
+        })
+    }
+}
+
+/// Every difference between `snapshot` and `actual`, in document order.
+#[must_use]
+pub fn differences(snapshot: &Value, actual: &Value) -> Vec<Difference> {
+    let mut differences = Vec::new();
+    compare(snapshot, actual, &mut String::new(), &mut differences);
+    differences
+}
+
+fn compare(snapshot: &Value, actual: &Value, pointer: &mut String, out: &mut Vec<Difference>) {
+    match (snapshot, actual) {
+        (Value::Object(snapshot), Value::Object(actual)) => {
+            let mut keys: Vec<&String> = snapshot.keys().chain(actual.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                let len = pointer.len();
+                pointer.push('/');
+                pointer.push_str(&key.replace('~', "~0").replace('/', "~1"));
+                match (snapshot.get(key), actual.get(key)) {
+                    (Some(snapshot), Some(actual)) => compare(snapshot, actual, pointer, out),
+                    (Some(value), None) => out.push(Difference::Removed {
+                        pointer: pointer.clone(),
+                        value: value.clone(),
+                    }),
+                    (None, Some(value)) => out.push(Difference::Added {
+                        pointer: pointer.clone(),
+                        value: value.clone(),
+                    }),
+                    (None, None) => {}
+                }
+                pointer.truncate(len);
+            }
+        }
+        (Value::Array(snapshot), Value::Array(actual)) => {
+            for i in 0..snapshot.len().max(actual.len()) {
+                let len = pointer.len();
+                pointer.push_str(&format!("/{}", i));
+                match (snapshot.get(i), actual.get(i)) {
+                    (Some(snapshot), Some(actual)) => compare(snapshot, actual, pointer, out),
+                    (Some(value), None) => out.push(Difference::Removed {
+                        pointer: pointer.clone(),
+                        value: value.clone(),
+                    }),
+                    (None, Some(value)) => out.push(Difference::Added {
+                        pointer: pointer.clone(),
+                        value: value.clone(),
+                    }),
+                    (None, None) => {}
+                }
+                pointer.truncate(len);
+            }
+        }
+        (snapshot, actual) if snapshot != actual => out.push(Difference::Changed {
+            pointer: pointer.clone(),
+            snapshot: snapshot.clone(),
+            actual: actual.clone(),
+        }),
+        _ => {}
+    }
+}
+
+fn is_yaml(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|e| e.to_str()),
+        Some("yaml" | "yml")
+    )
+}
+
+fn render_snapshot(openapi: &OpenApi, path: &Path) -> Result<String, String> {
+    if is_yaml(path) {
+        let value = serde_json::to_value(openapi).map_err(|e| e.to_string())?;
+        to_yaml(&render::canonicalize(value))
+    } else {
+        render::to_canonical_json(openapi).map_err(|e| e.to_string())
+    }
+}
+
+fn parse_snapshot(content: &str, path: &Path) -> Result<Value, String> {
+    if is_yaml(path) {
+        from_yaml(content)
+    } else {
+        serde_json::from_str(content).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(feature = "yaml")]
+fn to_yaml(value: &Value) -> Result<String, String> {
+    serde_yaml::to_string(value).map_err(|e| e.to_string())
+}
+
+#[cfg(feature = "yaml")]
+fn from_yaml(content: &str) -> Result<Value, String> {
+    serde_yaml::from_str(content).map_err(|e| e.to_string())
+}
+
+#[cfg(not(feature = "yaml"))]
+fn to_yaml(_: &Value) -> Result<String, String> {
+    Err("YAML snapshots need the `yaml` feature".to_owned())
+}
+
+#[cfg(not(feature = "yaml"))]
+fn from_yaml(_: &str) -> Result<Value, String> {
+    Err("YAML snapshots need the `yaml` feature".to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::{Mutex, MutexGuard};
+
+    use crate::utils::TempDir;
+
+    /// Held by every test, as one of them sets the process-wide [`UPDATE_VAR`].
+    fn lock() -> MutexGuard<'static, ()> {
+        static ENVIRONMENT: Mutex<()> = Mutex::new(());
+        ENVIRONMENT.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn openapi(title: &str) -> OpenApi {
+        let mut openapi = OpenApi {
+            openapi: "3.0.0".to_owned(),
+            ..OpenApi::default()
+        };
+        openapi.info.title = title.to_owned();
+        openapi.info.version = "1".to_owned();
+        openapi
+    }
+
+    #[test]
+    fn matching_snapshots_pass() {
+        let _lock = lock();
+        let dir = TempDir::new();
+        let path = dir.path("openapi.json");
+        std::fs::write(&path, render::to_canonical_json(&openapi("shop")).unwrap()).unwrap();
+        check_snapshot(&openapi("shop"), &path).unwrap();
+    }
+
+    #[test]
+    fn mismatches_list_every_difference() {
+        let _lock = lock();
+        let dir = TempDir::new();
+        let path = dir.path("openapi.json");
+        let mut snapshot = serde_json::to_value(openapi("shop")).unwrap();
+        snapshot["info"]["description"] = "Removed".into();
+        std::fs::write(&path, snapshot.to_string()).unwrap();
+
+        let mut actual = openapi("store");
+        actual.info.version = "2".to_owned();
+        match check_snapshot(&actual, &path) {
+            Err(SnapshotError::Mismatch { differences, .. }) => {
+                let lines: Vec<String> = differences.iter().map(ToString::to_string).collect();
+                assert_eq!(
+                    lines,
+                    [
+                        "- /info/description: \"Removed\"",
+                        "~ /info/title: \"shop\" -> \"store\"",
+                        "~ /info/version: \"1\" -> \"2\"",
+                    ]
+                );
+            }
+            other => panic!("expected a mismatch, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn missing_snapshots_fail() {
+        let _lock = lock();
+        let dir = TempDir::new();
+        let path = dir.path("openapi.json");
+        let error = check_snapshot(&openapi("shop"), &path).unwrap_err();
+        assert!(matches!(error, SnapshotError::Missing { .. }));
+        assert!(error.to_string().contains(UPDATE_VAR));
+    }
+
+    #[test]
+    fn updating_writes_the_snapshot() {
+        let _lock = lock();
+        let dir = TempDir::new();
+        let path = dir.path("specs/openapi.json");
+
+        std::env::set_var(UPDATE_VAR, "1");
+        let updated = check_snapshot(&openapi("shop"), &path);
+        std::env::set_var(UPDATE_VAR, "0");
+        let disabled = check_snapshot(&openapi("store"), &path);
+        std::env::remove_var(UPDATE_VAR);
+
+        updated.unwrap();
+        assert!(matches!(disabled, Err(SnapshotError::Mismatch { .. })));
+        let written = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(written, render::to_canonical_json(&openapi("shop")).unwrap());
+        check_snapshot(&openapi("shop"), &path).unwrap();
+    }
+}
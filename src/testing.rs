@@ -0,0 +1,363 @@
+//! Contract tests: send requests to an [`axum::Router`] and check that every response is
+//! documented by the generated specification.
+//!
+//! ```
+//! use axum::body::Body;
+//! use axum::extract::Path;
+//! use axum::http::{Request, StatusCode};
+//! use axum::routing::get;
+//! use axum::{Json, Router};
+//! use okapi::openapi3::OpenApi;
+//! use openapi_rs::gen::OpenApiGenerator;
+//! use openapi_rs::handler;
+//! use openapi_rs::settings::OpenApiSettings;
+//! use openapi_rs::testing::ContractTester;
+//!
+//! #[handler(method = "GET")]
+//! async fn get_user(Path(id): Path<u32>) -> Json<u32> {
+//!     Json(id)
+//! }
+//!
+//! fn app() -> (Router, OpenApi) {
+//!     let router = Router::new().route("/users/:id", get(get_user));
+//!     let mut gen = OpenApiGenerator::new(&OpenApiSettings::new());
+//!     get_user_spec("/users/{id}", &mut gen).unwrap();
+//!     (router, gen.into_openapi().unwrap())
+//! }
+//!
+//! // Usually a `#[tokio::test]`.
+//! #[tokio::main(flavor = "current_thread")]
+//! async fn main() {
+//!     let (router, openapi) = app();
+//!     let tester = ContractTester::new(router, &openapi);
+//!
+//!     let response = tester
+//!         .oneshot(Request::get("/users/1").body(Body::empty()).unwrap())
+//!         .await;
+//!     assert_eq!(response.status, StatusCode::OK);
+//! }
+//! ```
+//!
+//! A response fails the contract when its operation, status code or content type is not
+//! documented, or when its JSON body does not validate against the documented schema.
+
+use std::fmt;
+
+use axum::body::{Body, Bytes, HttpBody};
+use axum::http::{HeaderMap, Method, Request, StatusCode};
+use axum::Router;
+use okapi::openapi3::OpenApi;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use thiserror::Error;
+use tower::ServiceExt;
+
+use crate::json_schema::{self, SchemaError};
+use crate::matcher::PathMatcher;
+use crate::utils::{essence, is_json, media_type, resolve_ref};
+
+/// A response that does not match the specification.
+#[derive(Debug, Clone, Error)]
+pub enum ContractError {
+    /// No operation is documented for the method and path of the request.
+    #[error("{method} {path} is not documented")]
+    UndocumentedOperation { method: Method, path: String },
+    /// The operation does not document the status code of the response.
+    #[error("{operation}: status {status} is not documented")]
+    UndocumentedStatus {
+        operation: String,
+        status: StatusCode,
+    },
+    /// The response for the status code does not document the content type of the response.
+    #[error("{operation}: content type `{content_type}` is not documented for status {status}")]
+    UndocumentedContentType {
+        operation: String,
+        status: StatusCode,
+        content_type: String,
+    },
+    /// The body is not valid JSON, or does not validate against the documented schema.
+    #[error("{operation}: the body of the {status} response does not match its schema:\n{}", format_errors(.errors))]
+    InvalidBody {
+        operation: String,
+        status: StatusCode,
+        errors: Vec<SchemaError>,
+    },
+    /// The body of the response could not be read.
+    #[error("{operation}: cannot read the response body: {message}")]
+    Body { operation: String, message: String },
+}
+
+fn format_errors(errors: &[SchemaError]) -> String {
+    errors
+        .iter()
+        .map(|e| format!("  {}", e))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// A response checked against the specification.
+#[derive(Debug, Clone)]
+pub struct TestResponse {
+    /// The status code of the response.
+    pub status: StatusCode,
+    /// The headers of the response.
+    pub headers: HeaderMap,
+    /// The body of the response.
+    pub body: Bytes,
+}
+
+impl TestResponse {
+    /// Deserialize the JSON body.
+    ///
+    /// # Panics
+    /// When the body is not a valid `T`.
+    #[must_use]
+    pub fn json<T: DeserializeOwned>(&self) -> T {
+        serde_json::from_slice(&self.body).expect("response body is not a valid JSON value")
+    }
+}
+
+/// Sends requests to a [`Router`] and checks the responses against the specification of the
+/// router.
+#[derive(Clone)]
+pub struct ContractTester {
+    router: Router,
+    document: Value,
+    paths: PathMatcher<String>,
+}
+
+impl fmt::Debug for ContractTester {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ContractTester").finish_non_exhaustive()
+    }
+}
+
+impl ContractTester {
+    /// Create a tester for `router`, documented by `openapi`.
+    ///
+    /// # Panics
+    /// When `openapi` cannot be serialized.
+    #[must_use]
+    pub fn new(router: Router, openapi: &OpenApi) -> Self {
+        let mut paths = PathMatcher::default();
+        for path in openapi.paths.keys() {
+            paths.insert(path, path.clone());
+        }
+        ContractTester {
+            router,
+            document: serde_json::to_value(openapi).expect("the specification serializes"),
+            paths,
+        }
+    }
+
+    /// Send `request` and check the response.
+    ///
+    /// # Panics
+    /// When the response does not match the specification, failing the test.
+    pub async fn oneshot(&self, request: Request<Body>) -> TestResponse {
+        match self.try_oneshot(request).await {
+            Ok(response) => response,
+            Err(error) => panic!("contract violation: {}", error),
+        }
+    }
+
+    /// Send `request` and check the response, returning the violation instead of panicking.
+    pub async fn try_oneshot(&self, request: Request<Body>) -> Result<TestResponse, ContractError> {
+        let method = request.method().clone();
+        let path = request.uri().path().to_owned();
+        let undocumented = || ContractError::UndocumentedOperation {
+            method: method.clone(),
+            path: path.clone(),
+        };
+
+        let template = self.paths.find(&path).ok_or_else(undocumented)?;
+        let operation = self.document["paths"][template]
+            .get(method.as_str().to_ascii_lowercase())
+            .ok_or_else(undocumented)?;
+        let name = match operation.get("operationId").and_then(Value::as_str) {
+            Some(id) => format!("{} {} ({})", method, template, id),
+            None => format!("{} {}", method, template),
+        };
+
+        let response = match self.router.clone().oneshot(request).await {
+            Ok(response) => response,
+            Err(infallible) => match infallible {},
+        };
+        let (parts, mut body) = response.into_parts();
+        let mut bytes = Vec::new();
+        while let Some(chunk) = body.data().await {
+            let chunk = chunk.map_err(|e| ContractError::Body {
+                operation: name.clone(),
+                message: e.to_string(),
+            })?;
+            bytes.extend_from_slice(&chunk);
+        }
+        let response = TestResponse {
+            status: parts.status,
+            headers: parts.headers,
+            body: Bytes::from(bytes),
+        };
+
+        self.check(&name, operation, &response)?;
+        Ok(response)
+    }
+
+    fn check(
+        &self,
+        name: &str,
+        operation: &Value,
+        response: &TestResponse,
+    ) -> Result<(), ContractError> {
+        let status = response.status;
+        let documented = documented_response(operation, status).ok_or_else(|| {
+            ContractError::UndocumentedStatus {
+                operation: name.to_owned(),
+                status,
+            }
+        })?;
+        let documented = resolve_ref(&self.document, documented);
+
+        let content_type = essence(&response.headers);
+        let content_type = match content_type {
+            Some(content_type) => content_type,
+            None if response.body.is_empty() => return Ok(()),
+            None => "application/octet-stream".to_owned(),
+        };
+
+        let media = documented
+            .get("content")
+            .and_then(Value::as_object)
+            .and_then(|content| media_type(content, &content_type))
+            .ok_or_else(|| ContractError::UndocumentedContentType {
+                operation: name.to_owned(),
+                status,
+                content_type: content_type.clone(),
+            })?;
+
+        let schema = match media.get("schema") {
+            Some(schema) if is_json(&content_type) => schema,
+            _ => return Ok(()),
+        };
+        let body: Value = serde_json::from_slice(&response.body).map_err(|e| {
+            ContractError::InvalidBody {
+                operation: name.to_owned(),
+                status,
+                errors: vec![SchemaError {
+                    instance_path: String::new(),
+                    message: format!("invalid JSON: {}", e),
+                }],
+            }
+        })?;
+        let errors = json_schema::validate(&self.document, schema, &body);
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ContractError::InvalidBody {
+                operation: name.to_owned(),
+                status,
+                errors,
+            })
+        }
+    }
+}
+
+/// The documented response for `status`: the exact code, then its range (`2XX`), then `default`.
+pub(crate) fn documented_response(operation: &Value, status: StatusCode) -> Option<&Value> {
+    let responses = operation.get("responses")?;
+    let code = status.as_str();
+    let range = format!("{}XX", &code[..1]);
+    responses
+        .get(code)
+        .or_else(|| responses.get(&range))
+        .or_else(|| responses.get(range.to_ascii_lowercase()))
+        .or_else(|| responses.get("default"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use axum::http::header::CONTENT_TYPE;
+    use axum::response::IntoResponse;
+    use axum::routing::get;
+    use axum::Json;
+    use serde_json::json;
+
+    fn tester() -> ContractTester {
+        let router = Router::new()
+            .route("/users/:id", get(|| async { Json(json!({ "id": 1, "name": "Ada" })) }))
+            .route("/users/me", get(|| async { Json(json!({ "id": "me" })) }))
+            .route(
+                "/text",
+                get(|| async { ([(CONTENT_TYPE, "text/plain")], "hi").into_response() }),
+            )
+            .route("/missing", get(|| async { StatusCode::NOT_FOUND }));
+        let user = json!({
+            "200": { "description": "OK", "content": { "application/json": { "schema": {
+                "type": "object",
+                "required": ["id"],
+                "properties": { "id": { "type": "integer" } },
+            } } } }
+        });
+        let openapi: OpenApi = serde_json::from_value(json!({
+            "openapi": "3.0.0",
+            "info": { "title": "test", "version": "1" },
+            "paths": {
+                "/users/{id}": { "get": { "operationId": "get_user", "responses": user } },
+                "/users/me": { "get": { "responses": user } },
+                "/text": { "get": { "responses": { "2XX": {
+                    "description": "OK",
+                    "content": { "application/json": {} },
+                } } } },
+                "/missing": { "get": { "responses": { "default": { "description": "Error" } } } },
+            },
+        }))
+        .unwrap();
+        ContractTester::new(router, &openapi)
+    }
+
+    async fn check(uri: &str) -> Result<TestResponse, ContractError> {
+        tester()
+            .try_oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+    }
+
+    #[tokio::test]
+    async fn documented_responses_pass() {
+        let response = check("/users/1").await.unwrap();
+        assert_eq!(response.json::<Value>()["name"], "Ada");
+        check("/missing").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn bodies_are_validated_against_the_schema() {
+        let error = check("/users/me").await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "GET /users/me: the body of the 200 OK response does not match its schema:\n  /id: expected integer, found string"
+        );
+    }
+
+    #[tokio::test]
+    async fn undocumented_operations_and_content_types_fail() {
+        let error = tester()
+            .try_oneshot(Request::post("/users/1").body(Body::empty()).unwrap())
+            .await
+            .unwrap_err();
+        assert_eq!(error.to_string(), "POST /users/1 is not documented");
+
+        let error = check("/text").await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "GET /text: content type `text/plain` is not documented for status 200 OK"
+        );
+    }
+
+    #[test]
+    fn documented_responses_fall_back_to_ranges_and_default() {
+        let operation = json!({ "responses": { "201": 1, "4XX": 2, "default": 3 } });
+        assert_eq!(documented_response(&operation, StatusCode::CREATED), Some(&json!(1)));
+        assert_eq!(documented_response(&operation, StatusCode::CONFLICT), Some(&json!(2)));
+        assert_eq!(documented_response(&operation, StatusCode::OK), Some(&json!(3)));
+    }
+}
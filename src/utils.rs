@@ -1,13 +1,18 @@
-use okapi::{openapi3::{RefOr, Response, Responses, SchemaObject, MediaType}, Map};
+use okapi::{openapi3::{Header, MediaType, Object, OpenApi, Parameter, ParameterValue, PathItem, RefOr, Response, Responses, SchemaObject}, Map};
 
 use anyhow::Result;
+use axum::http::header::CONTENT_TYPE;
+use axum::http::HeaderMap;
+use serde_json::Value;
+use std::path::Path;
 
 use super::error::OpenApiError;
 
 pub fn ensure_not_ref(response: &mut RefOr<Response>) -> Result<&mut Response,OpenApiError> {
     match response {
-        RefOr::Ref(_) => Err(OpenApiError::new(
-            "Altering Ref responses is not supported.".to_owned(),
+        RefOr::Ref(r) => Err(OpenApiError::schema(
+            r.reference.clone(),
+            "altering Ref responses is not supported",
         )),
         RefOr::Object(o) => Ok(o),
     }
@@ -23,44 +28,128 @@ pub fn produce_any_responses(r1: Responses, r2: Responses) -> Result<Responses>
         let response1 = ensure_not_ref(
             result
                 .responses
-                .entry(status)
+                .entry(status.clone())
                 .or_insert_with(|| Response::default().into()),
         )?;
-        *response1 =
-            produce_either_response(ensure_not_ref(&mut response2)?.clone(), response1.clone());
+        *response1 = produce_either_response(
+            &status,
+            response1.clone(),
+            ensure_not_ref(&mut response2)?.clone(),
+        );
     }
     Ok(result)
 }
 
 
-fn produce_either_response(r1: Response, r2: Response) -> Response {
-    let description = if r1.description.is_empty() {
-        r2.description
-    } else if r2.description.is_empty() {
-        r1.description
-    } else {
-        format!("{}\n{}", r1.description, r2.description)
-    };
+fn produce_either_response(status: &str, r1: Response, r2: Response) -> Response {
+    let descriptions = merge_descriptions(status, descriptions(&r1), descriptions(&r2));
 
     let mut content = r1.content;
     for (content_type, media) in r2.content {
         add_media_type(&mut content, content_type, media);
     }
 
+    let mut extensions = extend(r1.extensions, r2.extensions);
+    extensions.insert(
+        DESCRIPTIONS_EXTENSION.to_owned(),
+        Value::from(descriptions.clone()),
+    );
     Response {
-        description,
+        description: descriptions.join(DESCRIPTION_SEPARATOR),
         content,
         headers: extend(r1.headers, r2.headers),
         links: extend(r1.links, r2.links),
-        extensions: extend(r1.extensions, r2.extensions),
+        extensions,
+    }
+}
+
+/// Separator between the descriptions of responses merged for the same status code.
+const DESCRIPTION_SEPARATOR: &str = "; ";
+
+/// Private extension keeping the descriptions a merged response was made of, so that merging it
+/// again does not split its joined description. Removed by `OpenApiGenerator::add_operation`.
+pub(crate) const DESCRIPTIONS_EXTENSION: &str = "x-openapi-rs-descriptions";
+
+/// The descriptions `response` was made of: those of the responses merged into it, or its own.
+fn descriptions(response: &Response) -> Vec<String> {
+    match response.extensions.get(DESCRIPTIONS_EXTENSION) {
+        Some(Value::Array(descriptions)) => descriptions
+            .iter()
+            .filter_map(Value::as_str)
+            .map(str::to_owned)
+            .collect(),
+        _ => vec![response.description.clone()],
+    }
+}
+
+/// Combine the descriptions of two responses sharing `status`, each kept whole. Duplicates are
+/// dropped, and so is the default reason phrase when a more specific description exists.
+fn merge_descriptions(status: &str, d1: Vec<String>, d2: Vec<String>) -> Vec<String> {
+    let reason = status.parse().ok().and_then(reason_phrase);
+    let mut descriptions: Vec<String> = Vec::new();
+    for description in d1.into_iter().chain(d2) {
+        let description = description.trim();
+        if !description.is_empty() && !descriptions.iter().any(|d| d == description) {
+            descriptions.push(description.to_owned());
+        }
+    }
+    if descriptions.len() > 1 {
+        descriptions.retain(|description| Some(description.as_str()) != reason);
     }
+    descriptions
 }
 
+/// The reason phrase of an HTTP status code, e.g. `Not Found` for `404`.
+#[must_use]
+pub fn reason_phrase(status: u16) -> Option<&'static str> {
+    axum::http::StatusCode::from_u16(status)
+        .ok()
+        .and_then(|s| s.canonical_reason())
+}
+
+/// Default description of a response with `status`, its reason phrase when it has one.
+#[must_use]
+pub fn default_response_description(status: u16) -> String {
+    reason_phrase(status).map_or_else(|| format!("Status {}", status), str::to_owned)
+}
+
+/// The first successful status among `responses`, `200` when there is none.
+pub fn first_success_status(responses: &Responses) -> u16 {
+    responses
+        .responses
+        .keys()
+        .filter(|status| status.starts_with('2'))
+        .find_map(|status| status.parse().ok())
+        .unwrap_or(200)
+}
+
+/// Get the response for `status`, inserting one described by the reason phrase of the status
+/// when missing.
 pub fn ensure_status_code_exists(responses: &mut Responses, status: u16) -> &mut RefOr<Response> {
     responses
         .responses
         .entry(status.to_string())
-        .or_insert_with(|| Response::default().into())
+        .or_insert_with(|| {
+            Response {
+                description: default_response_description(status),
+                ..Response::default()
+            }
+            .into()
+        })
+}
+
+/// Replace the description of the response for `status`, adding the response when missing.
+///
+/// Responders implementing [`OpenApiResponderInner`](crate::response::OpenApiResponderInner) call
+/// this to describe their responses; handlers do it with
+/// `#[handler(response(status = 404, description = "..."))]`.
+pub fn set_response_description(
+    responses: &mut Responses,
+    status: u16,
+    description: impl Into<String>,
+) -> Result<()> {
+    ensure_not_ref(ensure_status_code_exists(responses, status))?.description = description.into();
+    Ok(())
 }
 
 pub fn accept_either_media_type(mt1: MediaType, mt2: MediaType) -> MediaType {
@@ -135,3 +224,259 @@ pub fn add_media_type(
         .or_insert(media);
 }
 
+
+/// Add the specification extension `key` to the response for `status`, adding the response when
+/// missing.
+pub fn add_response_extension(
+    responses: &mut Responses,
+    status: u16,
+    key: &str,
+    value: serde_json::Value,
+) -> Result<()> {
+    crate::extension::check_extension_key(key)?;
+    let response = ensure_not_ref(ensure_status_code_exists(responses, status))?;
+    response.extensions.insert(key.to_owned(), value);
+    Ok(())
+}
+
+/// Document a `Set-Cookie` header setting `cookie_name` on every successful (2xx) response, or on
+/// `200` when there is none.
+pub fn add_set_cookie_header(responses: &mut Responses, cookie_name: &str) -> Result<()> {
+    let mut statuses: Vec<String> = responses
+        .responses
+        .keys()
+        .filter(|status| status.starts_with('2'))
+        .cloned()
+        .collect();
+    if statuses.is_empty() {
+        ensure_status_code_exists(responses, 200);
+        statuses.push("200".to_owned());
+    }
+
+    for status in statuses {
+        let response = ensure_not_ref(responses.responses.get_mut(&status).expect("status exists"))?;
+        let sets = format!("Sets the `{}` cookie.", cookie_name);
+        match response.headers.get_mut("Set-Cookie") {
+            Some(RefOr::Object(header)) => {
+                let description = header.description.get_or_insert_with(String::new);
+                if !description.contains(&sets) {
+                    if !description.is_empty() {
+                        description.push(' ');
+                    }
+                    description.push_str(&sets);
+                }
+            }
+            Some(RefOr::Ref(_)) => {}
+            None => {
+                let header = Header {
+                    description: Some(sets),
+                    required: false,
+                    deprecated: false,
+                    allow_empty_value: false,
+                    value: ParameterValue::Schema {
+                        style: None,
+                        explode: None,
+                        allow_reserved: false,
+                        schema: string_schema(),
+                        example: None,
+                        examples: None,
+                    },
+                    extensions: Object::default(),
+                };
+                response.headers.insert("Set-Cookie".to_owned(), header.into());
+            }
+        }
+    }
+    Ok(())
+}
+
+fn string_schema() -> SchemaObject {
+    SchemaObject {
+        instance_type: Some(schemars::schema::InstanceType::String.into()),
+        ..SchemaObject::default()
+    }
+}
+
+/// Remove the fields that deserializing an [`OpenApi`] also copies to the extensions of the
+/// types flattening both, such as the status codes of [`Responses`] and the `schema` of
+/// parameters and headers. Documents read from files and merged fragments go through this
+/// before use, else these fields are rendered twice, the stale copy last.
+pub(crate) fn drop_misread_extensions(openapi: &mut OpenApi) {
+    fn parameters<'a>(parameters: impl IntoIterator<Item = &'a mut RefOr<Parameter>>) {
+        for parameter in parameters {
+            if let RefOr::Object(parameter) = parameter {
+                parameter.extensions.retain(|key, _| key.starts_with("x-"));
+            }
+        }
+    }
+    fn headers(headers: &mut Map<String, RefOr<Header>>) {
+        for header in headers.values_mut() {
+            if let RefOr::Object(header) = header {
+                header.extensions.retain(|key, _| key.starts_with("x-"));
+            }
+        }
+    }
+    fn response(response: &mut RefOr<Response>) {
+        if let RefOr::Object(response) = response {
+            headers(&mut response.headers);
+        }
+    }
+    fn path_item(item: &mut PathItem) {
+        parameters(&mut item.parameters);
+        let operations = [
+            &mut item.get,
+            &mut item.put,
+            &mut item.post,
+            &mut item.delete,
+            &mut item.options,
+            &mut item.head,
+            &mut item.patch,
+            &mut item.trace,
+        ];
+        for operation in operations.into_iter().flatten() {
+            parameters(&mut operation.parameters);
+            operation.responses.extensions.retain(|key, _| key.starts_with("x-"));
+            operation.responses.default.iter_mut().for_each(response);
+            operation.responses.responses.values_mut().for_each(response);
+            for callback in operation.callbacks.values_mut() {
+                if let RefOr::Object(callback) = callback {
+                    callback.callbacks.values_mut().for_each(path_item);
+                }
+            }
+        }
+    }
+    openapi.paths.values_mut().for_each(path_item);
+    if let Some(components) = &mut openapi.components {
+        components.responses.values_mut().for_each(response);
+        parameters(components.parameters.values_mut());
+        headers(&mut components.headers);
+    }
+}
+
+/// Read the OpenAPI document at `path`: YAML when it ends in `.yaml` or `.yml`, which needs the
+/// `yaml` feature, and JSON otherwise. Fails with a message that does not repeat `path`.
+pub(crate) fn load_openapi(path: &Path) -> std::result::Result<OpenApi, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let mut openapi: OpenApi = match path.extension().and_then(|e| e.to_str()) {
+        #[cfg(feature = "yaml")]
+        Some("yaml" | "yml") => serde_yaml::from_str(&content).map_err(|e| e.to_string())?,
+        #[cfg(not(feature = "yaml"))]
+        Some("yaml" | "yml") => return Err("YAML documents need the `yaml` feature".to_owned()),
+        _ => serde_json::from_str(&content).map_err(|e| e.to_string())?,
+    };
+    drop_misread_extensions(&mut openapi);
+    Ok(openapi)
+}
+
+/// Follow the local `$ref` of `value`, if any, inside `document`. Unresolvable references are
+/// returned as is.
+pub(crate) fn resolve_ref<'a>(document: &'a Value, value: &'a Value) -> &'a Value {
+    match value.get("$ref").and_then(Value::as_str) {
+        Some(reference) => reference
+            .strip_prefix('#')
+            .and_then(|pointer| document.pointer(pointer))
+            .unwrap_or(value),
+        None => value,
+    }
+}
+
+/// The lowercase `Content-Type` of `headers` without its parameters.
+pub(crate) fn essence(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.split(';').next().unwrap_or_default().trim().to_ascii_lowercase())
+}
+
+/// The media type documented for `content_type`, accepting `type/*` and `*/*` entries.
+pub(crate) fn media_type<'a>(
+    content: &'a serde_json::Map<String, Value>,
+    content_type: &str,
+) -> Option<&'a Value> {
+    let wildcard = format!("{}/*", content_type.split('/').next().unwrap_or_default());
+    content
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(content_type))
+        .or_else(|| content.iter().find(|(key, _)| **key == wildcard))
+        .or_else(|| content.iter().find(|(key, _)| *key == "*/*"))
+        .map(|(_, media)| media)
+}
+
+/// `application/json` and `+json` media types.
+pub(crate) fn is_json(content_type: &str) -> bool {
+    content_type == "application/json" || content_type.ends_with("+json")
+}
+
+/// A temporary directory of its own for each test, removed when dropped.
+#[cfg(all(test, any(feature = "cli", feature = "test-support")))]
+pub(crate) struct TempDir(std::path::PathBuf);
+
+#[cfg(all(test, any(feature = "cli", feature = "test-support")))]
+impl TempDir {
+    pub(crate) fn new() -> Self {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let name = format!(
+            "openapi-rs-{}-{}",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        );
+        let dir = std::env::temp_dir().join(name);
+        std::fs::create_dir_all(&dir).unwrap();
+        TempDir(dir)
+    }
+
+    /// The path of the file `name` in the directory.
+    pub(crate) fn path(&self, name: &str) -> std::path::PathBuf {
+        self.0.join(name)
+    }
+}
+
+#[cfg(all(test, any(feature = "cli", feature = "test-support")))]
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn responses(status: u16, description: &str) -> Responses {
+        let mut responses = Responses::default();
+        let response = ensure_not_ref(ensure_status_code_exists(&mut responses, status)).unwrap();
+        response.description = description.to_owned();
+        responses
+    }
+
+    fn description(responses: &Responses, status: &str) -> String {
+        match &responses.responses[status] {
+            RefOr::Object(response) => response.description.clone(),
+            RefOr::Ref(_) => panic!("unexpected reference"),
+        }
+    }
+
+    #[test]
+    fn descriptions_are_kept_whole() {
+        let merged = produce_any_responses(
+            responses(201, "Created; see the Location header"),
+            responses(201, "Created"),
+        )
+        .unwrap();
+        assert_eq!(
+            description(&merged, "201"),
+            "Created; see the Location header"
+        );
+    }
+
+    #[test]
+    fn merged_descriptions_are_not_split_again() {
+        let merged = produce_any_responses(responses(400, "Invalid id"), responses(400, "Bad Request"))
+            .unwrap();
+        let merged = produce_any_responses(responses(400, "Missing name"), merged).unwrap();
+        let merged = produce_any_responses(merged, responses(400, "Invalid id")).unwrap();
+        assert_eq!(description(&merged, "400"), "Missing name; Invalid id");
+    }
+}
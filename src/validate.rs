@@ -0,0 +1,533 @@
+//! Structural validation of a finished [`OpenApi`] document against the OpenAPI 3.0 rules that
+//! the generator cannot enforce while operations are added.
+
+use std::collections::HashSet;
+
+use okapi::openapi3::{OpenApi, Operation, Parameter, PathItem, RefOr, SecurityRequirement};
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::deprecation::REPLACED_BY_EXTENSION;
+use crate::parameter::path_template_variables;
+
+/// A structural error found in an [`OpenApi`] document.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ValidationError {
+    /// A `$ref` points at something that does not exist in the document.
+    #[error("{location}: `$ref` `{reference}` does not resolve")]
+    DanglingReference { location: String, reference: String },
+    /// A security requirement names a scheme missing from `components/securitySchemes`.
+    #[error("{location}: security scheme `{scheme}` is not declared")]
+    UndeclaredSecurityScheme { location: String, scheme: String },
+    /// A variable of the path template has no matching `in: path` parameter.
+    #[error("{location}: path variable `{name}` has no matching path parameter")]
+    MissingPathParameter { location: String, name: String },
+    /// An `in: path` parameter does not appear in the path template.
+    #[error("{location}: path parameter `{name}` is not part of the path template")]
+    UnknownPathParameter { location: String, name: String },
+    /// An `in: path` parameter is not `required: true`, which OpenAPI requires.
+    #[error("{location}: path parameter `{name}` must be required")]
+    OptionalPathParameter { location: String, name: String },
+    /// The same parameter (name and location) is declared more than once.
+    #[error("{location}: parameter `{name}` in {parameter_location} is declared more than once")]
+    DuplicateParameter {
+        location: String,
+        name: String,
+        parameter_location: String,
+    },
+    /// An operation documents no response at all.
+    #[error("{location}: operation has no responses")]
+    NoResponses { location: String },
+    /// A response has an empty `description`.
+    #[error("{location}: response `{status}` has no description")]
+    MissingResponseDescription { location: String, status: String },
+    /// A key of `responses` is neither an HTTP status code nor a range like `4XX`.
+    #[error("{location}: `{status}` is not a valid response status")]
+    InvalidStatusCode { location: String, status: String },
+    /// A deprecated operation is replaced by an `operationId` no operation has, see
+    /// [`REPLACED_BY_EXTENSION`].
+    #[error("{location}: replacing operation `{operation_id}` does not exist")]
+    UnknownReplacement {
+        location: String,
+        operation_id: String,
+    },
+}
+
+/// Validate `openapi` and return every structural error found. An empty list means the document
+/// is valid as far as these checks go.
+#[must_use]
+pub fn validate(openapi: &OpenApi) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+
+    if let Ok(document) = serde_json::to_value(openapi) {
+        check_references(&document, &document, &mut String::new(), &mut errors);
+    }
+
+    let schemes: HashSet<&String> = openapi
+        .components
+        .as_ref()
+        .map(|c| c.security_schemes.keys().collect())
+        .unwrap_or_default();
+    check_security(&openapi.security, &schemes, "#/security", &mut errors);
+
+    let operation_ids: HashSet<&str> = openapi
+        .paths
+        .values()
+        .flat_map(operations)
+        .filter_map(|(_, op)| op.operation_id.as_deref())
+        .collect();
+
+    for (path, item) in &openapi.paths {
+        for (method, operation) in operations(item) {
+            let location = format!("{} {}", method, path);
+            let replaced_by = operation
+                .extensions
+                .get(REPLACED_BY_EXTENSION)
+                .and_then(Value::as_str);
+            if let Some(id) = replaced_by.filter(|id| !operation_ids.contains(id)) {
+                errors.push(ValidationError::UnknownReplacement {
+                    location: location.clone(),
+                    operation_id: id.to_owned(),
+                });
+            }
+            check_security(
+                operation.security.as_deref().unwrap_or_default(),
+                &schemes,
+                &location,
+                &mut errors,
+            );
+            check_parameters(openapi, path, item, operation, &location, &mut errors);
+            check_responses(operation, &location, &mut errors);
+        }
+    }
+
+    errors
+}
+
+/// The operations of a path item with their method names.
+pub(crate) fn operations(item: &PathItem) -> Vec<(&'static str, &Operation)> {
+    [
+        ("GET", &item.get),
+        ("PUT", &item.put),
+        ("POST", &item.post),
+        ("DELETE", &item.delete),
+        ("OPTIONS", &item.options),
+        ("HEAD", &item.head),
+        ("PATCH", &item.patch),
+        ("TRACE", &item.trace),
+    ]
+    .into_iter()
+    .filter_map(|(method, op)| op.as_ref().map(|op| (method, op)))
+    .collect()
+}
+
+fn check_references(
+    document: &Value,
+    value: &Value,
+    pointer: &mut String,
+    errors: &mut Vec<ValidationError>,
+) {
+    match value {
+        Value::Object(map) => {
+            if let Some(Value::String(reference)) = map.get("$ref") {
+                let resolves = reference
+                    .strip_prefix('#')
+                    .is_none_or(|target| document.pointer(target).is_some());
+                if !resolves {
+                    errors.push(ValidationError::DanglingReference {
+                        location: format!("#{}", pointer),
+                        reference: reference.clone(),
+                    });
+                }
+            }
+            for (key, child) in map {
+                let len = pointer.len();
+                pointer.push('/');
+                pointer.push_str(&key.replace('~', "~0").replace('/', "~1"));
+                check_references(document, child, pointer, errors);
+                pointer.truncate(len);
+            }
+        }
+        Value::Array(items) => {
+            for (index, child) in items.iter().enumerate() {
+                let len = pointer.len();
+                pointer.push('/');
+                pointer.push_str(&index.to_string());
+                check_references(document, child, pointer, errors);
+                pointer.truncate(len);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn check_security(
+    requirements: &[SecurityRequirement],
+    schemes: &HashSet<&String>,
+    location: &str,
+    errors: &mut Vec<ValidationError>,
+) {
+    for requirement in requirements {
+        for scheme in requirement.keys() {
+            if !schemes.contains(scheme) {
+                errors.push(ValidationError::UndeclaredSecurityScheme {
+                    location: location.to_owned(),
+                    scheme: scheme.clone(),
+                });
+            }
+        }
+    }
+}
+
+/// Resolve a parameter, following a reference into `components/parameters`.
+fn resolve_parameter<'a>(
+    openapi: &'a OpenApi,
+    parameter: &'a RefOr<Parameter>,
+) -> Option<&'a Parameter> {
+    match parameter {
+        RefOr::Object(p) => Some(p),
+        RefOr::Ref(r) => {
+            let name = r.reference.strip_prefix("#/components/parameters/")?;
+            match openapi.components.as_ref()?.parameters.get(name)? {
+                RefOr::Object(p) => Some(p),
+                RefOr::Ref(_) => None,
+            }
+        }
+    }
+}
+
+fn check_parameters(
+    openapi: &OpenApi,
+    path: &str,
+    item: &PathItem,
+    operation: &Operation,
+    location: &str,
+    errors: &mut Vec<ValidationError>,
+) {
+    let mut seen = HashSet::new();
+    for parameter in operation
+        .parameters
+        .iter()
+        .filter_map(|p| resolve_parameter(openapi, p))
+    {
+        if !seen.insert((parameter.name.clone(), parameter.location.clone())) {
+            errors.push(ValidationError::DuplicateParameter {
+                location: location.to_owned(),
+                name: parameter.name.clone(),
+                parameter_location: parameter.location.clone(),
+            });
+        }
+    }
+
+    // Path-level parameters apply unless overridden by the operation.
+    let mut path_parameters = HashSet::new();
+    for parameter in operation
+        .parameters
+        .iter()
+        .chain(&item.parameters)
+        .filter_map(|p| resolve_parameter(openapi, p))
+        .filter(|p| p.location == "path")
+    {
+        if !path_parameters.insert(parameter.name.clone()) {
+            continue;
+        }
+        if !parameter.required {
+            errors.push(ValidationError::OptionalPathParameter {
+                location: location.to_owned(),
+                name: parameter.name.clone(),
+            });
+        }
+    }
+
+    let variables = path_template_variables(path);
+    for name in &variables {
+        if !path_parameters.contains(name) {
+            errors.push(ValidationError::MissingPathParameter {
+                location: location.to_owned(),
+                name: name.clone(),
+            });
+        }
+    }
+    let mut unknown: Vec<&String> = path_parameters
+        .iter()
+        .filter(|name| !variables.contains(name))
+        .collect();
+    unknown.sort();
+    for name in unknown {
+        errors.push(ValidationError::UnknownPathParameter {
+            location: location.to_owned(),
+            name: name.clone(),
+        });
+    }
+}
+
+fn check_responses(operation: &Operation, location: &str, errors: &mut Vec<ValidationError>) {
+    let responses = &operation.responses;
+    if responses.responses.is_empty() && responses.default.is_none() {
+        errors.push(ValidationError::NoResponses {
+            location: location.to_owned(),
+        });
+    }
+
+    let default = responses
+        .default
+        .as_ref()
+        .map(|r| ("default".to_owned(), r));
+    for (status, response) in responses
+        .responses
+        .iter()
+        .map(|(s, r)| (s.clone(), r))
+        .chain(default)
+    {
+        if status != "default" && !is_valid_status(&status) {
+            errors.push(ValidationError::InvalidStatusCode {
+                location: location.to_owned(),
+                status: status.clone(),
+            });
+        }
+        if let RefOr::Object(response) = response {
+            if response.description.trim().is_empty() {
+                errors.push(ValidationError::MissingResponseDescription {
+                    location: location.to_owned(),
+                    status,
+                });
+            }
+        }
+    }
+}
+
+/// `100`-`599`, or a range such as `2XX`.
+fn is_valid_status(status: &str) -> bool {
+    let bytes = status.as_bytes();
+    if bytes.len() != 3 || !(b'1'..=b'5').contains(&bytes[0]) {
+        return false;
+    }
+    bytes[1..].iter().all(u8::is_ascii_digit) || &status[1..] == "XX"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use okapi::openapi3::{
+        Components, Object, Ref, Response, Responses, SchemaObject, SecurityScheme,
+        SecuritySchemeData,
+    };
+
+    use crate::parameter::{ParameterBuilder, ParameterLocation};
+
+    fn operation(id: &str) -> Operation {
+        let mut responses = Responses::default();
+        responses.responses.insert(
+            "200".to_owned(),
+            RefOr::Object(Response {
+                description: "OK".to_owned(),
+                ..Response::default()
+            }),
+        );
+        Operation {
+            operation_id: Some(id.to_owned()),
+            responses,
+            ..Operation::default()
+        }
+    }
+
+    fn document(operations: Vec<(&str, Operation)>) -> OpenApi {
+        let mut openapi = OpenApi::default();
+        for (path, operation) in operations {
+            openapi.paths.insert(
+                path.to_owned(),
+                PathItem {
+                    get: Some(operation),
+                    ..PathItem::default()
+                },
+            );
+        }
+        openapi
+    }
+
+    fn parameter(name: &str, location: ParameterLocation) -> RefOr<Parameter> {
+        RefOr::Object(ParameterBuilder::new(name, location, SchemaObject::default()).build())
+    }
+
+    fn errors(path: &str, operation: Operation) -> Vec<ValidationError> {
+        validate(&document(vec![(path, operation)]))
+    }
+
+    #[test]
+    fn valid_operations_have_no_errors() {
+        let mut op = operation("get_user");
+        op.parameters.push(parameter("id", ParameterLocation::Path));
+        assert!(errors("/users/{id}", op).is_empty());
+    }
+
+    #[test]
+    fn references_must_resolve() {
+        let mut op = operation("get_user");
+        op.parameters.push(RefOr::Ref(Ref {
+            reference: "#/components/parameters/Missing".to_owned(),
+        }));
+        assert_eq!(
+            errors("/users", op),
+            [ValidationError::DanglingReference {
+                location: "#/paths/~1users/get/parameters/0".to_owned(),
+                reference: "#/components/parameters/Missing".to_owned(),
+            }]
+        );
+    }
+
+    #[test]
+    fn security_schemes_must_be_declared() {
+        let mut op = operation("get_user");
+        let mut requirement = SecurityRequirement::new();
+        requirement.insert("bearer".to_owned(), Vec::new());
+        op.security = Some(vec![requirement]);
+        let mut openapi = document(vec![("/users", op)]);
+        assert_eq!(
+            validate(&openapi),
+            [ValidationError::UndeclaredSecurityScheme {
+                location: "GET /users".to_owned(),
+                scheme: "bearer".to_owned(),
+            }]
+        );
+
+        let mut components = Components::default();
+        components.security_schemes.insert(
+            "bearer".to_owned(),
+            RefOr::Object(SecurityScheme {
+                description: None,
+                data: SecuritySchemeData::Http {
+                    scheme: "bearer".to_owned(),
+                    bearer_format: None,
+                },
+                extensions: Object::default(),
+            }),
+        );
+        openapi.components = Some(components);
+        assert!(validate(&openapi).is_empty());
+    }
+
+    #[test]
+    fn path_variables_and_parameters_must_match() {
+        let mut op = operation("get_user");
+        op.parameters.push(parameter("user_id", ParameterLocation::Path));
+        assert_eq!(
+            errors("/users/{id}", op),
+            [
+                ValidationError::MissingPathParameter {
+                    location: "GET /users/{id}".to_owned(),
+                    name: "id".to_owned(),
+                },
+                ValidationError::UnknownPathParameter {
+                    location: "GET /users/{id}".to_owned(),
+                    name: "user_id".to_owned(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn path_parameters_must_be_required() {
+        let mut op = operation("get_user");
+        let mut id = ParameterBuilder::new("id", ParameterLocation::Path, SchemaObject::default())
+            .build();
+        id.required = false;
+        op.parameters.push(RefOr::Object(id));
+        assert_eq!(
+            errors("/users/{id}", op),
+            [ValidationError::OptionalPathParameter {
+                location: "GET /users/{id}".to_owned(),
+                name: "id".to_owned(),
+            }]
+        );
+    }
+
+    #[test]
+    fn parameters_must_be_unique() {
+        let mut op = operation("list_users");
+        op.parameters.push(parameter("page", ParameterLocation::Query));
+        op.parameters.push(parameter("page", ParameterLocation::Query));
+        op.parameters.push(parameter("page", ParameterLocation::Header));
+        assert_eq!(
+            errors("/users", op),
+            [ValidationError::DuplicateParameter {
+                location: "GET /users".to_owned(),
+                name: "page".to_owned(),
+                parameter_location: "query".to_owned(),
+            }]
+        );
+    }
+
+    #[test]
+    fn operations_must_have_responses() {
+        let mut op = operation("list_users");
+        op.responses = Responses::default();
+        assert_eq!(
+            errors("/users", op),
+            [ValidationError::NoResponses {
+                location: "GET /users".to_owned(),
+            }]
+        );
+    }
+
+    #[test]
+    fn responses_must_be_described() {
+        let mut op = operation("list_users");
+        op.responses.responses.insert(
+            "404".to_owned(),
+            RefOr::Object(Response {
+                description: " ".to_owned(),
+                ..Response::default()
+            }),
+        );
+        assert_eq!(
+            errors("/users", op),
+            [ValidationError::MissingResponseDescription {
+                location: "GET /users".to_owned(),
+                status: "404".to_owned(),
+            }]
+        );
+    }
+
+    #[test]
+    fn response_keys_must_be_statuses() {
+        let mut op = operation("list_users");
+        for status in ["4XX", "600", "20", "x-rate-limit"] {
+            op.responses.responses.insert(
+                status.to_owned(),
+                RefOr::Object(Response {
+                    description: "Other".to_owned(),
+                    ..Response::default()
+                }),
+            );
+        }
+        let mut invalid: Vec<_> = errors("/users", op)
+            .into_iter()
+            .map(|error| match error {
+                ValidationError::InvalidStatusCode { status, .. } => status,
+                error => panic!("unexpected error {}", error),
+            })
+            .collect();
+        invalid.sort();
+        assert_eq!(invalid, ["20", "600", "x-rate-limit"]);
+    }
+
+    #[test]
+    fn replacing_operations_must_exist() {
+        let mut old = operation("list_users");
+        old.extensions
+            .insert(REPLACED_BY_EXTENSION.to_owned(), "list_users_v2".into());
+        assert_eq!(
+            validate(&document(vec![("/users", old.clone())])),
+            [ValidationError::UnknownReplacement {
+                location: "GET /users".to_owned(),
+                operation_id: "list_users_v2".to_owned(),
+            }]
+        );
+        assert!(validate(&document(vec![
+            ("/users", old),
+            ("/v2/users", operation("list_users_v2")),
+        ]))
+        .is_empty());
+    }
+}
@@ -0,0 +1,371 @@
+use axum::headers::UserAgent;
+use axum::extract::{FromRequest, RequestParts};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router, TypedHeader};
+use okapi::openapi3::{
+    OpenApi, RefOr, Responses, SecurityRequirement, SecurityScheme, SecuritySchemeData,
+};
+use openapi_rs::example::OpenApiExample;
+use openapi_rs::request::{OpenApiFromRequest, RequestHeaderInput};
+use openapi_rs::gen::OpenApiGenerator;
+use openapi_rs::response::OpenApiResponderInner;
+use openapi_rs::{handler, utils};
+use openapi_rs::settings::OpenApiSettings;
+use openapi_rs::{deprecation, render};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+#[handler(method = "GET")]
+async fn with_user_agent(TypedHeader(_agent): TypedHeader<UserAgent>) -> Json<u32> {
+    Json(1)
+}
+
+#[handler(method = "GET")]
+async fn with_optional_user_agent(_agent: Option<TypedHeader<UserAgent>>) -> Json<u32> {
+    Json(1)
+}
+
+#[handler(method = "GET")]
+async fn json_or_text() -> Result<Json<u32>, String> {
+    Ok(Json(1))
+}
+
+/// A body served from the cache.
+struct Cached(String);
+
+/// A body served while the cache is refreshed.
+struct Stale(String);
+
+impl IntoResponse for Cached {
+    fn into_response(self) -> Response {
+        self.0.into_response()
+    }
+}
+
+impl IntoResponse for Stale {
+    fn into_response(self) -> Response {
+        self.0.into_response()
+    }
+}
+
+impl OpenApiResponderInner for Cached {
+    fn responses(gen: &mut OpenApiGenerator) -> anyhow::Result<Responses> {
+        let mut responses = String::responses(gen)?;
+        utils::set_response_description(&mut responses, 200, "Served from the cache")?;
+        Ok(responses)
+    }
+}
+
+impl OpenApiResponderInner for Stale {
+    fn responses(gen: &mut OpenApiGenerator) -> anyhow::Result<Responses> {
+        let mut responses = String::responses(gen)?;
+        utils::set_response_description(&mut responses, 200, "Served stale")?;
+        Ok(responses)
+    }
+}
+
+#[handler(method = "GET")]
+async fn fresh_cached_or_stale() -> Result<Result<Json<u32>, Cached>, Stale> {
+    Ok(Ok(Json(1)))
+}
+
+/// Authenticates requests with a bearer token.
+struct BearerAuth;
+
+/// The author of a change, which is not a security scheme despite its name.
+struct AuthorName;
+
+#[axum::async_trait]
+impl<B: Send> FromRequest<B> for BearerAuth {
+    type Rejection = StatusCode;
+
+    async fn from_request(_: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
+        Ok(BearerAuth)
+    }
+}
+
+#[axum::async_trait]
+impl<B: Send> FromRequest<B> for AuthorName {
+    type Rejection = StatusCode;
+
+    async fn from_request(_: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
+        Ok(AuthorName)
+    }
+}
+
+impl<B: Send> OpenApiFromRequest<B> for BearerAuth {
+    fn from_request_input(
+        _gen: &mut OpenApiGenerator,
+        _name: String,
+        _required: bool,
+    ) -> anyhow::Result<RequestHeaderInput> {
+        let scheme = SecurityScheme {
+            description: None,
+            data: SecuritySchemeData::Http {
+                scheme: "bearer".to_owned(),
+                bearer_format: None,
+            },
+            extensions: Default::default(),
+        };
+        let mut requirement = SecurityRequirement::new();
+        requirement.insert("bearer".to_owned(), Vec::new());
+        Ok(RequestHeaderInput::Security("bearer".to_owned(), scheme, requirement))
+    }
+}
+
+#[handler(method = "POST", security = "BearerAuth")]
+async fn sign_change(_auth: BearerAuth, _author: AuthorName) -> Json<u32> {
+    Json(1)
+}
+
+#[handler(method = "GET")]
+async fn changes_by(_author: AuthorName) -> Json<u32> {
+    Json(1)
+}
+
+#[handler(method = "GET", deprecated_since = "2026-06-01", sunset = "2027-01-01")]
+async fn retired() -> Json<u32> {
+    Json(1)
+}
+
+#[derive(Serialize, JsonSchema)]
+struct Order {
+    id: u32,
+}
+
+impl OpenApiExample for Order {
+    fn example() -> Self {
+        Order { id: 7 }
+    }
+}
+
+#[handler(method = "POST", response_examples, response(status = 202, description = "Queued"))]
+async fn create_order() -> Json<Order> {
+    Json(Order::example())
+}
+
+#[handler(method = "POST", response_examples(status = 202))]
+async fn queue_order() -> Json<Order> {
+    Json(Order::example())
+}
+
+#[handler(method = "GET", response_examples)]
+async fn get_order() -> Json<Order> {
+    Json(Order::example())
+}
+
+#[handler(
+    method = "POST",
+    callback(name = "onOrder", expression = "{$request.body#/callbackUrl}", payload = "Order"),
+    callback(
+        name = "onOrder",
+        expression = "{$request.body#/callbackUrl}",
+        payload = "Order",
+        method = "DELETE",
+        description = "The order was cancelled",
+    ),
+    callback(name = "onAudit", expression = "{$request.query.auditUrl}", payload = "u32")
+)]
+async fn subscribe_to_orders() -> Json<u32> {
+    Json(1)
+}
+
+#[handler(
+    method = "POST",
+    link(
+        name = "GetOrder",
+        handler = "get_order",
+        description = "The order placed",
+        parameter(name = "id", expression = "$response.body#/id"),
+    )
+)]
+async fn place_order() -> Json<Order> {
+    Json(Order::example())
+}
+
+fn header_required(openapi: &OpenApi, path: &str) -> bool {
+    let operation = openapi.paths[path].get.as_ref().unwrap();
+    match &operation.parameters[0] {
+        RefOr::Object(parameter) => {
+            assert_eq!(parameter.name, "user-agent");
+            parameter.required
+        }
+        RefOr::Ref(_) => panic!("unexpected reference"),
+    }
+}
+
+#[test]
+fn typed_headers_are_required_unless_optional() {
+    let _app: Router = Router::new()
+        .route("/required", get(with_user_agent))
+        .route("/optional", get(with_optional_user_agent));
+    let mut gen = OpenApiGenerator::new(&OpenApiSettings::new());
+    with_user_agent_spec("/required", &mut gen).unwrap();
+    with_optional_user_agent_spec("/optional", &mut gen).unwrap();
+    let openapi = gen.into_openapi().unwrap();
+
+    assert!(header_required(&openapi, "/required"));
+    assert!(!header_required(&openapi, "/optional"));
+}
+
+#[test]
+fn merged_responses_keep_every_description_and_no_private_extension() {
+    let _app: Router = Router::new()
+        .route("/", get(json_or_text))
+        .route("/cached", get(fresh_cached_or_stale));
+    let mut gen = OpenApiGenerator::new(&OpenApiSettings::new());
+    json_or_text_spec("/", &mut gen).unwrap();
+    fresh_cached_or_stale_spec("/cached", &mut gen).unwrap();
+    let openapi = gen.into_openapi().unwrap();
+    let ok = |path: &str| match &openapi.paths[path].get.as_ref().unwrap().responses.responses["200"] {
+        RefOr::Object(response) => response.clone(),
+        RefOr::Ref(_) => panic!("unexpected reference"),
+    };
+
+    let response = ok("/");
+    assert_eq!(response.description, "OK");
+    assert_eq!(
+        response.content.keys().collect::<Vec<_>>(),
+        ["application/json", "text/plain"]
+    );
+    let response = ok("/cached");
+    assert_eq!(response.description, "Served from the cache; Served stale");
+    assert_eq!(response.content.len(), 2);
+    assert!(!render::to_json(&openapi).unwrap().contains("x-openapi-rs-descriptions"));
+}
+
+#[test]
+fn deprecation_date_marks_the_operation_deprecated() {
+    let _app: Router = Router::new().route("/retired", get(retired));
+    let mut gen = OpenApiGenerator::new(&OpenApiSettings::new());
+    retired_spec("/retired", &mut gen).unwrap();
+    let openapi = gen.into_openapi().unwrap();
+    let operation = openapi.paths["/retired"].get.as_ref().unwrap();
+
+    assert!(operation.deprecated);
+    assert_eq!(operation.extensions[deprecation::DEPRECATED_SINCE_EXTENSION], "2026-06-01");
+    assert_eq!(operation.extensions[deprecation::SUNSET_EXTENSION], "2027-01-01");
+}
+
+fn example(openapi: &OpenApi, path: &str, status: &str) -> Option<serde_json::Value> {
+    let item = &openapi.paths[path];
+    let operation = item.get.as_ref().or(item.post.as_ref()).unwrap();
+    match &operation.responses.responses[status] {
+        RefOr::Object(response) => response.content["application/json"].example.clone(),
+        RefOr::Ref(_) => panic!("unexpected reference"),
+    }
+}
+
+#[test]
+fn response_examples_go_to_the_first_successful_response() {
+    let _app: Router = Router::new()
+        .route("/orders", post(create_order))
+        .route("/orders/7", get(get_order));
+    let mut gen = OpenApiGenerator::new(&OpenApiSettings::new());
+    create_order_spec("/orders", &mut gen).unwrap();
+    get_order_spec("/orders/7", &mut gen).unwrap();
+    let openapi = gen.into_openapi().unwrap();
+
+    assert_eq!(example(&openapi, "/orders", "200"), Some(serde_json::json!({ "id": 7 })));
+    assert_eq!(example(&openapi, "/orders/7", "200"), Some(serde_json::json!({ "id": 7 })));
+}
+
+#[test]
+fn response_examples_need_content_at_the_declared_status() {
+    let _app: Router = Router::new().route("/queue", post(queue_order));
+    let mut gen = OpenApiGenerator::new(&OpenApiSettings::new());
+    assert!(queue_order_spec("/queue", &mut gen).is_err());
+}
+
+#[test]
+fn paths_follow_the_key_order() {
+    let _app: Router = Router::new()
+        .route("/b", get(json_or_text))
+        .route("/a", get(get_order));
+    let paths = |settings: &OpenApiSettings| {
+        let mut gen = OpenApiGenerator::new(settings);
+        json_or_text_spec("/b", &mut gen).unwrap();
+        get_order_spec("/a", &mut gen).unwrap();
+        gen.into_openapi().unwrap().paths.keys().cloned().collect::<Vec<_>>()
+    };
+
+    assert_eq!(paths(&OpenApiSettings::new()), ["/a", "/b"]);
+    #[cfg(feature = "preserve_order")]
+    {
+        let settings = OpenApiSettings {
+            key_order: openapi_rs::settings::KeyOrder::Registration,
+            ..OpenApiSettings::new()
+        };
+        assert_eq!(paths(&settings), ["/b", "/a"]);
+    }
+}
+
+#[test]
+fn security_comes_from_declared_extractors_only() {
+    let _app: Router = Router::new()
+        .route("/changes", post(sign_change))
+        .route("/changes/by", get(changes_by));
+    let mut gen = OpenApiGenerator::new(&OpenApiSettings::new());
+    sign_change_spec("/changes", &mut gen).unwrap();
+    changes_by_spec("/changes/by", &mut gen).unwrap();
+    let openapi = gen.into_openapi().unwrap();
+
+    let signed = openapi.paths["/changes"].post.as_ref().unwrap();
+    let requirements = signed.security.as_ref().unwrap();
+    assert_eq!(requirements.len(), 1);
+    assert!(requirements[0].contains_key("bearer"));
+    assert!(openapi.components.unwrap().security_schemes.contains_key("bearer"));
+    assert!(openapi.paths["/changes/by"].get.as_ref().unwrap().security.is_none());
+}
+
+#[test]
+fn links_target_the_operation_of_a_handler() {
+    let _app: Router = Router::new()
+        .route("/orders", post(place_order))
+        .route("/orders/:id", get(get_order));
+    let mut gen = OpenApiGenerator::new(&OpenApiSettings::new());
+    place_order_spec("/orders", &mut gen).unwrap();
+    get_order_spec("/orders/:id", &mut gen).unwrap();
+    let openapi = serde_json::to_value(gen.into_openapi().unwrap()).unwrap();
+
+    let get_order = &openapi["paths"]["/orders/{id}"]["get"];
+    let link = &openapi["paths"]["/orders"]["post"]["responses"]["200"]["links"]["GetOrder"];
+    assert_eq!(link["operationId"], get_order["operationId"]);
+    assert_eq!(link["parameters"]["id"], "$response.body#/id");
+    assert_eq!(link["description"], "The order placed");
+    assert!(!link.to_string().contains("x-openapi-rs"));
+}
+
+#[test]
+fn links_need_the_operation_of_their_handler() {
+    let _app: Router = Router::new().route("/orders", post(place_order));
+    let mut gen = OpenApiGenerator::new(&OpenApiSettings::new());
+    place_order_spec("/orders", &mut gen).unwrap();
+    let error = gen.into_openapi().unwrap_err().to_string();
+    assert!(error.starts_with("cannot resolve the target `handler::get_order` of link `GetOrder`"));
+}
+
+#[test]
+fn callbacks_send_their_payload_to_the_runtime_expression() {
+    let _app: Router = Router::new().route("/subscriptions", post(subscribe_to_orders));
+    let mut gen = OpenApiGenerator::new(&OpenApiSettings::new());
+    subscribe_to_orders_spec("/subscriptions", &mut gen).unwrap();
+    let openapi = serde_json::to_value(gen.into_openapi().unwrap()).unwrap();
+
+    let callbacks = &openapi["paths"]["/subscriptions"]["post"]["callbacks"];
+    let on_order = &callbacks["onOrder"]["{$request.body#/callbackUrl}"];
+    let body = |operation: &serde_json::Value| {
+        operation["requestBody"]["content"]["application/json"]["schema"].clone()
+    };
+    let order = serde_json::json!({ "$ref": "#/components/schemas/Order" });
+    assert_eq!(body(&on_order["post"]), order);
+    assert!(on_order["post"].get("description").is_none());
+    assert_eq!(body(&on_order["delete"]), body(&on_order["post"]));
+    assert_eq!(on_order["delete"]["description"], "The order was cancelled");
+    assert!(on_order["delete"]["responses"]["200"].is_object());
+
+    let on_audit = &callbacks["onAudit"]["{$request.query.auditUrl}"]["post"];
+    assert_eq!(body(on_audit)["type"], "integer");
+}
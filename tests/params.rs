@@ -0,0 +1,150 @@
+use axum::extract::{Path, Query};
+use axum::routing::get;
+use axum::{Json, Router};
+use openapi_rs::gen::OpenApiGenerator;
+use openapi_rs::request::{HeaderParams, PathParams, QueryParams};
+use openapi_rs::settings::OpenApiSettings;
+use openapi_rs::{handler, OpenApiHeaders, OpenApiPath, OpenApiQuery};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Serialize, Deserialize, JsonSchema, OpenApiQuery)]
+#[serde(rename_all = "camelCase")]
+struct Search {
+    /// Words to look for.
+    #[validate(length(min = 2, max = 64))]
+    search_text: String,
+    #[param(example = "20", deprecated)]
+    #[validate(range(min = 1, max = 100))]
+    page_size: Option<u32>,
+    #[serde(rename = "sort")]
+    #[param(style = "form", explode = false, pattern = "^[a-z_]+$")]
+    sort_by: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, OpenApiPath)]
+struct OrderPath {
+    #[param(description = "The account owning the order")]
+    account_id: u64,
+    #[validate(length(equal = 36))]
+    order_id: String,
+}
+
+#[derive(Serialize, JsonSchema, OpenApiHeaders)]
+#[serde(rename_all = "kebab-case")]
+struct ClientHeaders {
+    #[validate(email)]
+    x_contact: Option<String>,
+    #[validate(url)]
+    x_callback_url: String,
+}
+
+#[handler(method = "GET", headers = "ClientHeaders")]
+async fn search_orders(Path(_path): Path<OrderPath>, Query(_search): Query<Search>) -> Json<u32> {
+    Json(1)
+}
+
+/// The parameters in JSON, keyed by name.
+fn by_name(parameters: Vec<impl Serialize>) -> Value {
+    parameters
+        .into_iter()
+        .map(|parameter| {
+            let parameter = serde_json::to_value(parameter).unwrap();
+            (parameter["name"].as_str().unwrap().to_owned(), parameter)
+        })
+        .collect::<serde_json::Map<_, _>>()
+        .into()
+}
+
+#[test]
+fn query_parameters_follow_serde_names_and_attributes() {
+    let mut gen = OpenApiGenerator::new(&OpenApiSettings::new());
+    let parameters = by_name(Search::query_parameters(&mut gen).unwrap());
+
+    let search = &parameters["searchText"];
+    assert_eq!(search["in"], "query");
+    assert_eq!(search["required"], true);
+    assert_eq!(search["description"], "Words to look for.");
+    assert_eq!(search["schema"]["type"], "string");
+    assert_eq!(search["schema"]["minLength"], 2);
+    assert_eq!(search["schema"]["maxLength"], 64);
+
+    let page_size = &parameters["pageSize"];
+    assert_ne!(page_size["required"], true);
+    assert_eq!(page_size["deprecated"], true);
+    assert_eq!(page_size["example"], 20);
+    assert_eq!(page_size["schema"]["minimum"], 1.0);
+    assert_eq!(page_size["schema"]["maximum"], 100.0);
+
+    let sort = &parameters["sort"];
+    assert_eq!(sort["style"], "form");
+    assert_eq!(sort["explode"], false);
+    assert_eq!(sort["schema"]["pattern"], "^[a-z_]+$");
+    assert!(parameters.get("sortBy").is_none());
+}
+
+#[test]
+fn path_parameters_are_required() {
+    let mut gen = OpenApiGenerator::new(&OpenApiSettings::new());
+    let parameters =
+        by_name(OrderPath::path_parameters(&mut gen, "/{account_id}/{order_id}").unwrap());
+
+    let account = &parameters["account_id"];
+    assert_eq!(account["in"], "path");
+    assert_eq!(account["required"], true);
+    assert_eq!(account["description"], "The account owning the order");
+
+    let order = &parameters["order_id"];
+    assert_eq!(order["required"], true);
+    assert_eq!(order["schema"]["minLength"], 36);
+    assert_eq!(order["schema"]["maxLength"], 36);
+}
+
+#[test]
+fn header_parameters_carry_validator_formats() {
+    let mut gen = OpenApiGenerator::new(&OpenApiSettings::new());
+    let parameters = by_name(ClientHeaders::header_parameters(&mut gen).unwrap());
+
+    let contact = &parameters["x-contact"];
+    assert_eq!(contact["in"], "header");
+    assert_ne!(contact["required"], true);
+    assert_eq!(contact["schema"]["format"], "email");
+
+    let callback = &parameters["x-callback-url"];
+    assert_eq!(callback["required"], true);
+    assert_eq!(callback["schema"]["format"], "uri");
+}
+
+#[test]
+fn handlers_document_the_parameters_of_their_extractors() {
+    let _app: Router = Router::new().route("/:account_id/:order_id", get(search_orders));
+    let mut gen = OpenApiGenerator::new(&OpenApiSettings::new());
+    search_orders_spec("/:account_id/:order_id", &mut gen).unwrap();
+    let openapi = gen.into_openapi().unwrap();
+
+    let operation = openapi.paths["/{account_id}/{order_id}"]
+        .get
+        .as_ref()
+        .unwrap();
+    let parameters = by_name(operation.parameters.clone());
+    let mut names: Vec<_> = parameters
+        .as_object()
+        .unwrap()
+        .iter()
+        .map(|(name, parameter)| format!("{} {}", parameter["in"].as_str().unwrap(), name))
+        .collect();
+    names.sort();
+    assert_eq!(
+        names,
+        [
+            "header x-callback-url",
+            "header x-contact",
+            "path account_id",
+            "path order_id",
+            "query pageSize",
+            "query searchText",
+            "query sort",
+        ]
+    );
+}